@@ -1,14 +1,279 @@
+use chrono::format::{Item, StrftimeItems};
 use config::{Config, ConfigError, Environment, File};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
-use std::{env, fmt::Debug, path::PathBuf};
+use std::{
+    env,
+    fmt::Debug,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    input::input_error::InputError,
+    utils::{cereal::EncodingType, general::string_to_bool},
+};
+
+/// The prefix of the environment variables that override settings, `THINGSTODO_ENCODING`
+/// sets [`AppSettings::encoding`] for example.
+pub const ENV_PREFIX: &str = "THINGSTODO";
+
+/// The name, without extension, of the settings file in [`AppSettings::config_dir`]. Any
+/// format the `config` crate supports may be used.
+pub const SETTINGS_FILE: &str = "settings";
+
+/// The environment variable the passphrase is read from when [`AppSettings::encrypt`] is
+/// set, so that scripts do not have to answer a prompt.
+pub const PASSPHRASE_ENV: &str = "THINGSTODO_PASSPHRASE";
+
+/// The format dates are shown in unless [`AppSettings::date_format`] says otherwise.
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// The symbol complete todos are marked with unless [`AppSettings::complete_symbol`] says
+/// otherwise.
+pub const DEFAULT_COMPLETE_SYMBOL: &str = "[X]";
+
+/// The symbol incomplete todos are marked with unless [`AppSettings::incomplete_symbol`]
+/// says otherwise.
+pub const DEFAULT_INCOMPLETE_SYMBOL: &str = "[ ]";
+
+/// The number of backups kept of each profile unless [`AppSettings::backup_retention`]
+/// says otherwise.
+pub const DEFAULT_BACKUP_RETENTION: usize = 5;
+
+/// How long, in milliseconds, to wait for another instance to release the database unless
+/// [`AppSettings::lock_timeout_ms`] says otherwise.
+pub const DEFAULT_LOCK_TIMEOUT_MS: u64 = 5000;
+
+/// Returns `true` if `format` is a usable chrono format string, see
+/// [`chrono::format::strftime`].
+pub fn is_valid_date_format(format: &str) -> bool {
+    !format.is_empty() && StrftimeItems::new(format).all(|item| item != Item::Error)
+}
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
 pub struct AppSettings {
-    config_dir: Option<String>,
-    data_dir: Option<String>,
-    use_backup: bool,
-    use_service: bool,
+    pub config_dir: Option<String>,
+    /// The directory the database and its companion files live in. The `--data-dir` flag
+    /// overrides it for a single run, and it is otherwise taken from `THINGSTODO_DATA_DIR`,
+    /// then the settings file, then the platform's data directory.
+    pub data_dir: Option<String>,
+    pub use_backup: bool,
+    /// The number of backups kept of each profile, the oldest are deleted after each new
+    /// backup. `0` keeps every backup.
+    pub backup_retention: usize,
+    /// How long, in milliseconds, to wait for another instance to release its lock on the
+    /// database before giving up. `0` gives up straight away.
+    pub lock_timeout_ms: u64,
+    /// Whether the tags of each todo are lowercased and de-duplicated when the database is
+    /// loaded, see [`TodoList::normalize_tags`](crate::todos::todolist::TodoList::normalize_tags).
+    pub normalize_tags: bool,
+    /// Whether added todos are refused when they match an existing todo ignoring case, see [`TodoList::set_case_insensitive_duplicates`](crate::todos::todolist::TodoList::set_case_insensitive_duplicates).
+    pub case_insensitive_duplicates: bool,
+    /// Whether a row of counts is appended to the
+    /// [STATS_FILE](crate::todos::stats::STATS_FILE) after each save, see
+    /// [StatsReport](crate::todos::stats::StatsReport).
+    pub stats_log: bool,
+    /// Whether commands are handed to the resident Windows service, when it is running,
+    /// instead of being applied by the CLI itself. Only builds with the `service` feature
+    /// have the service.
+    pub use_service: bool,
+    /// The command, with its arguments separated by whitespace, to run when the program is
+    /// launched without any. If this is `None` or empty the REPL is launched instead.
+    pub default_command: Option<String>,
+    /// Whether a mistyped command should run the closest known command instead of failing,
+    /// see [`ActionType::closest`](crate::state::actions::action_type::ActionType::closest).
+    pub auto_correct: bool,
+    /// The name of the encoding to save the database with, see [`EncodingType::from_name`].
+    pub encoding: Option<String>,
+    /// The chrono format string dates such as due dates are shown in.
+    pub date_format: String,
+    /// The symbol complete todos are marked with in listings, such as `✔`. Must not be empty.
+    pub complete_symbol: String,
+    /// The symbol incomplete todos are marked with in listings, such as `✗`. Must not be empty.
+    pub incomplete_symbol: String,
+    /// Whether the database is encrypted with a passphrase, see
+    /// [Crypto](crate::utils::crypto::Crypto).
+    pub encrypt: bool,
+}
+
+impl AppSettings {
+    /// Loads the settings from the settings file in the default config directory, if there
+    /// is one, overridden by any `THINGSTODO_` environment variables.
+    pub fn load() -> Result<AppSettings, ConfigError> {
+        AppSettings::load_from(AppSettings::settings_file().as_deref())
+    }
+
+    /// Loads the settings from `file`, given without its extension, overridden by any
+    /// `THINGSTODO_` environment variables. Anything set in neither keeps its default.
+    pub fn load_from(file: Option<&Path>) -> Result<AppSettings, ConfigError> {
+        let mut config = Config::default();
+        if let Some(file) = file.and_then(|file| file.to_str()) {
+            config.merge(File::with_name(file).required(false))?;
+        }
+        config.merge(Environment::with_prefix(ENV_PREFIX))?;
+
+        let mut settings: AppSettings = config.try_into()?;
+        if !is_valid_date_format(&settings.date_format) {
+            eprintln!(
+                "Invalid date format {:?} in settings, using {:?} instead.",
+                settings.date_format, DEFAULT_DATE_FORMAT
+            );
+            settings.date_format = DEFAULT_DATE_FORMAT.to_string();
+        }
+        if settings.complete_symbol.trim().is_empty() {
+            eprintln!(
+                "Empty complete symbol in settings, using {:?} instead.",
+                DEFAULT_COMPLETE_SYMBOL
+            );
+            settings.complete_symbol = DEFAULT_COMPLETE_SYMBOL.to_string();
+        }
+        if settings.incomplete_symbol.trim().is_empty() {
+            eprintln!(
+                "Empty incomplete symbol in settings, using {:?} instead.",
+                DEFAULT_INCOMPLETE_SYMBOL
+            );
+            settings.incomplete_symbol = DEFAULT_INCOMPLETE_SYMBOL.to_string();
+        }
+
+        match &settings.encoding {
+            Some(name) if EncodingType::from_name(name).is_none() => {
+                Err(ConfigError::Message(format!("Unknown encoding {:?}", name)))
+            }
+            _ => Ok(settings),
+        }
+    }
+
+    /// Returns the settings file in the default config directory, without its extension,
+    /// if there is a config directory.
+    pub fn settings_file() -> Option<PathBuf> {
+        AppSettings::default()
+            .config_dir
+            .map(|dir| Path::new(&dir).join(SETTINGS_FILE))
+    }
+
+    /// Returns the names of the settings, in the order [`AppSettings::render`] lists them.
+    pub fn keys() -> Vec<String> {
+        match serde_json::to_value(AppSettings::default()) {
+            Ok(serde_json::Value::Object(map)) => map.keys().cloned().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Renders every setting as a `key = value` line, unset optional settings as `none`.
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        if let Ok(serde_json::Value::Object(map)) = serde_json::to_value(self) {
+            for (key, value) in map {
+                let value = match value {
+                    serde_json::Value::Null => "none".to_string(),
+                    serde_json::Value::String(value) => value,
+                    value => value.to_string(),
+                };
+                output.push_str(&format!("{} = {}\n", key, value));
+            }
+        }
+        output
+    }
+
+    /// Parses `value` for the setting `key`, as given to `todo settings set`. Booleans
+    /// accept the same words as [string_to_bool], numbers must be whole, and optional
+    /// settings are unset with `none` or `unset`. Fails if there is no setting `key` or
+    /// `value` is not valid for it.
+    pub fn parse_setting(key: &str, value: &str) -> Result<serde_json::Value, InputError> {
+        let invalid = |expected: &str| {
+            InputError::bad_arg_with(format!("Invalid value {:?} for {}, expected {}.", value, key, expected))
+        };
+
+        let parsed = match SettingKind::of(key) {
+            None => {
+                return Err(InputError::bad_arg_with(format!(
+                    "Unknown setting {:?}, expected one of {}.",
+                    key,
+                    AppSettings::keys().join(", ")
+                )))
+            }
+            Some(SettingKind::Bool) => {
+                serde_json::Value::Bool(string_to_bool(value).ok_or_else(|| invalid("true or false"))?)
+            }
+            Some(SettingKind::Number) => {
+                serde_json::Value::from(value.parse::<u64>().map_err(|_| invalid("a whole number"))?)
+            }
+            Some(SettingKind::OptionalText)
+                if value.eq_ignore_ascii_case("none") || value.eq_ignore_ascii_case("unset") =>
+            {
+                serde_json::Value::Null
+            }
+            Some(SettingKind::Text | SettingKind::OptionalText) => serde_json::Value::String(value.to_string()),
+        };
+
+        match (key, &parsed) {
+            ("date_format", serde_json::Value::String(format)) if !is_valid_date_format(format) => {
+                Err(invalid("a chrono format string"))
+            }
+            ("encoding", serde_json::Value::String(name)) if EncodingType::from_name(name).is_none() => {
+                Err(invalid("the name of an encoding"))
+            }
+            ("complete_symbol" | "incomplete_symbol", serde_json::Value::String(symbol)) if symbol.trim().is_empty() => {
+                Err(invalid("a symbol that is not empty"))
+            }
+            _ => Ok(parsed),
+        }
+    }
+
+    /// Sets `key` to `value` in the JSON settings file `{file}.json`, creating it if
+    /// needed and keeping the other settings in it, see [`AppSettings::parse_setting`].
+    /// Settings in a file of another format with the same name are left alone.
+    pub fn save_setting(file: &Path, key: &str, value: serde_json::Value) -> Result<(), ConfigError> {
+        let file = file.with_extension("json");
+        let mut map = match std::fs::read(&file) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|err| ConfigError::Foreign(Box::new(err)))?,
+            Err(_) => serde_json::Map::new(),
+        };
+        map.insert(key.to_string(), value);
+
+        if let Some(dir) = file.parent() {
+            std::fs::create_dir_all(dir).map_err(|err| ConfigError::Foreign(Box::new(err)))?;
+        }
+        let json = serde_json::to_vec_pretty(&map).map_err(|err| ConfigError::Foreign(Box::new(err)))?;
+        std::fs::write(&file, json).map_err(|err| ConfigError::Foreign(Box::new(err)))
+    }
+
+    /// Returns how long to wait for the lock on the database, see [`AppSettings::lock_timeout_ms`].
+    pub fn lock_timeout(&self) -> Duration {
+        Duration::from_millis(self.lock_timeout_ms)
+    }
+
+    /// Returns the configured [EncodingType], if any.
+    pub fn encoding(&self) -> Option<EncodingType> {
+        self.encoding.as_deref().and_then(EncodingType::from_name)
+    }
+}
+
+/// The type of value each setting in [AppSettings] holds, used to parse the values given
+/// to `todo settings set`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SettingKind {
+    Bool,
+    Number,
+    Text,
+    /// Text that may be unset, stored as `None`.
+    OptionalText,
+}
+
+impl SettingKind {
+    /// Returns the kind of the setting `key`, or `None` if there is no such setting.
+    fn of(key: &str) -> Option<SettingKind> {
+        match key {
+            "use_backup" | "normalize_tags" | "case_insensitive_duplicates" | "stats_log" | "use_service"
+            | "auto_correct" | "encrypt" => Some(SettingKind::Bool),
+            "backup_retention" | "lock_timeout_ms" => Some(SettingKind::Number),
+            "date_format" | "complete_symbol" | "incomplete_symbol" => Some(SettingKind::Text),
+            "config_dir" | "data_dir" | "default_command" | "encoding" => Some(SettingKind::OptionalText),
+            _ => None,
+        }
+    }
 }
 
 impl Default for AppSettings {
@@ -27,7 +292,115 @@ impl Default for AppSettings {
                 .and_then(|p| p.to_str())
                 .map(|st| st.to_owned()),
             use_backup: true,
+            backup_retention: DEFAULT_BACKUP_RETENTION,
+            lock_timeout_ms: DEFAULT_LOCK_TIMEOUT_MS,
+            normalize_tags: true,
+            case_insensitive_duplicates: false,
+            stats_log: false,
             use_service: false,
+            default_command: None,
+            auto_correct: false,
+            encoding: None,
+            date_format: DEFAULT_DATE_FORMAT.to_string(),
+            complete_symbol: DEFAULT_COMPLETE_SYMBOL.to_string(),
+            incomplete_symbol: DEFAULT_INCOMPLETE_SYMBOL.to_string(),
+            encrypt: false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{input::cli_options::CliOptions, utils::testing::temp_dir};
+
+    #[test]
+    fn encoding_resolves_flag_then_env_then_file() {
+        let dir = temp_dir("settings-encoding");
+        std::fs::write(dir.join("settings.json"), r#"{ "encoding": "ron" }"#).unwrap();
+        let file = dir.join(SETTINGS_FILE);
+
+        let from_file = AppSettings::load_from(Some(&file)).unwrap();
+        env::set_var("THINGSTODO_ENCODING", "cbor");
+        let from_env = AppSettings::load_from(Some(&file));
+        env::remove_var("THINGSTODO_ENCODING");
+        let from_env = from_env.unwrap();
+
+        assert_eq!(from_file.encoding(), Some(EncodingType::Ron));
+        assert_eq!(from_env.encoding(), Some(EncodingType::Cbor));
+        assert!(from_env.use_backup);
+
+        let (options, _) = CliOptions::parse(&["--encoding".to_string(), "json".to_string()]).unwrap();
+        assert_eq!(options.encoding(&from_env), Some(EncodingType::Json));
+        assert_eq!(CliOptions::default().encoding(&from_env), Some(EncodingType::Cbor));
+    }
+
+    #[test]
+    fn missing_file_falls_back_to_defaults() {
+        let dir = temp_dir("settings-missing");
+
+        let settings = AppSettings::load_from(Some(&dir.join(SETTINGS_FILE))).unwrap();
+
+        assert_eq!(settings.backup_retention, DEFAULT_BACKUP_RETENTION);
+        assert_eq!(settings.date_format, DEFAULT_DATE_FORMAT);
+        assert!(settings.use_backup);
+        assert!(settings.normalize_tags);
+    }
+
+    #[test]
+    fn settings_are_validated_and_saved() {
+        let dir = temp_dir("settings-set");
+        let file = dir.join(SETTINGS_FILE);
+
+        assert_eq!(AppSettings::parse_setting("use_backup", "no"), Ok(serde_json::Value::Bool(false)));
+        assert_eq!(AppSettings::parse_setting("backup_retention", "3"), Ok(serde_json::Value::from(3)));
+        assert_eq!(AppSettings::parse_setting("data_dir", "none"), Ok(serde_json::Value::Null));
+        assert_eq!(AppSettings::parse_setting("config_dir", "unset"), Ok(serde_json::Value::Null));
+        assert_eq!(AppSettings::parse_setting("default_command", "None"), Ok(serde_json::Value::Null));
+        assert_eq!(
+            AppSettings::parse_setting("data_dir", "/tmp/todos"),
+            Ok(serde_json::Value::String("/tmp/todos".to_string()))
+        );
+        for key in AppSettings::keys() {
+            assert!(SettingKind::of(&key).is_some(), "{} has no kind", key);
+        }
+        assert!(AppSettings::parse_setting("use_service", "maybe").is_err());
+        assert!(AppSettings::parse_setting("encoding", "xml").is_err());
+        assert!(matches!(
+            AppSettings::parse_setting("colour", "red"),
+            Err(InputError::InvalidArgument(_))
+        ));
+
+        AppSettings::save_setting(&file, "use_backup", serde_json::Value::Bool(false)).unwrap();
+        AppSettings::save_setting(&file, "backup_retention", serde_json::Value::from(3)).unwrap();
+        let settings = AppSettings::load_from(Some(&file)).unwrap();
+
+        assert!(!settings.use_backup);
+        assert_eq!(settings.backup_retention, 3);
+        assert!(settings.render().contains("use_backup = false\n"));
+    }
+
+    #[test]
+    fn invalid_date_format_falls_back_to_default() {
+        let dir = temp_dir("settings-date-format");
+        std::fs::write(dir.join("settings.json"), r#"{ "date_format": "%Y-%Q" }"#).unwrap();
+
+        let settings = AppSettings::load_from(Some(&dir.join(SETTINGS_FILE))).unwrap();
+
+        assert_eq!(settings.date_format, DEFAULT_DATE_FORMAT);
+        assert!(is_valid_date_format("%d/%m/%Y"));
+        assert!(!is_valid_date_format(""));
+    }
+
+    #[test]
+    fn empty_status_symbols_fall_back_to_default() {
+        let dir = temp_dir("settings-symbols");
+        std::fs::write(dir.join("settings.json"), r#"{ "complete_symbol": " ", "incomplete_symbol": "✗" }"#).unwrap();
+
+        let settings = AppSettings::load_from(Some(&dir.join(SETTINGS_FILE))).unwrap();
+
+        assert_eq!(settings.complete_symbol, DEFAULT_COMPLETE_SYMBOL);
+        assert_eq!(settings.incomplete_symbol, "✗");
+        assert!(AppSettings::parse_setting("complete_symbol", "").is_err());
+    }
+}
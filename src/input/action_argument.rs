@@ -46,11 +46,12 @@ impl ActionArgument {
         }
     }
 
-    // pub fn number<S: Into<String>>(name: S, order: usize) -> Self {
-    //     ActionArgument {
-    //         name: name.into(),
-    //         arg_type: ArgumentType::Number,
-    //         order,
-    //     }
-    // }
+    /// Convenience function to create an [ArgumentType::Number].
+    pub fn number<S: Into<String>>(name: S, order: usize) -> Self {
+        ActionArgument {
+            name: name.into(),
+            arg_type: ArgumentType::Number,
+            order,
+        }
+    }
 }
@@ -17,6 +17,8 @@ pub enum ArgumentType {
     /// Represents an existing item in the Todo Database. Useful for such commands as
     /// [crate::state::actions::ActionType::Remove]
     ExistingTodo,
+    /// Represents a whole, non-negative number input argument.
+    Number,
 }
 
 impl ArgumentType {
@@ -25,28 +27,11 @@ impl ArgumentType {
     pub fn validate<S: AsRef<str>>(&self, input: S) -> bool {
         // let value: String = input.into();
         match self {
-            ArgumentType::Boolean => {
-                if let Some(_) = string_to_bool(input) {
-                    true
-                } else {
-                    false
-                }
-            }
-            ArgumentType::String => {
-                if input.as_ref().is_empty() {
-                    false
-                } else {
-                    true
-                }
-            }
+            ArgumentType::Boolean => string_to_bool(input).is_some(),
+            ArgumentType::String => !input.as_ref().is_empty(),
+            ArgumentType::Number => input.as_ref().parse::<usize>().is_ok(),
             // TODO Fixing this is probably going to mean redesigning a lot of things.
-            ArgumentType::ExistingTodo => {
-                if input.as_ref().is_empty() {
-                    false
-                } else {
-                    true
-                }
-            }
+            ArgumentType::ExistingTodo => !input.as_ref().is_empty(),
         }
     }
 }
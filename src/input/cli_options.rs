@@ -0,0 +1,380 @@
+use std::{path::PathBuf, slice::Iter};
+
+use super::input_error::InputError;
+use crate::{config::settings::AppSettings, state::actions::action_type::ActionType, utils::cereal::EncodingType};
+
+/// What the program should do once its arguments have been parsed.
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Clone)]
+pub enum Dispatch {
+    /// Launch the interactive REPL.
+    Repl,
+    /// Run the command and arguments given.
+    Command(Vec<String>),
+}
+
+impl Dispatch {
+    /// Decides what to do with the `positional` arguments left over after parsing
+    /// [CliOptions]. When there are none the [`AppSettings::default_command`] is run, or
+    /// the REPL is launched if no default command is configured.
+    pub fn resolve(positional: Vec<String>, settings: &AppSettings) -> Dispatch {
+        if !positional.is_empty() {
+            return Dispatch::Command(positional);
+        }
+
+        let default_args: Vec<String> = settings
+            .default_command
+            .as_deref()
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(|arg| arg.to_string())
+            .collect();
+
+        if default_args.is_empty() {
+            Dispatch::Repl
+        } else {
+            Dispatch::Command(default_args)
+        }
+    }
+}
+
+/// Options given to the CLI as flags. Flags may appear anywhere in the arguments and take
+/// their value either as the next argument (`--output out.txt`) or inline
+/// (`--output=out.txt`). Everything after a bare `--` is treated as positional.
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Clone, Default)]
+pub struct CliOptions {
+    /// Write the output of list commands to this file instead of stdout.
+    pub output: Option<String>,
+    /// Overrides [`AppSettings::data_dir`] for this run, set by `--data-dir`.
+    pub data_dir: Option<String>,
+    /// Describe what the command would do without applying or saving anything.
+    pub dry_run: bool,
+    /// Overrides [`AppSettings::use_backup`] for this run, set by `--backup` and `--no-backup`.
+    pub backup: Option<bool>,
+    /// Answer yes to any confirmation prompt, for use in scripts.
+    pub assume_yes: bool,
+    /// Save the database with this encoding for this run, set by `--format` or `--encoding`.
+    pub format: Option<EncodingType>,
+    /// Show listings as a table of aligned columns, set by `--format table`.
+    pub table: bool,
+    /// Cut the text of each todo in listings down to this many characters, `0` for no
+    /// limit, set by `--max-width`. See [`TodoList::set_max_width`](crate::todos::todolist::TodoList::set_max_width).
+    pub max_width: Option<usize>,
+    /// Only list the todos that changed since the program last ran, set by
+    /// `--since-last-run`.
+    pub since_last_run: bool,
+    /// Follow the command with a prompt for related changes before saving, see
+    /// [FollowUp](crate::todos::follow_up::FollowUp). Set by `-i` or `--interactive`.
+    pub interactive: bool,
+    /// Print extra details, such as the size of the database once it is saved, set by
+    /// `--verbose`.
+    pub verbose: bool,
+    /// Print only how many todos a search matches, set by `-c` or `--count-only`.
+    pub count_only: bool,
+    /// Print the help text instead of running a command, set by `-h` or `--help`.
+    pub help: bool,
+    /// Print the version instead of running a command, set by `-v` or `--version`.
+    pub version: bool,
+}
+
+impl CliOptions {
+    /// Splits the raw CLI arguments (without the program name) into [CliOptions] and the
+    /// remaining positional arguments, the command followed by its arguments.
+    pub fn parse(args: &[String]) -> Result<(CliOptions, Vec<String>), InputError> {
+        let mut options = CliOptions::default();
+        let mut positional: Vec<String> = Vec::new();
+        let mut iter = args.iter();
+
+        while let Some(arg) = iter.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+
+            match flag {
+                "--" => {
+                    positional.extend(iter.cloned());
+                    break;
+                }
+                "-h" | "--help" => options.help = true,
+                "-v" | "--version" => options.version = true,
+                "-n" | "--dry-run" => options.dry_run = true,
+                "-y" | "--yes" => options.assume_yes = true,
+                "--since-last-run" => options.since_last_run = true,
+                "-c" | "--count-only" => options.count_only = true,
+                "--verbose" => options.verbose = true,
+                "-i" | "--interactive" => options.interactive = true,
+                "--backup" => options.backup = Some(true),
+                "--no-backup" => options.backup = Some(false),
+                "--data-dir" => options.data_dir = Some(CliOptions::value(flag, inline, &mut iter)?),
+                "-o" | "--output" => options.output = Some(CliOptions::value(flag, inline, &mut iter)?),
+                "--max-width" => {
+                    let value = CliOptions::value(flag, inline, &mut iter)?;
+                    options.max_width = Some(CliOptions::max_width(&value)?);
+                }
+                "--format" | "--encoding" => match CliOptions::value(flag, inline, &mut iter)? {
+                    value if flag == "--format" && value.eq_ignore_ascii_case("table") => options.table = true,
+                    value => options.format = Some(CliOptions::format(&value)?),
+                },
+                _ => positional.push(arg.clone()),
+            }
+        }
+
+        Ok((options, positional))
+    }
+
+    /// Returns whether the database should be backed up before saving, honouring the
+    /// `--backup` / `--no-backup` override over the configured `settings`.
+    pub fn use_backup(&self, settings: &AppSettings) -> bool {
+        self.backup.unwrap_or(settings.use_backup)
+    }
+
+    /// Returns the directory the database and its companion files live in. The
+    /// `--data-dir` flag takes precedence over [`AppSettings::data_dir`], which is read from
+    /// the environment, then the settings file, then the platform's data directory. If none
+    /// of those give a directory the working directory is used.
+    pub fn data_dir(&self, settings: &AppSettings) -> PathBuf {
+        self.data_dir
+            .as_ref()
+            .or(settings.data_dir.as_ref())
+            .map_or_else(|| PathBuf::from("."), PathBuf::from)
+    }
+
+    /// Returns whether the database should be saved after running `action`, which is when
+    /// it `changed` the list, or when the database is being converted with `--format`.
+    /// Queries are never saved, even if loading the list tidied it up.
+    pub fn should_save(&self, action: &ActionType, changed: bool) -> bool {
+        self.format.is_some() || (changed && action.is_mutating())
+    }
+
+    /// Returns the encoding to save the database with, honouring the `--format` override
+    /// over the configured `settings`. `None` keeps the encoding already on disk.
+    pub fn encoding(&self, settings: &AppSettings) -> Option<EncodingType> {
+        self.format.or_else(|| settings.encoding())
+    }
+
+    /// Parses the value of the `--format` flag into an [EncodingType].
+    fn format(value: &str) -> Result<EncodingType, InputError> {
+        EncodingType::from_name(value).ok_or_else(|| {
+            InputError::bad_arg_with(format!(
+                "Unknown format {:?}, expected one of {}.",
+                value,
+                EncodingType::all()
+                    .iter()
+                    .map(|ty| ty.get_file_ext())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        })
+    }
+
+    /// Parses the value of the `--max-width` flag.
+    fn max_width(value: &str) -> Result<usize, InputError> {
+        value.parse().map_err(|_| {
+            InputError::bad_arg_with(format!(
+                "Invalid max width {:?}, expected a number of characters or 0 for no limit.",
+                value
+            ))
+        })
+    }
+
+    /// Returns the value for `flag`, either given `inline` or as the next argument.
+    fn value(flag: &str, inline: Option<String>, iter: &mut Iter<String>) -> Result<String, InputError> {
+        match inline.or_else(|| iter.next().cloned()) {
+            Some(value) if !value.is_empty() => Ok(value),
+            _ => Err(InputError::bad_arg_with(format!(
+                "The {} flag must be given a value.",
+                flag
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        state::actions::action_type::ActionType,
+        todos::{profile::Profile, todolist::TodoList},
+        utils::{general::s, testing::temp_dir},
+    };
+
+    fn args(raw: &[&str]) -> Vec<String> {
+        raw.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_output_flag() {
+        let (options, positional) = CliOptions::parse(&args(&["ls", "--output", "out.txt"])).unwrap();
+        assert_eq!(options.output.as_deref(), Some("out.txt"));
+        assert_eq!(positional, args(&["ls"]));
+
+        let (options, positional) = CliOptions::parse(&args(&["--output=out.txt", "ls"])).unwrap();
+        assert_eq!(options.output.as_deref(), Some("out.txt"));
+        assert_eq!(positional, args(&["ls"]));
+    }
+
+    #[test]
+    fn data_dir_flag_overrides_settings() {
+        let settings = AppSettings {
+            data_dir: Some("configured".to_string()),
+            ..AppSettings::default()
+        };
+        let (options, positional) = CliOptions::parse(&args(&["--data-dir", "/tmp/ci", "ls"])).unwrap();
+        assert_eq!(options.data_dir(&settings), PathBuf::from("/tmp/ci"));
+        assert_eq!(positional, args(&["ls"]));
+
+        let (options, _) = CliOptions::parse(&args(&["ls"])).unwrap();
+        assert_eq!(options.data_dir(&settings), PathBuf::from("configured"));
+        let settings = AppSettings { data_dir: None, ..settings };
+        assert_eq!(options.data_dir(&settings), PathBuf::from("."));
+    }
+
+    #[test]
+    fn queries_are_never_saved() {
+        let options = CliOptions::default();
+        assert!(!options.should_save(&ActionType::List, true));
+        assert!(!options.should_save(&ActionType::Grep, false));
+        assert!(options.should_save(&ActionType::Add, true));
+        assert!(!options.should_save(&ActionType::Add, false));
+
+        let (options, _) = CliOptions::parse(&args(&["ls", "--format", "json"])).unwrap();
+        assert!(options.should_save(&ActionType::List, false));
+    }
+
+    #[test]
+    fn parses_dry_run_flag() {
+        let (options, positional) = CliOptions::parse(&args(&["--dry-run", "clear"])).unwrap();
+        assert!(options.dry_run);
+        assert_eq!(positional, args(&["clear"]));
+    }
+
+    #[test]
+    fn parses_help_and_version_flags() {
+        let (options, positional) = CliOptions::parse(&args(&["--help"])).unwrap();
+        assert!(options.help && !options.version);
+        assert!(positional.is_empty());
+
+        let (options, _) = CliOptions::parse(&args(&["-v"])).unwrap();
+        assert!(options.version && !options.help);
+    }
+
+    #[test]
+    fn parses_yes_flag() {
+        let (options, positional) = CliOptions::parse(&args(&["clear", "-y"])).unwrap();
+        assert!(options.assume_yes);
+        assert_eq!(positional, args(&["clear"]));
+    }
+
+    #[test]
+    fn format_table_is_a_view_not_an_encoding() {
+        let (options, positional) = CliOptions::parse(&args(&["ls", "--format=table"])).unwrap();
+        assert!(options.table);
+        assert_eq!(options.format, None);
+        assert_eq!(positional, args(&["ls"]));
+
+        assert!(CliOptions::parse(&args(&["ls", "--encoding", "table"])).is_err());
+    }
+
+    #[test]
+    fn parses_max_width_flag() {
+        let (options, positional) = CliOptions::parse(&args(&["ls", "--max-width", "20"])).unwrap();
+        assert_eq!(options.max_width, Some(20));
+        assert_eq!(positional, args(&["ls"]));
+
+        let (options, _) = CliOptions::parse(&args(&["--max-width=0", "ls"])).unwrap();
+        assert_eq!(options.max_width, Some(0));
+
+        assert!(CliOptions::parse(&args(&["ls", "--max-width", "wide"])).is_err());
+    }
+
+    #[test]
+    fn parses_format_flag() {
+        let (options, positional) = CliOptions::parse(&args(&["--format", "json", "ls"])).unwrap();
+        assert_eq!(options.format, Some(EncodingType::Json));
+        assert_eq!(positional, args(&["ls"]));
+
+        let (options, _) = CliOptions::parse(&args(&["ls", "--format=MsgPack"])).unwrap();
+        assert_eq!(options.format, Some(EncodingType::MsgPack));
+
+        assert!(matches!(
+            CliOptions::parse(&args(&["--format", "yaml", "ls"])),
+            Err(InputError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn missing_value_is_an_error() {
+        assert!(CliOptions::parse(&args(&["ls", "--output"])).is_err());
+    }
+
+    #[test]
+    fn double_dash_ends_flags() {
+        let (options, positional) = CliOptions::parse(&args(&["add", "--", "--output"])).unwrap();
+        assert_eq!(options, CliOptions::default());
+        assert_eq!(positional, args(&["add", "--output"]));
+    }
+
+    #[test]
+    fn backup_flags_override_settings() {
+        let settings = AppSettings::default();
+        assert!(settings.use_backup);
+
+        let (options, _) = CliOptions::parse(&args(&["ls"])).unwrap();
+        assert!(options.use_backup(&settings));
+
+        let (options, _) = CliOptions::parse(&args(&["--no-backup", "ls"])).unwrap();
+        assert!(!options.use_backup(&settings));
+
+        let settings = AppSettings {
+            use_backup: false,
+            ..AppSettings::default()
+        };
+        let (options, _) = CliOptions::parse(&args(&["--backup", "ls"])).unwrap();
+        assert!(options.use_backup(&settings));
+    }
+
+    #[test]
+    fn no_backup_flag_suppresses_backup() {
+        let dir = temp_dir("cli-no-backup");
+        let settings = AppSettings {
+            use_backup: true,
+            ..AppSettings::default()
+        };
+        let (options, _) = CliOptions::parse(&args(&["--no-backup", "add", "x"])).unwrap();
+
+        let list = TodoList::new();
+        list.save_to_profile(&dir, "work").unwrap();
+        list.save_to_profile_with(&dir, "work", options.use_backup(&settings)).unwrap();
+
+        assert!(!Profile::backup_dir_for(&dir, "work").exists());
+    }
+
+    #[test]
+    fn no_args_runs_default_command() {
+        let settings = AppSettings {
+            default_command: Some(s("ls")),
+            ..AppSettings::default()
+        };
+
+        match Dispatch::resolve(vec![], &settings) {
+            Dispatch::Command(args) => {
+                assert_eq!(ActionType::try_parse_cmd(&args[0]), Ok(ActionType::List));
+            }
+            Dispatch::Repl => panic!("Expected the default command to be dispatched"),
+        }
+    }
+
+    #[test]
+    fn no_args_without_default_launches_repl() {
+        let mut settings = AppSettings::default();
+        assert_eq!(Dispatch::resolve(vec![], &settings), Dispatch::Repl);
+
+        settings.default_command = Some(s("   "));
+        assert_eq!(Dispatch::resolve(vec![], &settings), Dispatch::Repl);
+
+        assert_eq!(
+            Dispatch::resolve(args(&["add", "x"]), &settings),
+            Dispatch::Command(args(&["add", "x"]))
+        );
+    }
+}
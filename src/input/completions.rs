@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+
+use super::argument_type::ArgumentType;
+use crate::state::actions::action_type::ActionType;
+
+/// The name of the binary the completion scripts complete.
+pub const BIN_NAME: &str = "todo";
+
+/// The shells that completion scripts can be generated for.
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Serialize, Deserialize, Clone, Copy)]
+pub enum Shell {
+    Bash,
+    Fish,
+    Zsh,
+}
+
+impl Shell {
+    pub fn all() -> Vec<Shell> {
+        vec![Shell::Bash, Shell::Fish, Shell::Zsh]
+    }
+
+    /// Returns the [Shell] with the given name, ignoring case, if any.
+    pub fn from_name<S: AsRef<str>>(name: S) -> Option<Shell> {
+        Shell::all()
+            .into_iter()
+            .find(|shell| shell.to_string().eq_ignore_ascii_case(name.as_ref()))
+    }
+}
+
+impl std::fmt::Display for Shell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Shell::Bash => write!(f, "bash"),
+            Shell::Fish => write!(f, "fish"),
+            Shell::Zsh => write!(f, "zsh"),
+        }
+    }
+}
+
+/// Stateless struct used to group the functions generating shell completion scripts. The
+/// scripts complete the command names from [`ActionType::all_actions`] and, for commands
+/// taking an existing todo, the todo texts printed by the hidden `__complete` command.
+pub struct Completions;
+
+impl Completions {
+    /// Returns the completion script for `shell`, meant to be redirected to a file and
+    /// sourced by the shell.
+    pub fn script(shell: Shell) -> String {
+        let commands = Completions::commands().join(" ");
+        let todo_commands = Completions::todo_commands();
+        match shell {
+            Shell::Bash => Completions::bash(&commands, &todo_commands.join("|")),
+            Shell::Fish => Completions::fish(&commands, &todo_commands.join(" ")),
+            Shell::Zsh => Completions::zsh(&commands, &todo_commands.join("|")),
+        }
+    }
+
+    /// Returns the commands of every visible action, see [`ActionType::visible_actions`].
+    pub fn commands() -> Vec<String> {
+        ActionType::visible_actions()
+            .iter()
+            .map(|action| action.get_input_string())
+            .collect()
+    }
+
+    /// Returns the commands of every action with an [`ArgumentType::ExistingTodo`]
+    /// argument, whose arguments are completed with todo texts.
+    pub fn todo_commands() -> Vec<String> {
+        ActionType::visible_actions()
+            .iter()
+            .filter(|action| {
+                action
+                    .get_arguments()
+                    .iter()
+                    .any(|arg| arg.arg_type == ArgumentType::ExistingTodo)
+            })
+            .map(|action| action.get_input_string())
+            .collect()
+    }
+
+    fn bash(commands: &str, todo_commands: &str) -> String {
+        format!(
+            r#"_{bin}() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    if [ "$COMP_CWORD" -eq 1 ]; then
+        COMPREPLY=($(compgen -W "{commands}" -- "$cur"))
+        return
+    fi
+    case "${{COMP_WORDS[1]}}" in
+        {todo_commands})
+            local IFS=$'\n'
+            COMPREPLY=($({bin} __complete "$cur" 2>/dev/null))
+            ;;
+    esac
+}}
+complete -F _{bin} {bin}
+"#,
+            bin = BIN_NAME,
+            commands = commands,
+            todo_commands = todo_commands
+        )
+    }
+
+    fn zsh(commands: &str, todo_commands: &str) -> String {
+        format!(
+            r#"#compdef {bin}
+
+_{bin}() {{
+    local -a commands todos
+    commands=({commands})
+    if (( CURRENT == 2 )); then
+        compadd -a commands
+        return
+    fi
+    case "$words[2]" in
+        {todo_commands})
+            todos=("${{(@f)$({bin} __complete "$PREFIX" 2>/dev/null)}}")
+            compadd -a todos
+            ;;
+    esac
+}}
+
+compdef _{bin} {bin}
+"#,
+            bin = BIN_NAME,
+            commands = commands,
+            todo_commands = todo_commands
+        )
+    }
+
+    fn fish(commands: &str, todo_commands: &str) -> String {
+        format!(
+            r#"complete -c {bin} -f
+complete -c {bin} -n "__fish_use_subcommand" -a "{commands}"
+complete -c {bin} -n "__fish_seen_subcommand_from {todo_commands}" -a "({bin} __complete (commandline -ct) 2>/dev/null)"
+"#,
+            bin = BIN_NAME,
+            commands = commands,
+            todo_commands = todo_commands
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts_list_visible_commands() {
+        for shell in Shell::all() {
+            let script = Completions::script(shell);
+            let words: Vec<&str> = script
+                .split(|c: char| c.is_whitespace() || c == '"' || c == '\'' || c == '(')
+                .collect();
+            for command in ["add", "addsub", "age", "patch"] {
+                assert!(words.contains(&command), "{}: {} missing from {}", shell, command, script);
+            }
+            assert!(script.contains("todo __complete"), "{}: {}", shell, script);
+            assert!(!script.contains("completions"), "{}: {}", shell, script);
+        }
+
+        assert!(Completions::todo_commands().contains(&"rm".to_string()));
+        assert!(!Completions::todo_commands().contains(&"add".to_string()));
+        assert!(!Completions::commands().contains(&"__complete".to_string()));
+        assert!(!Completions::commands().contains(&"completions".to_string()));
+    }
+
+    #[test]
+    fn shells_parse_by_name() {
+        assert_eq!(Shell::from_name("ZSH"), Some(Shell::Zsh));
+        assert_eq!(Shell::from_name("powershell"), None);
+    }
+}
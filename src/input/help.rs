@@ -0,0 +1,105 @@
+use super::input_error::InputError;
+use crate::state::actions::action_type::ActionType;
+
+/// The version of the program, from its manifest.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Stateless struct used to group the functions generating help text. Everything is built
+/// from the action metadata on [ActionType] so that the help never drifts from the
+/// commands it describes.
+pub struct Help;
+
+impl Help {
+    /// Returns the help text listing every visible command, see
+    /// [`ActionType::visible_actions`].
+    pub fn text() -> String {
+        let mut output = format!(
+            "todo {}\n\nUsage: todo [OPTIONS] <COMMAND> [ARGS]...\n\nCommands:\n",
+            VERSION
+        );
+        for action in ActionType::visible_actions() {
+            output.push_str(&format!("  {}\n", Help::usage(&action)));
+        }
+        output.push_str(concat!(
+            "\nOptions:\n",
+            "  -h, --help         Print this help\n",
+            "  -v, --version      Print the version\n",
+            "  -n, --dry-run      Describe what the command would do without doing it\n",
+            "  -y, --yes          Answer yes to any confirmation prompt\n",
+            "  -o, --output FILE  Write listings to FILE instead of stdout\n",
+            "  --format table     Show listings as a table of aligned columns\n",
+            "  --max-width N      Cut todos in listings down to N characters, 0 for no limit\n",
+            "  --data-dir DIR     Keep the database in DIR for this run only\n",
+            "  --format FORMAT    Save the database with FORMAT, also --encoding\n",
+            "  --backup           Back up the database before saving\n",
+            "  --no-backup        Do not back up the database before saving\n",
+            "  --since-last-run   Only list todos changed since the last run\n",
+            "  -c, --count-only   Print only the number of todos grep matches\n",
+            "  --verbose          Print extra details, such as the saved database size\n",
+            "  -i, --interactive  Offer related follow-ups, such as tagging, before saving\n",
+            "\nRun `todo help <COMMAND>` for the details of a command.\n",
+            "Run `todo settings` to show the settings and `todo settings set <KEY> <VALUE>` to change one.\n",
+        ));
+        output
+    }
+
+    /// Returns the detailed help for the command `cmd`: its usage line followed by each
+    /// of its arguments, in order, with their [ArgumentType](super::argument_type::ArgumentType).
+    /// Fails with [`InputError::cmd_unknown`] if there is no such command.
+    pub fn for_command(cmd: &str) -> Result<String, InputError> {
+        let action = ActionType::try_parse_cmd(cmd).map_err(|_| InputError::cmd_unknown(cmd))?;
+        let mut arguments = action.get_arguments();
+        arguments.sort_by_key(|arg| arg.order);
+
+        let mut output = format!("Usage: todo {}\n", Help::usage(&action));
+        if !arguments.is_empty() {
+            output.push_str("\nArguments:\n");
+            for arg in arguments {
+                let name = format!("<{}>", arg.name);
+                output.push_str(&format!("  {:<2} {:<24} {:?}\n", arg.order + 1, name, arg.arg_type));
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Returns the one line usage of `action`: its command, its arguments in order and
+    /// how many arguments it expects.
+    pub fn usage(action: &ActionType) -> String {
+        let mut arguments = action.get_arguments();
+        arguments.sort_by_key(|arg| arg.order);
+
+        let mut usage = format!("{:<14}", action.get_input_string());
+        for arg in arguments {
+            usage.push_str(&format!(" <{}>", arg.name));
+        }
+        usage.push_str(&format!(" ({})", action.get_arg_count()));
+        usage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn help_lists_every_command() {
+        let help = Help::text();
+        for action in ActionType::visible_actions() {
+            assert!(help.contains(&format!("  {} ", action.get_input_string())), "{}", help);
+        }
+        assert!(!help.contains("__complete"));
+        assert!(help.contains(VERSION));
+    }
+
+    #[test]
+    fn command_help_lists_arguments() {
+        let help = Help::for_command("due").unwrap();
+        assert!(help.starts_with("Usage: todo due"), "{}", help);
+        assert!(help.contains("<todo>"), "{}", help);
+        assert!(help.contains("ExistingTodo"), "{}", help);
+        assert!(help.contains("(2 arguments)"), "{}", help);
+
+        assert_eq!(Help::for_command("nope"), Err(InputError::cmd_unknown("nope")));
+    }
+}
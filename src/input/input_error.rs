@@ -25,6 +25,15 @@ impl InputError {
         InputError::bad_cmd_with(format!("Unknown command {:?}", input))
     }
 
+    /// Creates an [InputError::InvalidCommand] with an "Unknown Command"
+    /// message that suggests the command the user probably meant.
+    pub fn cmd_unknown_suggest(input: &str, suggestion: &str) -> InputError {
+        InputError::bad_cmd_with(format!(
+            "Unknown command {:?}, did you mean {:?}?",
+            input, suggestion
+        ))
+    }
+
     /// Creates an [InputError::InvalidArgument] with a message showing
     /// the `expected` arguments for the command vs the `received` arguments.
     pub fn bad_arg_count(received: usize, expected: usize) -> InputError {
@@ -79,7 +88,7 @@ impl InputError {
     }
 
     /// Converts this [InputError] into a String form for display.
-    pub fn to_string(&self) -> String {
+    fn message(&self) -> String {
         match self {
             InputError::InvalidCommand(msg) => {
                 if let Some(m) = msg {
@@ -101,6 +110,6 @@ impl InputError {
 
 impl std::fmt::Display for InputError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.to_string())
+        write!(f, "{}", self.message())
     }
 }
\ No newline at end of file
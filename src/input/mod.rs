@@ -1,4 +1,7 @@
 pub mod action_argument;
 pub mod argument_type;
+pub mod cli_options;
+pub mod completions;
+pub mod help;
 pub mod input_error;
 pub mod prompter;
\ No newline at end of file
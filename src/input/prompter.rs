@@ -1,6 +1,6 @@
 use std::io;
 
-use dialoguer::{theme::ColorfulTheme, Confirm, FuzzySelect, Input, Select};
+use dialoguer::{theme::ColorfulTheme, Confirm, FuzzySelect, Input, MultiSelect, Password, Select};
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 
@@ -64,6 +64,8 @@ pub type ResponseString = ResponseState<String>;
 pub type ResponseBool = ResponseState<bool>;
 /// Specialization of [ResponseState] for [usize] or index responses.
 pub type ResponseIndex = ResponseState<usize>;
+/// Specialization of [ResponseState] for responses made up of several indices.
+pub type ResponseIndices = ResponseState<Vec<usize>>;
 
 impl ResponseBool {
     fn to_response_string(&self) -> ResponseString {
@@ -99,6 +101,35 @@ impl Prompter {
         )
     }
 
+    /// A confirmation prompt which can be skipped for scripting. If `assume_yes` is `true`
+    /// the prompt is answered with `true` without being shown. Otherwise, if stdin is not
+    /// a terminal, an error is returned rather than blocking on input that will never come.
+    ///
+    /// ### Arguments
+    /// `text` - The text to display to the user when this prompt is executed.
+    /// `assume_yes` - Whether to answer the prompt with `true` without asking.
+    pub fn confirm_unless<S: AsRef<str>>(text: S, assume_yes: bool) -> ResponseBool {
+        Prompter::confirm_with(text, assume_yes, atty::is(atty::Stream::Stdin))
+    }
+
+    fn confirm_with<S: AsRef<str>>(text: S, assume_yes: bool, interactive: bool) -> ResponseBool {
+        if assume_yes {
+            return ResponseBool::value(true);
+        }
+
+        if !interactive {
+            return ResponseBool::error(io::Error::new(
+                io::ErrorKind::Other,
+                format!(
+                    "{:?} needs confirmation but stdin is not a terminal, pass --yes to confirm",
+                    text.as_ref()
+                ),
+            ));
+        }
+
+        Prompter::confirm(text)
+    }
+
     /// Prompt which accepts any [String] input from the user. The only validation
     /// that occurs is that the input string cannot be empty. This prompt cannot be cancelled
     /// so the only valid states returned from this function are [`ResponseState<String>::Value`]
@@ -110,6 +141,64 @@ impl Prompter {
         ResponseString::from_result(Input::with_theme(&*THEME).with_prompt(text.as_ref()).interact_text())
     }
 
+    /// Prompt which accepts any [String] input from the user, pre-filled with `default` which
+    /// is returned if the user just presses enter. If `default` is empty this is the same as
+    /// [`Prompter::input`], so the final input still cannot be empty.
+    ///
+    /// ### Arguments
+    /// `text` - The text to display to the user when this prompt is executed.
+    /// `default` - The value to offer the user, such as the current text of a todo.
+    pub fn input_with_default<S: AsRef<str>, D: AsRef<str>>(text: S, default: D) -> ResponseString {
+        if default.as_ref().is_empty() {
+            return Prompter::input(text);
+        }
+
+        ResponseString::from_result(
+            Input::with_theme(&*THEME)
+                .with_prompt(text.as_ref())
+                .default(default.as_ref().to_string())
+                .show_default(true)
+                .interact_text(),
+        )
+    }
+
+    /// Prompt which accepts hidden [String] input from the user, such as a passphrase. The
+    /// input cannot be empty and, like [`Prompter::input`], this prompt cannot be cancelled.
+    ///
+    /// ### Arguments
+    /// `text` - The text to display to the user when this prompt is executed.
+    pub fn password<S: AsRef<str>>(text: S) -> ResponseString {
+        ResponseString::from_result(Password::with_theme(&*THEME).with_prompt(text.as_ref()).interact())
+    }
+
+    /// Prompt which asks for hidden [String] input twice, returning an error if the two
+    /// entries do not match. Use this when choosing a new passphrase.
+    ///
+    /// ### Arguments
+    /// `text` - The text to display to the user when this prompt is executed.
+    pub fn password_confirmed<S: AsRef<str>>(text: S) -> ResponseString {
+        let first = match Prompter::password(&text) {
+            ResponseState::Value(first) => first,
+            other => return other,
+        };
+
+        match Prompter::password(format!("{} (again)", text.as_ref())) {
+            ResponseState::Value(second) => Prompter::matching(first, second),
+            other => other,
+        }
+    }
+
+    fn matching(first: String, second: String) -> ResponseString {
+        if first == second {
+            ResponseString::value(first)
+        } else {
+            ResponseString::error(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "The two passwords entered do not match",
+            ))
+        }
+    }
+
     /// Prompt which accepts [String] input from the user and validates that input against the
     /// given [`ValidatorFunc`]. Validator function should take a reference to the input [String]
     /// and return [Ok(())] if the input is acceptable, or an [Err(String)] describing the error.
@@ -134,12 +223,12 @@ impl Prompter {
     /// already existing todos.
     pub fn fuzzy_select<TPrompt: AsRef<str>, TChoice: std::fmt::Display>(
         text: TPrompt,
-        choices: &Vec<TChoice>,
+        choices: &[TChoice],
     ) -> ResponseIndex {
         ResponseIndex::from_result_opt(
             FuzzySelect::with_theme(&*THEME)
                 .with_prompt(text.as_ref())
-                .items(&choices)
+                .items(choices)
                 .interact_opt(),
         )
     }
@@ -149,12 +238,27 @@ impl Prompter {
     /// an index pointing to the chosen response in the given `choices` array.
     pub fn select<TPrompt: Into<String>, TChoice: std::fmt::Display>(
         text: TPrompt,
-        choices: &Vec<TChoice>,
+        choices: &[TChoice],
     ) -> ResponseIndex {
         ResponseIndex::from_result_opt(
             Select::with_theme(&*THEME)
                 .with_prompt(text)
-                .items(&choices)
+                .items(choices)
+                .interact_opt(),
+        )
+    }
+
+    /// A multi-select prompt which gives the user a list of options and allows them to
+    /// toggle any number of them with the space bar. Upon success this function returns
+    /// the indices of the chosen responses in the given `choices` array, which may be empty.
+    pub fn multi_select<TPrompt: Into<String>, TChoice: std::fmt::Display>(
+        text: TPrompt,
+        choices: &[TChoice],
+    ) -> ResponseIndices {
+        ResponseIndices::from_result_opt(
+            MultiSelect::with_theme(&*THEME)
+                .with_prompt(text)
+                .items(choices)
                 .interact_opt(),
         )
     }
@@ -169,13 +273,17 @@ impl Prompter {
 
         match aa.arg_type {
             ArgumentType::Boolean => {
-                match Prompter::fuzzy_select(format!("Select value for {:?} (bool)", aa.name), &*BOOLS) {
+                match Prompter::fuzzy_select(format!("Select value for {:?} (bool)", aa.name), &BOOLS) {
                     ResponseState::Value(idx) => ResponseString::Value((*BOOLS[idx]).to_string().to_lowercase()),
                     ResponseState::Cancelled => ResponseString::cancelled(),
                     ResponseState::Error(err) => ResponseString::error(err),
                 }
             },
             ArgumentType::String => Prompter::input(format!("Please enter value for {:?}", aa.name)),
+            ArgumentType::Number => Prompter::validated_input(
+                format!("Please enter value for {:?} (number)", aa.name),
+                |input: &String| input.parse::<usize>().map(|_| ()).map_err(|err| err.to_string()),
+            ),
             ArgumentType::ExistingTodo => match Prompter::fuzzy_select(format!("Please choose existing todo for {:?}", aa.name), existing) {
                 ResponseState::Value(idx) => ResponseString::value(existing[idx].clone()),
                 ResponseState::Cancelled => ResponseString::cancelled(),
@@ -184,3 +292,26 @@ impl Prompter {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn confirm_never_blocks_when_not_interactive() {
+        assert!(matches!(Prompter::confirm_with("Sure?", true, false), ResponseBool::Value(true)));
+        assert!(matches!(Prompter::confirm_with("Sure?", false, false), ResponseBool::Error(_)));
+    }
+
+    #[test]
+    fn mismatched_passwords_are_an_error() {
+        assert!(matches!(
+            Prompter::matching("hunter2".to_string(), "hunter2".to_string()),
+            ResponseString::Value(value) if value == "hunter2"
+        ));
+        match Prompter::matching("hunter2".to_string(), "hunter3".to_string()) {
+            ResponseString::Error(err) => assert!(err.to_string().contains("do not match")),
+            other => panic!("Expected an error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,12 @@
+#![allow(unused)]
+
+//! The core of the todo application, usable as a library independently of the CLI binary.
+
+pub mod config;
+pub mod input;
+pub mod service;
+pub mod state;
+pub mod todos;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod utils;
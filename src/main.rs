@@ -1,49 +1,199 @@
-#![allow(unused)]
-#![feature(path_try_exists)]
-
-use mimalloc::MiMalloc;
-
-mod config;
-mod input;
-// mod service;
-mod state;
-mod todos;
-mod utils;
-
-use crate::input::prompter::{Prompter, ResponseIndex, ResponseString};
-use crate::{
-    state::actions::action_type::ActionType,
-    todos::todolist::TodoList,
+use std::{path::Path, process::ExitCode};
+
+use chrono::{DateTime, Utc};
+
+use todo::config::settings::{AppSettings, PASSPHRASE_ENV};
+use todo::input::{
+    cli_options::{CliOptions, Dispatch},
+    help::{Help, VERSION},
+    prompter::{Prompter, ResponseBool, ResponseIndex, ResponseIndices, ResponseString},
 };
+use todo::{
+    state::actions::{action_outcome::ActionOutcome, action_payload::ActionPayload, action_type::ActionType},
+    todos::{
+        action_log::ActionLog,
+        cached_todolist::{CachedTodoList, DEFAULT_CACHE_TTL},
+        command_error::CommandError,
+        follow_up::FollowUp,
+        profile::{Profile, DEFAULT_PROFILE},
+        run_state::RunState,
+        stats::{StatsReport, STATS_FILE},
+        todolist::{payload_preview, TodoList},
+        triage::TriageChoice,
+    },
+    utils::{color::should_color, exit_code::ExitReason, fs::FileSystem, general::format_bytes, lock::DatabaseLock},
+};
+
+#[cfg(any(unix, all(windows, feature = "service")))]
+use todo::service::protocol::{is_servable, ServiceRequest, ServiceResponse};
+#[cfg(unix)]
+use todo::service::unix_service::{ServiceClient, UnixService};
+#[cfg(all(windows, feature = "service"))]
+use todo::service::win_service::{self, ServiceClient};
+#[cfg(feature = "tui")]
+use todo::tui::tui_view::TuiView;
+
+/// The hidden command the service manager launches the program with to run it as the
+/// resident service.
+#[cfg(all(windows, feature = "service"))]
+const SERVICE_COMMAND: &str = "__service";
 
 // #[global_allocator]
-// static GLOBAL: MiMalloc = MiMalloc;
+// static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
-fn main() {
-    if std::env::args().len() < 2 {
-        println!("No args passed, launching REPL");
-        let mut todo_list = TodoList::load_from_disk().expect("Unable to load Todo-List!");
-        println!("Loaded {} todos from disk.", todo_list.len());
-        repl(&mut todo_list);
-        if let Err(err) = todo_list.save_to_disk() {
-            println!("Error saving Todo-List database! {}", err);
+fn main() -> ExitCode {
+    let (options, positional) = match CliOptions::parse(&std::env::args().skip(1).collect::<Vec<String>>()) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            eprintln!("Error while parsing options!\n{}\n\n", err);
+            return ExitReason::from(&err).into();
         }
+    };
 
-        return;
+    if options.version {
+        println!("todo {}", VERSION);
+        return ExitReason::Success.into();
     }
 
-    let cmd_raw: String = std::env::args().nth(1).unwrap_or("".to_string());
-    let args_raw: Vec<String> = std::env::args().skip(2).collect::<Vec<String>>();
-    println!(
-        "Input Command = {:?}\nInput Args = {:?}\n",
-        cmd_raw, args_raw
-    );
+    if options.help {
+        print!("{}", Help::text());
+        return ExitReason::Success.into();
+    }
+
+    #[cfg(all(windows, feature = "service"))]
+    if positional.first().map(|cmd| cmd.as_str()) == Some(SERVICE_COMMAND) {
+        return match win_service::run() {
+            Ok(_) => ExitReason::Success.into(),
+            Err(err) => {
+                eprintln!("Unable to start the service! {}", err);
+                ExitReason::IoFailed.into()
+            }
+        };
+    }
+
+    let settings = AppSettings::load().unwrap_or_else(|err| {
+        eprintln!("Unable to load settings, using the defaults. {}", err);
+        AppSettings::default()
+    });
+    let dir = options.data_dir(&settings);
+    if let Err(err) = FileSystem::ensure_writable_dir(&dir) {
+        eprintln!("The data directory {:?} cannot be used! {}", dir, err);
+        return ExitReason::IoFailed.into();
+    }
+
+    let state_path = Profile::state_path_for(&dir, DEFAULT_PROFILE);
+    let mut state = RunState::load(&state_path);
+    let exit_code = run(&dir, options, positional, settings, state.last_opened);
+
+    state.last_opened = Some(Utc::now());
+    if let Err(err) = state.save(&state_path) {
+        eprintln!("Warning: unable to record this run: {}", err);
+    }
+
+    exit_code
+}
+
+/// Runs the command in `positional` against the database in the data directory `dir`,
+/// `last_opened` is when the program last finished running, if ever.
+fn run(
+    dir: &Path,
+    options: CliOptions,
+    positional: Vec<String>,
+    settings: AppSettings,
+    last_opened: Option<DateTime<Utc>>,
+) -> ExitCode {
+    let positional = match Dispatch::resolve(positional, &settings) {
+        Dispatch::Command(positional) => positional,
+        Dispatch::Repl => Vec::new(),
+    };
+
+    let passphrase = match passphrase(&settings) {
+        Ok(passphrase) => passphrase,
+        Err(err) => {
+            eprintln!("Unable to read the passphrase! {}", err);
+            return ExitReason::InvalidArgument.into();
+        }
+    };
+
+    if positional.is_empty() {
+        eprintln!("No args passed, launching REPL");
+        let mut todo_list = match load_cached(dir, &settings, &options, passphrase) {
+            Ok(list) => list,
+            Err(err) => {
+                eprintln!("Unable to load Todo-List! {}", err);
+                return ExitReason::IoFailed.into();
+            }
+        };
+        eprintln!("Loaded {} todos from disk.", todo_list.len().unwrap_or_default());
+        repl(&mut todo_list, dir);
+        if let Err(err) = todo_list.flush() {
+            eprintln!("Error saving Todo-List database! {}", err);
+            return ExitReason::IoFailed.into();
+        }
+
+        return ExitReason::Success.into();
+    }
+
+    let cmd_raw: String = positional[0].clone();
+    let args_raw: Vec<String> = positional[1..].to_vec();
 
-    let parse_result = ActionType::try_parse_cmd(&cmd_raw);
+    #[cfg(feature = "tui")]
+    if cmd_raw == "tui" {
+        let mut todo_list = match load_cached(dir, &settings, &options, passphrase) {
+            Ok(list) => list,
+            Err(err) => {
+                eprintln!("Unable to load Todo-List! {}", err);
+                return ExitReason::IoFailed.into();
+            }
+        };
+        if let Err(err) = TuiView::run(&mut todo_list) {
+            eprintln!("The TUI failed! {}", err);
+        }
+        return match todo_list.flush() {
+            Ok(_) => ExitReason::Success.into(),
+            Err(err) => {
+                eprintln!("Error saving Todo-List database! {}", err);
+                ExitReason::IoFailed.into()
+            }
+        };
+    }
+
+    if cmd_raw == "help" {
+        let help = match args_raw.first() {
+            Some(cmd) => Help::for_command(cmd),
+            None => Ok(Help::text()),
+        };
+        return match help {
+            Ok(help) => {
+                print!("{}", help);
+                ExitReason::Success.into()
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                ExitReason::from(&err).into()
+            }
+        };
+    }
+
+    if cmd_raw == "settings" {
+        return run_settings(&settings, &args_raw);
+    }
+
+    let parse_result = ActionType::try_parse_cmd(&cmd_raw).or_else(|err| match ActionType::closest(&cmd_raw) {
+        Some((corrected, _)) if settings.auto_correct => {
+            eprintln!(
+                "Unknown command {:?}, running {:?} instead.",
+                cmd_raw,
+                corrected.get_input_string()
+            );
+            Ok(corrected)
+        }
+        _ => Err(err),
+    });
 
     if let Err(err) = &parse_result {
-        println!("Error while parsing command!\n{}\n\n", err.to_string());
-        return;
+        eprintln!("Error while parsing command!\n{}\n\n", err);
+        return ExitReason::from(err).into();
     }
 
     let action = parse_result.unwrap();
@@ -51,31 +201,194 @@ fn main() {
     let payload = match action.try_create_payload(&args_raw) {
         Ok(act) => act,
         Err(err) => {
-            println!("Error while validating action!\n{}\n\n", err.to_string());
-            return;
+            eprintln!("Error while validating action!\n{}\n\n", err);
+            return ExitReason::from(&err).into();
         }
     };
 
-    let mut todo_list = TodoList::load_from_disk().map_or_else(|_| TodoList::new(), |tl| tl);
-    println!("Loaded {} todos from disk.", todo_list.len());
-    println!(
-        "Loaded Todo-List containing {:?} {}.",
-        todo_list.len(),
-        if todo_list.len() == 1 {
-            "entry"
-        } else {
-            "entries"
+    let payload = match (payload, last_opened) {
+        (ActionPayload::List, Some(since)) if options.since_last_run => ActionPayload::ListSince(since),
+        (ActionPayload::List, None) if options.since_last_run => {
+            eprintln!("There is no record of a previous run, listing everything.");
+            ActionPayload::List
         }
-    );
+        (_, _) if options.since_last_run => {
+            eprintln!("The --since-last-run flag can only be used with the list command.");
+            return ExitReason::InvalidArgument.into();
+        }
+        (payload, _) => payload,
+    };
+
+    if options.dry_run && action.is_profile_action() {
+        eprintln!("Dry runs are not supported for profile commands.");
+        return ExitReason::InvalidArgument.into();
+    }
+
+    if action.is_profile_action() {
+        let outcome = Profile::apply_action(dir, DEFAULT_PROFILE, payload.clone(), options.assume_yes)
+            .and_then(|outcome| {
+                confirm_outcome(outcome, options.assume_yes, || {
+                    Profile::apply_action(dir, DEFAULT_PROFILE, payload, true)
+                })
+            });
+        return match outcome {
+            Ok(outcome) => {
+                report(&outcome);
+                ExitReason::Success.into()
+            }
+            Err(err) => {
+                eprintln!("There was an error applying profile command: {}", err);
+                ExitReason::from(&err).into()
+            }
+        };
+    }
+
+    if action == ActionType::Triage && !options.dry_run {
+        let mut todo_list = match load_cached(dir, &settings, &options, passphrase) {
+            Ok(list) => list,
+            Err(err) => {
+                eprintln!("Unable to load Todo-List! {}", err);
+                return ExitReason::IoFailed.into();
+            }
+        };
+        triage_interactively(&mut todo_list);
+        return ExitReason::Success.into();
+    }
+
+    if action == ActionType::Serve && !options.dry_run {
+        return serve(dir, passphrase, &settings, &options);
+    }
+
+    #[cfg(any(unix, all(windows, feature = "service")))]
+    if settings.use_service {
+        if let Some(exit_code) = forward_to_service(dir, &payload, &options) {
+            return exit_code;
+        }
+    }
+
+    // Hold the lock from loading to saving so another instance cannot save in between and
+    // have its changes clobbered.
+    let _lock = match DatabaseLock::acquire(Profile::lock_path_for(dir, DEFAULT_PROFILE), settings.lock_timeout()) {
+        Ok(lock) => lock,
+        Err(err) => {
+            eprintln!("Unable to lock Todo-List! {}", err);
+            return ExitReason::from(&err).into();
+        }
+    };
+
+    let mut todo_list = match TodoList::load_from_disk_with(dir, passphrase.as_deref()) {
+        Ok(list) => list,
+        // Starting from an empty list when there is a database would throw its todos away
+        // on the next save, for example when the passphrase is wrong.
+        Err(err) if Profile::exists(dir, DEFAULT_PROFILE) => {
+            eprintln!("Unable to load Todo-List! {}", err);
+            return ExitReason::IoFailed.into();
+        }
+        Err(_) => {
+            let mut list = TodoList::new();
+            list.set_passphrase(passphrase);
+            list
+        }
+    };
+    todo_list.set_assume_yes(options.assume_yes);
+    todo_list.set_date_format(&settings.date_format);
+    todo_list.set_status_symbols(&settings.complete_symbol, &settings.incomplete_symbol);
+    todo_list.set_case_insensitive_duplicates(settings.case_insensitive_duplicates);
+    todo_list.set_table_view(options.table);
+    todo_list.set_max_width(options.max_width.unwrap_or_default());
+    todo_list.set_colorize(should_color());
+    // Kept to show what a verbose save changes.
+    let saved = if options.verbose { Some(todo_list.clone()) } else { None };
+    let merged_tags = if settings.normalize_tags { todo_list.normalize_tags() } else { 0 };
+    if merged_tags > 0 {
+        eprintln!("Merged {} duplicate tags.", merged_tags);
+    }
+
+    if options.dry_run {
+        return match todo_list.apply_action_dry(&payload) {
+            Ok(description) => {
+                println!("{}", description);
+                ExitReason::Success.into()
+            }
+            Err(err) => {
+                eprintln!("Dry run failed: {}", err);
+                ExitReason::from(&err).into()
+            }
+        };
+    }
+
+    // The count answers through the exit code too, like `grep -c`, so no matches is a no.
+    if options.count_only {
+        return match todo_list.count_matches(&payload) {
+            Ok(count) => {
+                println!("{}", count);
+                if count == 0 {
+                    ExitReason::CheckFailed.into()
+                } else {
+                    ExitReason::Success.into()
+                }
+            }
+            Err(err) => {
+                eprintln!("Unable to count matches: {}", err);
+                ExitReason::from(&err).into()
+            }
+        };
+    }
+
+    if let Some(output) = &options.output {
+        return match todo_list.save_listing(&payload, output) {
+            Ok(_) => {
+                eprintln!("Wrote listing to {:?}.", output);
+                ExitReason::Success.into()
+            }
+            Err(err) => {
+                eprintln!("There was an error writing the listing: {}", err);
+                ExitReason::from(&err).into()
+            }
+        };
+    }
+
+    let logged = payload.clone();
+    let outcome = match &payload {
+        // Large JSON Lines files take a while, so show how far the import has got.
+        ActionPayload::Import(file) => todo_list.validate_action(&payload).and_then(|_| {
+            todo_list
+                .import_file_with(file, |count| eprintln!("Read {} lines...", count))
+                .map(ActionOutcome::Imported)
+        }),
+        _ => todo_list.apply_action(payload.clone()).and_then(|outcome| {
+            confirm_outcome(outcome, options.assume_yes, || todo_list.apply_confirmed(payload))
+        }),
+    };
+    let outcome = match outcome {
+        // Checks answer through the exit code alone so they can be used in scripts.
+        Ok(ActionOutcome::IsEmpty(true)) => return ExitReason::Success.into(),
+        Ok(ActionOutcome::IsEmpty(false)) => return ExitReason::CheckFailed.into(),
+        Ok(outcome) => {
+            report(&outcome);
+            outcome
+        }
+        Err(err) => {
+            eprintln!(
+                "There was an error applying command to the Todo-List: {:?}",
+                err.to_string()
+            );
+            return ExitReason::from(&err).into();
+        }
+    };
 
-    if let Err(err) = todo_list.apply_action(payload) {
-        println!(
-            "There was an error applying command to the Todo-List: {:?}",
-            err.to_string()
-        );
+    let mut applied = vec![(logged, outcome)];
+    if options.interactive {
+        let offered = FollowUp::offered_after(&applied[0].1);
+        applied.extend(follow_up(&mut todo_list, offered));
     }
 
-    println!(
+    let changed = merged_tags > 0 || applied.iter().any(|(_, outcome)| outcome.is_change());
+    if !options.should_save(&action, changed) {
+        return ExitReason::Success.into();
+    }
+
+    eprintln!(
         "Todo-List contains {:?} {}",
         todo_list.len(),
         if todo_list.len() == 1 {
@@ -84,48 +397,94 @@ fn main() {
             "entries"
         }
     );
-    println!("Writing Todo-List...");
+    eprintln!("Writing Todo-List...");
 
-    match todo_list.save_to_disk() {
-        Ok(_) => println!("Success!"),
-        Err(e) => println!("An error has occurred! {:#?}", e),
+    match todo_list.save_to_disk(dir, options.use_backup(&settings), options.encoding(&settings)) {
+        Ok(size) => {
+            eprintln!("Success!");
+            if options.verbose {
+                eprintln!("Saved {} ({} bytes).", format_bytes(size), size);
+            }
+            if let Some(saved) = &saved {
+                eprint!("Changes saved:\n{}", saved.diff_with(&todo_list).render_changes(should_color()));
+            }
+            if settings.stats_log {
+                if let Err(err) = StatsReport::of(&todo_list, Utc::now()).append(dir.join(STATS_FILE)) {
+                    eprintln!("Warning: the stats could not be recorded: {}", err);
+                }
+            }
+            if let Err(err) = Profile::prune_backups(dir, DEFAULT_PROFILE, settings.backup_retention) {
+                eprintln!("Warning: old backups could not be pruned: {}", err);
+            }
+            for (logged, outcome) in &applied {
+                if let Err(err) = ActionLog::record(Profile::log_path_for(dir, DEFAULT_PROFILE), logged, outcome) {
+                    eprintln!("Warning: the action could not be recorded in the action log: {}", err);
+                }
+            }
+            ExitReason::Success.into()
+        }
+        Err(e) => {
+            eprintln!("An error has occurred! {:#?}", e);
+            ExitReason::IoFailed.into()
+        }
     }
 }
 
-fn repl(todo_list: &mut TodoList) {
-    let actions: Vec<ActionType> = ActionType::all_actions();
-    let mut choices: Vec<String> = ActionType::all_action_names();
+fn repl(todo_list: &mut CachedTodoList, dir: &Path) {
+    let actions: Vec<ActionType> = ActionType::all_actions()
+        .into_iter()
+        .filter(|action| !action.is_hidden())
+        .collect();
+    let mut choices: Vec<String> = actions.iter().map(|action| action.get_action_name()).collect();
     choices.push("Exit".to_string());
     let exit_number = choices.len() - 1;
 
-    println!("Actions = {:#?}", actions);
-    println!("Choices = {:#?}", choices);
-    println!("Exit Number = {:#?}", exit_number);
-
     loop {
         match Prompter::select("Please choose an option", &choices) {
             ResponseIndex::Value(i) => {
-                println!("i = {}", i);
-
                 if i == exit_number {
                     break;
                 }
 
+                if actions[i] == ActionType::Complete {
+                    complete_interactively(todo_list);
+                    continue;
+                }
+
+                if actions[i] == ActionType::Remove {
+                    remove_interactively(todo_list);
+                    continue;
+                }
+
+                if actions[i] == ActionType::Triage {
+                    triage_interactively(todo_list);
+                    continue;
+                }
+
                 let action_args = actions[i].get_arguments();
                 let mut args: Vec<String> = vec![];
+                let todos_text = todo_list.get_todos_text().unwrap_or_default();
+                let existing: Vec<&String> = todos_text.iter().collect();
                 for at in &action_args {
                     loop {
-                        match Prompter::for_argument(at, &todo_list.get_todos_text()) {
+                        // The new text of an edit usually resembles the old, so start from it.
+                        let response = match (actions[i], args.first()) {
+                            (ActionType::Edit, Some(old)) => {
+                                Prompter::input_with_default(format!("Please enter value for {:?}", at.name), old)
+                            }
+                            _ => Prompter::for_argument(at, &existing),
+                        };
+                        match response {
                             ResponseString::Value(s) => {
                                 args.push(s.clone());
                                 break;
                             },
                             ResponseString::Cancelled => {
-                                println!("Argument prompt cancelled.");
+                                eprintln!("Argument prompt cancelled.");
                                 continue;
                             },
                             ResponseString::Error(err) => {
-                                println!("Error during argument prompt: {}", err);
+                                eprintln!("Error during argument prompt: {}", err);
                                 continue;
                             }
                         }
@@ -133,21 +492,444 @@ fn repl(todo_list: &mut TodoList) {
                 }
 
                 match actions[i].try_create_payload(&args) {
-                    Ok(payload) => match todo_list.apply_action(payload) {
-                        Ok(_) => println!(""),
-                        Err(err) => println!("Error applying action.\n{}\n", err.to_string()),
-                    },
-                    Err(err) => println!("Error creating action.\n{}\n", err.to_string()),
+                    Ok(payload) if actions[i].is_profile_action() => {
+                        let outcome = Profile::apply_action(dir, DEFAULT_PROFILE, payload.clone(), false)
+                            .and_then(|outcome| {
+                                confirm_outcome(outcome, false, || {
+                                    Profile::apply_action(dir, DEFAULT_PROFILE, payload, true)
+                                })
+                            });
+                        match outcome {
+                            Ok(outcome) => report(&outcome),
+                            Err(err) => eprintln!("Error applying profile action.\n{}\n", err),
+                        }
+                    }
+                    Ok(payload) => {
+                        if let Ok(list) = todo_list.list() {
+                            eprintln!("{}", payload_preview(&payload, &list));
+                        }
+
+                        // Destructive actions get a final confirmation, which stands in for
+                        // any confirmation the action itself would ask for.
+                        let outcome = if actions[i].is_destructive() {
+                            match Prompter::confirm("Apply this change?") {
+                                ResponseBool::Value(true) => todo_list.apply_confirmed(payload),
+                                _ => Ok(ActionOutcome::Nothing("Cancelled, nothing was changed.".to_string())),
+                            }
+                        } else {
+                            todo_list.apply_action(payload.clone()).and_then(|outcome| {
+                                confirm_outcome(outcome, false, || todo_list.apply_confirmed(payload))
+                            })
+                        };
+                        match outcome {
+                            Ok(outcome) => report(&outcome),
+                            Err(err) => eprintln!("Error applying action.\n{}\n", err),
+                        }
+                    }
+                    Err(err) => eprintln!("Error creating action.\n{}\n", err),
                 };
             }
             ResponseIndex::Cancelled => {
-                println!("Selection cancelled, exiting program...");
+                eprintln!("Selection cancelled, exiting program...");
+                return;
+            }
+            ResponseIndex::Error(err) => {
+                eprintln!("An error has occurred: {}", err);
+                return;
+            }
+        }
+    }
+}
+
+/// Shows a multi-select of the incomplete todos and marks the chosen ones as complete.
+fn complete_interactively(todo_list: &mut CachedTodoList) {
+    let incomplete = todo_list.get_todos_with_status(false).unwrap_or_default();
+    if incomplete.is_empty() {
+        eprintln!("There are no incomplete todos.");
+        return;
+    }
+
+    let chosen = match Prompter::multi_select("Choose the todos to complete", &incomplete) {
+        ResponseIndices::Value(indices) => match todo_list.list() {
+            Ok(list) => list.chosen_incomplete(&indices),
+            Err(err) => {
+                eprintln!("Error loading Todo-List.\n{}\n", err);
                 return;
             }
+        },
+        ResponseIndices::Cancelled => {
+            eprintln!("Cancelling complete operation.");
+            return;
+        }
+        ResponseIndices::Error(err) => {
+            eprintln!("Error during prompt: {}", err);
+            return;
+        }
+    };
+
+    if chosen.is_empty() {
+        eprintln!("No todos chosen.");
+        return;
+    }
+
+    match todo_list.apply_action(ActionPayload::Complete(chosen)) {
+        Ok(outcome) => report(&outcome),
+        Err(err) => eprintln!("Error applying action.\n{}\n", err),
+    }
+}
+
+/// Shows a multi-select of every todo and removes the chosen ones once the user confirms.
+fn remove_interactively(todo_list: &mut CachedTodoList) {
+    let todos = todo_list.get_todos_text().unwrap_or_default();
+    if todos.is_empty() {
+        eprintln!("There are no todos.");
+        return;
+    }
+
+    let chosen: Vec<String> = match Prompter::multi_select("Choose the todos to remove", &todos) {
+        ResponseIndices::Value(indices) => indices.iter().filter_map(|idx| todos.get(*idx).cloned()).collect(),
+        ResponseIndices::Cancelled => {
+            eprintln!("Cancelling remove operation.");
+            return;
+        }
+        ResponseIndices::Error(err) => {
+            eprintln!("Error during prompt: {}", err);
+            return;
+        }
+    };
+
+    if chosen.is_empty() {
+        eprintln!("No todos chosen.");
+        return;
+    }
+
+    match Prompter::confirm(format!("Remove {} todos?", chosen.len())) {
+        ResponseBool::Value(true) => {}
+        ResponseBool::Value(false) | ResponseBool::Cancelled => {
+            eprintln!("Cancelled, nothing was changed.");
+            return;
+        }
+        ResponseBool::Error(err) => {
+            eprintln!("Error during prompt: {}", err);
+            return;
+        }
+    }
+
+    for todo in chosen {
+        match todo_list.apply_action(ActionPayload::Remove(todo)) {
+            Ok(outcome) => report(&outcome),
+            Err(err) => eprintln!("Error applying action.\n{}\n", err),
+        }
+    }
+}
+
+/// Walks through the incomplete todos one at a time, asking whether to complete, delete,
+/// skip or tag each. Every decision is saved straight away, so cancelling at any point
+/// keeps the progress made so far.
+fn triage_interactively(todo_list: &mut CachedTodoList) {
+    let incomplete = todo_list.get_todos_with_status(false).unwrap_or_default();
+    if incomplete.is_empty() {
+        eprintln!("There are no incomplete todos.");
+        return;
+    }
+
+    let choices = TriageChoice::all();
+    for (index, todo) in incomplete.iter().enumerate() {
+        let prompt = format!("[{}/{}] {:?} (Esc to stop)", index + 1, incomplete.len(), todo);
+        let choice = match Prompter::select(prompt, &choices) {
+            ResponseIndex::Value(idx) => match &choices[idx] {
+                TriageChoice::Tag(_) => match Prompter::input(format!("Tag {:?} with", todo)) {
+                    ResponseString::Value(tag) => TriageChoice::Tag(tag),
+                    ResponseString::Cancelled => TriageChoice::Skip,
+                    ResponseString::Error(err) => {
+                        eprintln!("Error during prompt: {}", err);
+                        break;
+                    }
+                },
+                choice => choice.clone(),
+            },
+            ResponseIndex::Cancelled => {
+                eprintln!("Stopping triage.");
+                break;
+            }
             ResponseIndex::Error(err) => {
-                println!("An error has occurred: {}", err);
+                eprintln!("Error during prompt: {}", err);
+                break;
+            }
+        };
+
+        if let Some(action) = choice.action_for(todo.as_str()) {
+            match todo_list.apply_action(action) {
+                Ok(outcome) => report(&outcome),
+                Err(err) => eprintln!("Error applying action.\n{}\n", err),
+            }
+            if let Err(err) = todo_list.flush() {
+                eprintln!("Error saving Todo-List database! {}", err);
                 return;
             }
         }
     }
 }
+
+/// Offers the `offered` follow-ups until the user is done, applying each one chosen to
+/// `todo_list`. Returns the actions applied along with their outcomes, nothing is saved.
+fn follow_up(todo_list: &mut TodoList, mut offered: Vec<FollowUp>) -> Vec<(ActionPayload, ActionOutcome)> {
+    let mut applied = Vec::new();
+    while !offered.is_empty() {
+        let chosen = match Prompter::select("Anything else? (Esc when done)", &offered) {
+            ResponseIndex::Value(idx) => offered[idx].clone(),
+            ResponseIndex::Cancelled => break,
+            ResponseIndex::Error(err) => {
+                eprintln!("Error during prompt: {}", err);
+                break;
+            }
+        };
+        let prompt = match chosen.prompt() {
+            Some(prompt) => prompt,
+            None => break,
+        };
+
+        let action = match Prompter::input(prompt) {
+            ResponseString::Value(value) => chosen.action_for(&value),
+            ResponseString::Cancelled => continue,
+            ResponseString::Error(err) => {
+                eprintln!("Error during prompt: {}", err);
+                break;
+            }
+        };
+        let action = match action {
+            Ok(Some(action)) => action,
+            Ok(None) => break,
+            Err(err) => {
+                eprintln!("{}", err);
+                continue;
+            }
+        };
+
+        match todo_list.apply_action(action.clone()) {
+            Ok(outcome) => {
+                report(&outcome);
+                offered = FollowUp::next(offered, &outcome);
+                applied.push((action, outcome));
+            }
+            Err(err) => eprintln!("Error applying action.\n{}\n", err),
+        }
+    }
+
+    applied
+}
+
+/// Returns the passphrase the database is encrypted with if [`AppSettings::encrypt`] is
+/// set, read from [PASSPHRASE_ENV] or else asked for.
+fn passphrase(settings: &AppSettings) -> Result<Option<String>, String> {
+    if !settings.encrypt {
+        return Ok(None);
+    }
+
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(Some(passphrase));
+    }
+
+    match Prompter::password("Passphrase") {
+        ResponseString::Value(passphrase) => Ok(Some(passphrase)),
+        ResponseString::Cancelled => Err("Passphrase prompt cancelled.".to_string()),
+        ResponseString::Error(err) => Err(err.to_string()),
+    }
+}
+
+/// Asks the user to confirm `outcome` if it needs confirmation, calling `apply_confirmed`
+/// to apply the action for real once they agree. Any other outcome is returned as is.
+fn confirm_outcome<F>(outcome: ActionOutcome, assume_yes: bool, apply_confirmed: F) -> Result<ActionOutcome, CommandError>
+where
+    F: FnOnce() -> Result<ActionOutcome, CommandError>,
+{
+    match outcome {
+        ActionOutcome::NeedsConfirmation(prompt) => match Prompter::confirm_unless(&prompt, assume_yes) {
+            ResponseBool::Value(true) => apply_confirmed(),
+            ResponseBool::Value(false) | ResponseBool::Cancelled => {
+                Ok(ActionOutcome::Nothing("Cancelled, nothing was changed.".to_string()))
+            }
+            ResponseBool::Error(err) => Err(CommandError::InputInvalid(err.to_string())),
+        },
+        outcome => Ok(outcome),
+    }
+}
+
+/// Hands `payload` to the resident service, see [`AppSettings::use_service`]. Returns `None`
+/// if the command should be run locally instead, because it uses options the service does
+/// not support, the service cannot run it, or the service is not running.
+#[cfg(any(unix, all(windows, feature = "service")))]
+fn forward_to_service(dir: &Path, payload: &ActionPayload, options: &CliOptions) -> Option<ExitCode> {
+    let local_only = options.dry_run
+        || options.data_dir.is_some()
+        || options.output.is_some()
+        || options.format.is_some()
+        || options.table
+        || options.max_width.is_some()
+        || options.count_only
+        || options.since_last_run;
+    if local_only || !is_servable(payload) {
+        return None;
+    }
+
+    let mut request = ServiceRequest::Apply {
+        action: payload.clone(),
+        assume_yes: options.assume_yes,
+        colorize: should_color(),
+    };
+    loop {
+        match send_to_service(dir, &request) {
+            Ok(ServiceResponse::Applied(output)) => {
+                println!("{}", output.trim_end());
+                return Some(ExitReason::Success.into());
+            }
+            Ok(ServiceResponse::NeedsConfirmation(prompt)) => match Prompter::confirm(&prompt) {
+                ResponseBool::Value(true) => {
+                    request = ServiceRequest::Apply {
+                        action: payload.clone(),
+                        assume_yes: true,
+                        colorize: should_color(),
+                    }
+                }
+                _ => {
+                    eprintln!("Cancelled, nothing was changed.");
+                    return Some(ExitReason::Success.into());
+                }
+            },
+            Ok(ServiceResponse::Failed(err)) => {
+                eprintln!("The service could not apply the command: {}", err);
+                return Some(ExitReason::from(&err).into());
+            }
+            Ok(ServiceResponse::ShuttingDown) => {
+                eprintln!("The service is shutting down, running the command locally.");
+                return None;
+            }
+            Err(err) => {
+                eprintln!("The service is not available, running the command locally. {}", err);
+                return None;
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn send_to_service(dir: &Path, request: &ServiceRequest) -> std::io::Result<ServiceResponse> {
+    ServiceClient::send(Profile::socket_path_for(dir, DEFAULT_PROFILE), request)
+}
+
+#[cfg(all(windows, feature = "service"))]
+fn send_to_service(_dir: &Path, request: &ServiceRequest) -> std::io::Result<ServiceResponse> {
+    ServiceClient::send(request)
+}
+
+/// Runs the daemon for `todo serve` until it is told to shut down, see [UnixService].
+/// Every change is saved as it is made, so stopping the daemon with Ctrl-C loses nothing.
+#[cfg(unix)]
+fn serve(dir: &Path, passphrase: Option<String>, settings: &AppSettings, options: &CliOptions) -> ExitCode {
+    let mut todo_list = match load_cached(dir, settings, options, passphrase) {
+        Ok(list) => list,
+        Err(err) => {
+            eprintln!("Unable to load Todo-List! {}", err);
+            return ExitReason::IoFailed.into();
+        }
+    };
+
+    let socket = Profile::socket_path_for(dir, DEFAULT_PROFILE);
+    let service = match UnixService::bind(&socket) {
+        Ok(service) => service,
+        Err(err) => {
+            eprintln!("Unable to listen on {:?}! {}", socket, err);
+            return ExitReason::IoFailed.into();
+        }
+    };
+
+    eprintln!("Serving {} todos on {:?}.", todo_list.len().unwrap_or_default(), socket);
+    let served = service
+        .serve(&mut todo_list)
+        .and_then(|_| todo_list.flush().map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err)));
+    match served {
+        Ok(_) => ExitReason::Success.into(),
+        Err(err) => {
+            eprintln!("The daemon stopped with an error! {}", err);
+            ExitReason::IoFailed.into()
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn serve(_dir: &Path, _passphrase: Option<String>, _settings: &AppSettings, _options: &CliOptions) -> ExitCode {
+    eprintln!("The serve command is only available on Unix, run the service on Windows instead.");
+    ExitReason::InvalidCommand.into()
+}
+
+/// Loads the [DEFAULT_PROFILE] in `dir` into a [CachedTodoList] set up from `settings`,
+/// with `--backup` and `--no-backup` in `options` taking precedence. Every command that
+/// keeps a list loaded, such as the REPL and `serve`, loads it through here.
+fn load_cached(
+    dir: &Path,
+    settings: &AppSettings,
+    options: &CliOptions,
+    passphrase: Option<String>,
+) -> Result<CachedTodoList, String> {
+    Ok(CachedTodoList::load_with(dir, DEFAULT_PROFILE, DEFAULT_CACHE_TTL, passphrase)?
+        .with_backup(options.use_backup(settings))
+        .with_backup_retention(settings.backup_retention)
+        .with_lock_timeout(settings.lock_timeout())
+        .with_tag_normalization(settings.normalize_tags)
+        .with_stats_log(settings.stats_log)
+        .with_date_format(&settings.date_format)
+        .with_status_symbols(&settings.complete_symbol, &settings.incomplete_symbol)
+        .with_case_insensitive_duplicates(settings.case_insensitive_duplicates)
+        .with_colorize(should_color()))
+}
+
+/// Runs `todo settings`, which prints the effective settings, or `todo settings set <KEY>
+/// <VALUE>`, which saves a setting to the settings file in the config directory.
+fn run_settings(settings: &AppSettings, args: &[String]) -> ExitCode {
+    let (key, value) = match args {
+        [] => {
+            print!("{}", settings.render());
+            return ExitReason::Success.into();
+        }
+        [set, key, value] if set == "set" => (key, value),
+        _ => {
+            eprintln!("Usage: todo settings [set <KEY> <VALUE>]");
+            return ExitReason::InvalidArgument.into();
+        }
+    };
+
+    let value = match AppSettings::parse_setting(key, value) {
+        Ok(value) => value,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitReason::from(&err).into();
+        }
+    };
+    let file = match AppSettings::settings_file() {
+        Some(file) => file,
+        None => {
+            eprintln!("There is no config directory to save the settings in.");
+            return ExitReason::IoFailed.into();
+        }
+    };
+
+    match AppSettings::save_setting(&file, key, value) {
+        Ok(_) => {
+            eprintln!("Saved {} to {:?}.", key, file.with_extension("json"));
+            ExitReason::Success.into()
+        }
+        Err(err) => {
+            eprintln!("Unable to save the settings! {}", err);
+            ExitReason::IoFailed.into()
+        }
+    }
+}
+
+/// Prints the message for `outcome`. Listings are the result of the command and go to
+/// stdout, every other outcome is a status message and goes to stderr.
+fn report(outcome: &ActionOutcome) {
+    match outcome {
+        ActionOutcome::Listing(listing) => print!("{}", listing),
+        outcome => eprintln!("{}", outcome),
+    }
+}
@@ -1 +1,5 @@
-pub mod win_service;
\ No newline at end of file
+pub mod protocol;
+#[cfg(unix)]
+pub mod unix_service;
+#[cfg(all(windows, feature = "service"))]
+pub mod win_service;
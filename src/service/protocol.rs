@@ -0,0 +1,182 @@
+//! The wire protocol spoken between the CLI and a resident service, either the Windows
+//! service or the Unix daemon started with `todo serve`.
+//!
+//! A connection carries one [ServiceRequest] from the client followed by one
+//! [ServiceResponse] from the service. Each is sent as a frame: its length in bytes as a
+//! big-endian `u32`, then the value serialized with [Cereal] in [WIRE_ENCODING]. Frames
+//! longer than [MAX_FRAME_LEN] are refused.
+
+use std::io::{self, Read, Write};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    state::actions::{action_outcome::ActionOutcome, action_payload::ActionPayload},
+    todos::{cached_todolist::CachedTodoList, command_error::CommandError},
+    utils::cereal::{Cereal, EncodingType},
+};
+
+/// The encoding frames are serialized with.
+pub const WIRE_ENCODING: EncodingType = EncodingType::MsgPack;
+
+/// The largest frame, in bytes, either side will read.
+pub const MAX_FRAME_LEN: u32 = 16 * 1024 * 1024;
+
+/// What a client asks the service to do.
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Serialize, Deserialize, Clone)]
+pub enum ServiceRequest {
+    /// Apply `action` to the resident list, answering yes to any confirmation it asks for
+    /// if `assume_yes` is set. Listings are colored if `colorize` is set, which the client
+    /// decides for its own terminal.
+    Apply {
+        action: ActionPayload,
+        assume_yes: bool,
+        colorize: bool,
+    },
+    /// Save the list and stop serving.
+    Shutdown,
+}
+
+/// How the service answered a [ServiceRequest].
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Serialize, Deserialize, Clone)]
+pub enum ServiceResponse {
+    /// The action was applied and saved, with the message or listing of its outcome.
+    Applied(String),
+    /// The action needs confirmation, with the question to ask. Nothing was changed.
+    NeedsConfirmation(String),
+    /// The action could not be applied.
+    Failed(CommandError),
+    /// The service saved the list and is stopping.
+    ShuttingDown,
+}
+
+/// Applies the action in `request` to `list` and returns the answer for the client.
+/// Changes are saved straight away so nothing is lost if the service stops. Profile
+/// commands and commands that need the terminal, such as `triage`, are refused, the CLI
+/// runs those itself. [`ServiceRequest::Shutdown`] only saves the list, stopping is up to
+/// the caller.
+pub fn handle_request(list: &mut CachedTodoList, request: ServiceRequest) -> ServiceResponse {
+    let (action, assume_yes) = match request {
+        ServiceRequest::Apply {
+            action,
+            assume_yes,
+            colorize,
+        } => {
+            list.set_colorize(colorize);
+            (action, assume_yes)
+        }
+        ServiceRequest::Shutdown => {
+            return match list.flush() {
+                Ok(_) => ServiceResponse::ShuttingDown,
+                Err(err) => ServiceResponse::Failed(CommandError::IoError(err)),
+            }
+        }
+    };
+
+    if !is_servable(&action) {
+        return ServiceResponse::Failed(CommandError::InputInvalid(format!(
+            "the {:?} command cannot be run by the service",
+            action.get_action_type().get_input_string()
+        )));
+    }
+
+    let outcome = if assume_yes {
+        list.apply_confirmed(action)
+    } else {
+        list.apply_action(action)
+    };
+    let outcome = match outcome {
+        Ok(ActionOutcome::NeedsConfirmation(prompt)) => return ServiceResponse::NeedsConfirmation(prompt),
+        Ok(outcome) => outcome,
+        Err(err) => return ServiceResponse::Failed(err),
+    };
+
+    match list.flush() {
+        Ok(_) => ServiceResponse::Applied(outcome.to_string()),
+        Err(err) => ServiceResponse::Failed(CommandError::IoError(err)),
+    }
+}
+
+/// Returns `true` if `action` can be handed to a service rather than run by the CLI.
+pub fn is_servable(action: &ActionPayload) -> bool {
+    !matches!(action, ActionPayload::Serve | ActionPayload::Triage)
+        && !action.get_action_type().is_profile_action()
+}
+
+/// Writes `value` to `writer` as a frame.
+pub fn write_frame<W: Write, T: Serialize>(writer: &mut W, value: &T) -> io::Result<()> {
+    let bytes = Cereal::serialize_with(WIRE_ENCODING, value)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    let len = u32::try_from(bytes.len())
+        .ok()
+        .filter(|len| *len <= MAX_FRAME_LEN)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "frame is too large"))?;
+
+    writer.write_all(&len.to_be_bytes())?;
+    writer.write_all(&bytes)?;
+    writer.flush()
+}
+
+/// Reads one frame from `reader`.
+pub fn read_frame<R: Read, T: DeserializeOwned>(reader: &mut R) -> io::Result<T> {
+    let mut len = [0; 4];
+    reader.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len);
+    if len > MAX_FRAME_LEN {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "frame is too large"));
+    }
+
+    let mut bytes = vec![0; len as usize];
+    reader.read_exact(&mut bytes)?;
+    Cereal::deserialize_with(WIRE_ENCODING, &bytes)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        todos::{cached_todolist::DEFAULT_CACHE_TTL, todolist::TodoList},
+        utils::testing::temp_dir,
+    };
+
+    fn apply(action: ActionPayload, assume_yes: bool) -> ServiceRequest {
+        ServiceRequest::Apply {
+            action,
+            assume_yes,
+            colorize: false,
+        }
+    }
+
+    #[test]
+    fn requests_are_applied_and_saved() {
+        let dir = temp_dir("service-protocol");
+        TodoList::new().save_to_profile(&dir, "work").unwrap();
+        let mut list = CachedTodoList::load(&dir, "work", DEFAULT_CACHE_TTL).unwrap();
+
+        let added = handle_request(&mut list, apply(ActionPayload::Add("buy milk".to_string()), false));
+        assert!(matches!(added, ServiceResponse::Applied(_)));
+        assert_eq!(TodoList::load_from_profile(&dir, "work").unwrap().len(), 1);
+
+        let cleared = handle_request(&mut list, apply(ActionPayload::Clear, false));
+        assert!(matches!(cleared, ServiceResponse::NeedsConfirmation(_)));
+        let cleared = handle_request(&mut list, apply(ActionPayload::Clear, true));
+        assert!(matches!(cleared, ServiceResponse::Applied(_)));
+        assert!(TodoList::load_from_profile(&dir, "work").unwrap().is_empty());
+
+        let triage = handle_request(&mut list, apply(ActionPayload::Triage, false));
+        assert!(matches!(triage, ServiceResponse::Failed(CommandError::InputInvalid(_))));
+        assert_eq!(handle_request(&mut list, ServiceRequest::Shutdown), ServiceResponse::ShuttingDown);
+    }
+
+    #[test]
+    fn frames_round_trip() {
+        let request = apply(ActionPayload::Add("buy milk".to_string()), true);
+        let mut bytes = Vec::new();
+        write_frame(&mut bytes, &request).unwrap();
+
+        let read: ServiceRequest = read_frame(&mut bytes.as_slice()).unwrap();
+        assert_eq!(read, request);
+        assert!(read_frame::<_, ServiceRequest>(&mut &bytes[..bytes.len() - 1]).is_err());
+    }
+}
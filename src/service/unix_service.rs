@@ -0,0 +1,100 @@
+use std::{
+    io,
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use super::protocol::{handle_request, read_frame, write_frame, ServiceRequest, ServiceResponse};
+use crate::todos::cached_todolist::CachedTodoList;
+
+/// How long the daemon waits for a client to send its request before dropping it, so that
+/// one stalled client cannot hold up every other.
+pub const CLIENT_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The Unix counterpart of the Windows service: a daemon that keeps a [CachedTodoList]
+/// loaded and applies the actions other instances send it over a Unix domain socket,
+/// speaking the [protocol](super::protocol). It is started with `todo serve` and runs
+/// until it is sent [`ServiceRequest::Shutdown`].
+pub struct UnixService {
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+impl UnixService {
+    /// Listens on the socket at `path`. A socket left behind by a daemon that did not shut
+    /// down cleanly is replaced, but one with a daemon still listening is an error.
+    pub fn bind<TPath: AsRef<Path>>(path: TPath) -> io::Result<UnixService> {
+        let path = path.as_ref().to_path_buf();
+        if path.exists() {
+            if UnixStream::connect(&path).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    format!("a daemon is already listening on {:?}", path),
+                ));
+            }
+            std::fs::remove_file(&path)?;
+        }
+
+        Ok(UnixService {
+            listener: UnixListener::bind(&path)?,
+            path,
+        })
+    }
+
+    /// Answers clients one at a time, applying their actions to `list`, until one asks the
+    /// daemon to shut down. A client that fails part way through, or sends nothing within
+    /// [CLIENT_READ_TIMEOUT], is reported and skipped.
+    pub fn serve(&self, list: &mut CachedTodoList) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    eprintln!("Unable to accept a client on {:?}: {}", self.path, err);
+                    continue;
+                }
+            };
+
+            if let Err(err) = stream.set_read_timeout(Some(CLIENT_READ_TIMEOUT)) {
+                eprintln!("Unable to set a read timeout on {:?}: {}", self.path, err);
+                continue;
+            }
+            let response = match read_frame::<_, ServiceRequest>(&mut stream) {
+                Ok(request) => handle_request(list, request),
+                Err(err) => {
+                    eprintln!("Unable to read a request on {:?}: {}", self.path, err);
+                    continue;
+                }
+            };
+            if let Err(err) = write_frame(&mut stream, &response) {
+                eprintln!("Unable to answer a client on {:?}: {}", self.path, err);
+            }
+
+            if response == ServiceResponse::ShuttingDown {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for UnixService {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Stateless struct used to group the functions the CLI uses to talk to the daemon, see
+/// [`AppSettings::use_service`](crate::config::settings::AppSettings::use_service).
+pub struct ServiceClient;
+
+impl ServiceClient {
+    /// Sends `request` to the daemon listening on the socket at `path` and waits for its
+    /// response. Fails if no daemon is listening.
+    pub fn send<TPath: AsRef<Path>>(path: TPath, request: &ServiceRequest) -> io::Result<ServiceResponse> {
+        let mut stream = UnixStream::connect(path)?;
+        write_frame(&mut stream, request)?;
+        read_frame(&mut stream)
+    }
+}
@@ -1,37 +1,243 @@
-use std::ffi::OsString;
-use windows_service::{define_windows_service, service, service_control_handler, service_dispatcher, service_manager};
+use std::{
+    ffi::{OsStr, OsString},
+    fs::{File, OpenOptions},
+    io,
+    iter::once,
+    os::windows::{ffi::OsStrExt, io::FromRawHandle},
+    path::PathBuf,
+    ptr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use winapi::{
+    shared::winerror::ERROR_PIPE_CONNECTED,
+    um::{
+        handleapi::{CloseHandle, INVALID_HANDLE_VALUE},
+        namedpipeapi::ConnectNamedPipe,
+        winbase::{CreateNamedPipeW, PIPE_ACCESS_DUPLEX, PIPE_READMODE_BYTE, PIPE_TYPE_BYTE, PIPE_WAIT},
+    },
+};
+use windows_service::{
+    define_windows_service,
+    service::{ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceState, ServiceStatus, ServiceType},
+    service_control_handler::{self, ServiceControlHandlerResult, ServiceStatusHandle},
+    service_dispatcher,
+};
+
+use super::protocol::{handle_request, read_frame, write_frame, ServiceRequest, ServiceResponse};
+use crate::{
+    config::settings::AppSettings,
+    todos::{
+        cached_todolist::{CachedTodoList, DEFAULT_CACHE_TTL},
+        command_error::CommandError,
+        profile::DEFAULT_PROFILE,
+    },
+};
+
+/// The name the service is registered under.
+pub const SERVICE_NAME: &str = "thingstodo_service";
+
+/// The named pipe the service listens on and the CLI connects to.
+pub const PIPE_NAME: &str = r"\\.\pipe\thingstodo";
+
+/// The size of the pipe's input and output buffers.
+const PIPE_BUFFER_SIZE: u32 = 4096;
+
+/// How long the service asks the service manager to wait while it saves and stops.
+const STOP_WAIT_HINT: Duration = Duration::from_secs(10);
 
 define_windows_service!(ffi_service_main, todo_service_main);
 
-fn todo_service_main(arguments: Vec<OsString>) {
-    if let Err(e) = run_service(arguments) {
-        // handle errors
+/// Hands the process over to the service manager, which calls back into the service. This
+/// blocks until the service stops and only works when the process was started as a service.
+pub fn run() -> Result<(), windows_service::Error> {
+    service_dispatcher::start(SERVICE_NAME, ffi_service_main)
+}
+
+fn todo_service_main(_arguments: Vec<OsString>) {
+    if let Err(err) = run_service() {
+        eprintln!("The {} service failed: {}", SERVICE_NAME, err);
     }
 }
 
-fn run_service(arguments: Vec<OsString>) -> Result<(), windows_service::Error> {
-    let event_handler = move |control_event: service::ServiceControl| -> service_control_handler::ServiceControlHandlerResult {
+/// Registers the control handler, loads the list and serves requests on [PIPE_NAME] until
+/// the service is stopped, saving the list before reporting that it has stopped.
+fn run_service() -> Result<(), windows_service::Error> {
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let event_handler = move |control_event| -> ServiceControlHandlerResult {
         match control_event {
-            service::ServiceControl::Continue => todo!(),
-            service::ServiceControl::Interrogate => service_control_handler::ServiceControlHandlerResult::NoError,
-            service::ServiceControl::NetBindAdd => todo!(),
-            service::ServiceControl::NetBindDisable => todo!(),
-            service::ServiceControl::NetBindEnable => todo!(),
-            service::ServiceControl::NetBindRemove => todo!(),
-            service::ServiceControl::ParamChange => todo!(),
-            service::ServiceControl::Pause => todo!(),
-            service::ServiceControl::Preshutdown => todo!(),
-            service::ServiceControl::Shutdown => todo!(),
-            service::ServiceControl::Stop => service_control_handler::ServiceControlHandlerResult::NoError,
-            service::ServiceControl::HardwareProfileChange(hw_prof_chg_params) => todo!(),
-            service::ServiceControl::PowerEvent(pwr_event_params) => todo!(),
-            service::ServiceControl::SessionChange(sess_chg_params) => todo!(),
-            service::ServiceControl::TimeChange => todo!(),
-            service::ServiceControl::TriggerEvent => todo!(),
+            ServiceControl::Stop | ServiceControl::Shutdown | ServiceControl::Preshutdown => {
+                let _ = shutdown_tx.send(());
+                ServiceControlHandlerResult::NoError
+            }
+            // The status is reported whenever it changes, so there is nothing more to say.
+            ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+            // Settings are read when the service starts, a restart picks up any changes.
+            ServiceControl::ParamChange => ServiceControlHandlerResult::NoError,
+            _ => ServiceControlHandlerResult::NotImplemented,
+        }
+    };
+    let status_handle = service_control_handler::register(SERVICE_NAME, event_handler)?;
+
+    let list = match load_list() {
+        Ok(list) => Arc::new(Mutex::new(list)),
+        Err(err) => {
+            eprintln!("Unable to load Todo-List! {}", err);
+            set_status(&status_handle, ServiceState::Stopped, ServiceExitCode::ServiceSpecific(1))?;
+            return Ok(());
+        }
+    };
+    set_status(&status_handle, ServiceState::Running, ServiceExitCode::Win32(0))?;
+
+    let stopping = Arc::new(AtomicBool::new(false));
+    let server = {
+        let list = Arc::clone(&list);
+        let stopping = Arc::clone(&stopping);
+        thread::spawn(move || serve(&list, &stopping))
+    };
+
+    let _ = shutdown_rx.recv();
+    set_status(&status_handle, ServiceState::StopPending, ServiceExitCode::Win32(0))?;
+
+    // The server blocks waiting for a client, so connect to wake it up once it knows to stop.
+    stopping.store(true, Ordering::SeqCst);
+    let _ = OpenOptions::new().read(true).write(true).open(PIPE_NAME);
+    let _ = server.join();
+
+    let saved = list.lock().map_err(|err| err.to_string()).and_then(|mut list| list.flush());
+    let exit_code = match saved {
+        Ok(_) => ServiceExitCode::Win32(0),
+        Err(err) => {
+            eprintln!("Error saving Todo-List database! {}", err);
+            ServiceExitCode::ServiceSpecific(2)
+        }
+    };
+    set_status(&status_handle, ServiceState::Stopped, exit_code)
+}
+
+/// Loads the default profile from the configured data directory, the working directory of
+/// a service is not somewhere the database should live.
+fn load_list() -> Result<CachedTodoList, String> {
+    let settings = AppSettings::load().unwrap_or_default();
+    let dir = settings.data_dir.clone().map_or_else(|| PathBuf::from("."), PathBuf::from);
+    std::fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+
+    Ok(CachedTodoList::load(&dir, DEFAULT_PROFILE, DEFAULT_CACHE_TTL)?
+        .with_backup(settings.use_backup)
+        .with_backup_retention(settings.backup_retention)
+        .with_lock_timeout(settings.lock_timeout())
+        .with_tag_normalization(settings.normalize_tags)
+        .with_stats_log(settings.stats_log)
+        .with_date_format(&settings.date_format)
+        .with_status_symbols(&settings.complete_symbol, &settings.incomplete_symbol)
+        .with_case_insensitive_duplicates(settings.case_insensitive_duplicates))
+}
+
+fn set_status(
+    status_handle: &ServiceStatusHandle,
+    state: ServiceState,
+    exit_code: ServiceExitCode,
+) -> Result<(), windows_service::Error> {
+    let controls_accepted = match state {
+        ServiceState::Running => ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN,
+        _ => ServiceControlAccept::empty(),
+    };
+    let wait_hint = match state {
+        ServiceState::StopPending => STOP_WAIT_HINT,
+        _ => Duration::default(),
+    };
+
+    status_handle.set_service_status(ServiceStatus {
+        service_type: ServiceType::OWN_PROCESS,
+        current_state: state,
+        controls_accepted,
+        exit_code,
+        checkpoint: 0,
+        wait_hint,
+        process_id: None,
+    })
+}
+
+/// Answers clients on [PIPE_NAME] one at a time, see [protocol](super::protocol), until
+/// `stopping` is set.
+fn serve(list: &Mutex<CachedTodoList>, stopping: &AtomicBool) {
+    while !stopping.load(Ordering::SeqCst) {
+        let pipe = match accept() {
+            Ok(pipe) => pipe,
+            Err(err) => {
+                eprintln!("Unable to accept a client on {}: {}", PIPE_NAME, err);
+                thread::sleep(Duration::from_secs(1));
+                continue;
+            }
+        };
+        if stopping.load(Ordering::SeqCst) {
+            break;
+        }
+
+        if let Err(err) = answer(&pipe, list) {
+            eprintln!("Unable to answer a client on {}: {}", PIPE_NAME, err);
+        }
+    }
+}
+
+/// Creates an instance of [PIPE_NAME] and waits for a client to connect to it.
+fn accept() -> io::Result<File> {
+    let name: Vec<u16> = OsStr::new(PIPE_NAME).encode_wide().chain(once(0)).collect();
+    let handle = unsafe {
+        CreateNamedPipeW(
+            name.as_ptr(),
+            PIPE_ACCESS_DUPLEX,
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT,
+            1,
+            PIPE_BUFFER_SIZE,
+            PIPE_BUFFER_SIZE,
+            0,
+            ptr::null_mut(),
+        )
+    };
+    if handle == INVALID_HANDLE_VALUE {
+        return Err(io::Error::last_os_error());
+    }
+
+    // A client that connected between creating the pipe and waiting for it is fine too.
+    if unsafe { ConnectNamedPipe(handle, ptr::null_mut()) } == 0 {
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() != Some(ERROR_PIPE_CONNECTED as i32) {
+            unsafe { CloseHandle(handle) };
+            return Err(err);
         }
+    }
+
+    Ok(unsafe { File::from_raw_handle(handle as _) })
+}
+
+/// Reads one [ServiceRequest] from `pipe`, applies it and writes back the [ServiceResponse].
+/// Stopping the service is left to the service manager, so a shutdown request only saves.
+fn answer(mut pipe: &File, list: &Mutex<CachedTodoList>) -> io::Result<()> {
+    let request: ServiceRequest = read_frame(&mut pipe)?;
+    let response = match list.lock() {
+        Ok(mut list) => handle_request(&mut list, request),
+        Err(err) => ServiceResponse::Failed(CommandError::IoError(err.to_string())),
     };
 
-    let status_handler = service_control_handler::register("thingstodo_service", event_handler)?;
-    
-    Ok(())
-}
\ No newline at end of file
+    write_frame(&mut pipe, &response)
+}
+
+/// Stateless struct used to group the functions the CLI uses to talk to the resident
+/// service, see [`AppSettings::use_service`].
+pub struct ServiceClient;
+
+impl ServiceClient {
+    /// Sends `request` to the service over [PIPE_NAME] and waits for its response. Fails if
+    /// the service is not running.
+    pub fn send(request: &ServiceRequest) -> io::Result<ServiceResponse> {
+        let mut pipe = OpenOptions::new().read(true).write(true).open(PIPE_NAME)?;
+        write_frame(&mut pipe, request)?;
+        read_frame(&mut pipe)
+    }
+}
@@ -0,0 +1,198 @@
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+
+use crate::todos::{
+    priority::Priority,
+    recurrence::Recurrence,
+    todolist::{AddSummary, ImportSummary, ReplaceReport},
+};
+
+/// What applying an [ActionPayload](super::action_payload::ActionPayload) did, or what it
+/// needs before it can be done. Applying an action never prints or prompts, the caller
+/// decides how to present the outcome. The [Display](std::fmt::Display) implementation
+/// gives the message to show the user.
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub enum ActionOutcome {
+    /// Nothing was changed because the action needs the user to answer this yes or no
+    /// prompt first. Once they agree, apply the action again with confirmation, see
+    /// [`TodoList::apply_confirmed`](crate::todos::todolist::TodoList::apply_confirmed).
+    NeedsConfirmation(String),
+    /// There was nothing for the action to do, for the reason given.
+    Nothing(String),
+    Added(String),
+    AddedMany(AddSummary),
+    /// The second todo was added as a subtask of the first.
+    AddedSub(String, String),
+    Cleared(usize),
+    /// A tag, or every tag if `None`, was removed from `count` todos.
+    TagsCleared { tag: Option<String>, count: usize },
+    Colored(String, String),
+    /// The todos given were replaced by the single todo given.
+    Combined(Vec<String>, String),
+    Completed(usize),
+    /// The due date of a todo was set, or cleared if `None`.
+    DueSet(String, Option<NaiveDate>),
+    Edited(String, String),
+    Exported { file: String, count: usize },
+    Imported(ImportSummary),
+    /// The answer to an `empty` check, `true` if there were no matching todos.
+    IsEmpty(bool),
+    /// The rendered output of a listing action.
+    Listing(String),
+    /// A todo was moved to the zero-based position given.
+    Moved(String, usize),
+    /// The note of a todo was set, or removed if `false`.
+    Noted(String, bool),
+    /// A patch file was applied, making `count` changes.
+    Patched { file: String, count: usize },
+    /// The priority of a todo was set, or cleared if `None`.
+    PrioritySet(String, Option<Priority>),
+    /// How often a todo recurs was set, or it was stopped recurring if `None`.
+    RecurrenceSet(String, Option<Recurrence>),
+    Purged(usize),
+    Removed(String),
+    Replaced(ReplaceReport),
+    /// A todo was replaced by the new todos given.
+    Split(String, Vec<String>),
+    StatusSet(String, bool),
+    Tagged(String, String),
+    Toggled(String, bool),
+    ProfileCopied(String, String),
+    ProfileDeleted { name: String, backup: PathBuf },
+    ProfileRenamed(String, String),
+    /// A profile was rebuilt from its action log by replaying `count` actions.
+    ProfileRecovered { name: String, count: usize },
+    /// A debug command was run, these report on their own as they go.
+    DebugRan(String),
+}
+
+impl ActionOutcome {
+    /// Returns `true` if this outcome shows that the list was changed, which is what
+    /// decides whether the action is recorded in the action log.
+    pub fn is_change(&self) -> bool {
+        !matches!(
+            self,
+            ActionOutcome::NeedsConfirmation(_)
+                | ActionOutcome::Nothing(_)
+                | ActionOutcome::Exported { .. }
+                | ActionOutcome::IsEmpty(_)
+                | ActionOutcome::Listing(_)
+                | ActionOutcome::ProfileCopied(_, _)
+                | ActionOutcome::ProfileDeleted { .. }
+                | ActionOutcome::ProfileRenamed(_, _)
+                | ActionOutcome::ProfileRecovered { .. }
+                | ActionOutcome::DebugRan(_)
+        )
+    }
+}
+
+fn todos(count: usize) -> &'static str {
+    if count == 1 {
+        "todo"
+    } else {
+        "todos"
+    }
+}
+
+fn status(completed: bool) -> &'static str {
+    if completed {
+        "complete"
+    } else {
+        "incomplete"
+    }
+}
+
+impl std::fmt::Display for ActionOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ActionOutcome::NeedsConfirmation(prompt) => write!(f, "{}", prompt),
+            ActionOutcome::Nothing(reason) => write!(f, "{}", reason),
+            ActionOutcome::Added(todo) => write!(f, "Added {:?}.", todo),
+            ActionOutcome::AddedSub(parent, todo) => write!(f, "Added {:?} under {:?}.", todo, parent),
+            ActionOutcome::AddedMany(summary) => {
+                for todo in &summary.skipped {
+                    writeln!(f, "Skipping {:?}, it already exists.", todo)?;
+                }
+                write!(f, "{}", summary)
+            }
+            ActionOutcome::Cleared(count) => write!(f, "Cleared {} {}.", count, todos(*count)),
+            ActionOutcome::TagsCleared { tag: Some(tag), count } => {
+                write!(f, "Removed tag {:?} from {} {}.", tag, count, todos(*count))
+            }
+            ActionOutcome::TagsCleared { tag: None, count } => {
+                write!(f, "Removed all tags from {} {}.", count, todos(*count))
+            }
+            ActionOutcome::Colored(todo, color) => write!(f, "Colored {:?} {}.", todo, color),
+            ActionOutcome::Combined(todos, combined) => {
+                write!(f, "Combined {} todos into {:?}.", todos.len(), combined)
+            }
+            ActionOutcome::Completed(count) => write!(f, "Completed {} {}.", count, todos(*count)),
+            ActionOutcome::DueSet(todo, Some(due)) => write!(f, "Set {:?} as due on {}.", todo, due),
+            ActionOutcome::DueSet(todo, None) => write!(f, "Cleared the due date of {:?}.", todo),
+            ActionOutcome::Edited(old, new) => write!(f, "Changed {:?} to {:?}.", old, new),
+            ActionOutcome::Exported { file, count } => {
+                write!(f, "Exported {} {} to {:?}.", count, todos(*count), file)
+            }
+            ActionOutcome::Imported(summary) => write!(f, "{}", summary),
+            ActionOutcome::IsEmpty(true) => write!(f, "The list is empty."),
+            ActionOutcome::IsEmpty(false) => write!(f, "The list is not empty."),
+            ActionOutcome::Listing(listing) => write!(f, "{}", listing),
+            ActionOutcome::Moved(todo, position) => {
+                write!(f, "Moved {:?} to position {}.", todo, position + 1)
+            }
+            ActionOutcome::Noted(todo, true) => write!(f, "Saved the note on {:?}.", todo),
+            ActionOutcome::Noted(todo, false) => write!(f, "Removed the note from {:?}.", todo),
+            ActionOutcome::Patched { file, count } => write!(
+                f,
+                "Applied {} {} from {:?}.",
+                count,
+                if *count == 1 { "change" } else { "changes" },
+                file
+            ),
+            ActionOutcome::PrioritySet(todo, Some(priority)) => {
+                write!(f, "Set the priority of {:?} to {}.", todo, priority)
+            }
+            ActionOutcome::PrioritySet(todo, None) => write!(f, "Cleared the priority of {:?}.", todo),
+            ActionOutcome::RecurrenceSet(todo, Some(recurrence)) => {
+                write!(f, "{:?} now repeats {}.", todo, recurrence)
+            }
+            ActionOutcome::RecurrenceSet(todo, None) => write!(f, "{:?} no longer repeats.", todo),
+            ActionOutcome::Purged(count) => write!(f, "Purged {} completed {}.", count, todos(*count)),
+            ActionOutcome::Removed(todo) => write!(f, "Removed {:?}.", todo),
+            ActionOutcome::Replaced(report) => write!(f, "{}", report),
+            ActionOutcome::Split(todo, created) => write!(
+                f,
+                "Split {:?} into {} {}: {}.",
+                todo,
+                created.len(),
+                todos(created.len()),
+                created.iter().map(|text| format!("{:?}", text)).collect::<Vec<_>>().join(", ")
+            ),
+            ActionOutcome::StatusSet(todo, completed) => {
+                write!(f, "Marked {:?} as {}.", todo, status(*completed))
+            }
+            ActionOutcome::Tagged(todo, tag) => write!(f, "Tagged {:?} with {:?}.", todo, tag),
+            ActionOutcome::Toggled(todo, completed) => {
+                write!(f, "Marked {:?} as {}.", todo, status(*completed))
+            }
+            ActionOutcome::ProfileCopied(source, dest) => {
+                write!(f, "Copied profile {:?} to {:?}.", source, dest)
+            }
+            ActionOutcome::ProfileDeleted { name, backup } => {
+                write!(f, "Deleted profile {:?}, a backup was saved to {:?}.", name, backup)
+            }
+            ActionOutcome::ProfileRenamed(old, new) => {
+                write!(f, "Renamed profile {:?} to {:?}.", old, new)
+            }
+            ActionOutcome::ProfileRecovered { name, count } => write!(
+                f,
+                "Recovered profile {:?} by replaying {} logged {}.",
+                name,
+                count,
+                if *count == 1 { "action" } else { "actions" }
+            ),
+            ActionOutcome::DebugRan(input) => write!(f, "Finished debug command {:?}.", input),
+        }
+    }
+}
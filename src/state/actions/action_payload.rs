@@ -1,29 +1,131 @@
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
-use super::action_type::ActionType;
+use super::{action_type::ActionType, arg_count::ArgCount};
+use crate::{
+    input::completions::Shell,
+    todos::{priority::Priority, recurrence::Recurrence},
+};
 
 #[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Serialize, Deserialize, Clone)]
 pub enum ActionPayload {
     Add(String),
+    AddMany(Vec<String>),
+    /// Adds the second todo as a subtask of the first.
+    AddSub(String, String),
+    Age,
+    ApplyPatch(String),
+    /// Moves the priority of a todo up one level if `true`, or down one level if `false`.
+    Bump(String, bool),
     Clear,
+    ClearTags(Option<String>),
+    Color(String, String),
+    /// Replaces the given todos with a single todo.
+    Combine(Vec<String>),
+    Complete(Vec<String>),
+    /// Prints the text of every todo starting with the prefix given, for shell completion.
+    CompleteData(Option<String>),
+    /// Prints the completion script for the shell given.
+    Completions(Shell),
+    CopyProfile(String, String),
+    DeleteProfile(String),
+    /// Lists the differences between two profiles.
+    DiffProfiles(String, String),
+    Digest,
+    Dump,
+    Due(String, Option<NaiveDate>),
     Edit(String, String),
+    Export(String),
+    /// Lists the todos matching the regular expression given.
+    Grep(String),
+    Import(String),
+    /// Checks whether there are no todos, or no todos with the given status.
+    IsEmpty(Option<bool>),
     List,
+    /// Lists the todos created or completed after the time given.
+    ListSince(DateTime<Utc>),
     ListWithStatus(bool),
+    ListByDue,
+    ListByPriority(Priority),
+    Move(String, usize),
+    Note(String, Option<String>),
+    PurgeCompleted,
+    Recover,
     Remove(String),
+    RenameProfile(String, String),
+    /// Replaces the matches of a regular expression in the text of every todo.
+    Replace(String, String),
+    /// Replays the action log at the given path onto an empty list and compares the result
+    /// with the list, see [`TodoList::replay`](crate::todos::todolist::TodoList::replay).
+    Replay(String),
+    /// Keeps the list loaded and answers other instances over a socket, see
+    /// [`UnixService`](crate::service::unix_service::UnixService). This blocks until the
+    /// daemon is shut down, so it is run by the caller rather than applied to a list.
+    Serve,
     Set(String, bool),
+    SetPriority(String, Option<Priority>),
+    /// Sets how often a todo recurs, or stops it recurring if `None`.
+    SetRecurrence(String, Option<Recurrence>),
+    Show(String),
+    /// Replaces a todo with the given new todos.
+    Split(String, Vec<String>),
+    Stale(usize),
+    Tag(String, String),
+    Toggle(String),
+    /// Walks through the incomplete todos one at a time, asking what to do with each. This
+    /// prompts, so it is run by the caller rather than applied to a list.
+    Triage,
     Other(String),
 }
 
 impl ActionPayload {
     pub fn get_action_type(&self) -> ActionType {
         match self {
-            ActionPayload::Add(_) => ActionType::Add,
+            ActionPayload::Add(_) | ActionPayload::AddMany(_) => ActionType::Add,
+            ActionPayload::AddSub(_, _) => ActionType::AddSub,
+            ActionPayload::Age => ActionType::Age,
+            ActionPayload::ApplyPatch(_) => ActionType::ApplyPatch,
+            ActionPayload::Bump(_, _) => ActionType::Bump,
             ActionPayload::Clear => ActionType::Clear,
+            ActionPayload::ClearTags(_) => ActionType::ClearTags,
+            ActionPayload::Color(_, _) => ActionType::Color,
+            ActionPayload::Combine(_) => ActionType::Combine,
+            ActionPayload::Complete(_) => ActionType::Complete,
+            ActionPayload::CompleteData(_) => ActionType::CompleteData,
+            ActionPayload::Completions(_) => ActionType::Completions,
+            ActionPayload::CopyProfile(_, _) => ActionType::CopyProfile,
+            ActionPayload::DeleteProfile(_) => ActionType::DeleteProfile,
+            ActionPayload::DiffProfiles(_, _) => ActionType::DiffProfiles,
+            ActionPayload::Digest => ActionType::Digest,
+            ActionPayload::Dump => ActionType::Dump,
+            ActionPayload::Due(_, _) => ActionType::Due,
             ActionPayload::Edit(_, _) => ActionType::Edit,
-            ActionPayload::List => ActionType::List,
+            ActionPayload::Export(_) => ActionType::Export,
+            ActionPayload::Grep(_) => ActionType::Grep,
+            ActionPayload::Import(_) => ActionType::Import,
+            ActionPayload::IsEmpty(_) => ActionType::IsEmpty,
+            ActionPayload::List | ActionPayload::ListSince(_) => ActionType::List,
             ActionPayload::ListWithStatus(_) => ActionType::ListType,
+            ActionPayload::ListByDue => ActionType::ListByDue,
+            ActionPayload::ListByPriority(_) => ActionType::ListByPriority,
+            ActionPayload::Move(_, _) => ActionType::Move,
+            ActionPayload::Note(_, _) => ActionType::Note,
+            ActionPayload::PurgeCompleted => ActionType::PurgeCompleted,
+            ActionPayload::Recover => ActionType::Recover,
             ActionPayload::Remove(_) => ActionType::Remove,
+            ActionPayload::RenameProfile(_, _) => ActionType::RenameProfile,
+            ActionPayload::Replace(_, _) => ActionType::Replace,
+            ActionPayload::Replay(_) => ActionType::Replay,
+            ActionPayload::Serve => ActionType::Serve,
             ActionPayload::Set(_, _) => ActionType::Set,
+            ActionPayload::SetPriority(_, _) => ActionType::SetPriority,
+            ActionPayload::SetRecurrence(_, _) => ActionType::SetRecurrence,
+            ActionPayload::Show(_) => ActionType::Show,
+            ActionPayload::Split(_, _) => ActionType::Split,
+            ActionPayload::Stale(_) => ActionType::Stale,
+            ActionPayload::Tag(_, _) => ActionType::Tag,
+            ActionPayload::Toggle(_) => ActionType::Toggle,
+            ActionPayload::Triage => ActionType::Triage,
             ActionPayload::Other(_) => ActionType::Other,
         }
     }
@@ -32,7 +134,16 @@ impl ActionPayload {
         self.get_action_type().get_input_string()
     }
 
-    pub fn expected_arg_count(&self) -> usize {
+    pub fn expected_arg_count(&self) -> ArgCount {
         self.get_action_type().get_arg_count()
     }
+
+    /// Returns `true` if applying this action reads or writes a file other than the
+    /// database, such as the file given to `import` or `export`.
+    pub fn touches_files(&self) -> bool {
+        matches!(
+            self,
+            ActionPayload::ApplyPatch(_) | ActionPayload::Export(_) | ActionPayload::Import(_) | ActionPayload::Replay(_)
+        )
+    }
 }
\ No newline at end of file
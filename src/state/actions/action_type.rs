@@ -1,24 +1,69 @@
+use chrono::NaiveDate;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 
-use super::action_payload::ActionPayload;
+use super::{action_payload::ActionPayload, arg_count::ArgCount};
 
 use crate::{
-    input::{action_argument::ActionArgument, input_error::InputError},
-    utils::general::string_to_bool,
+    input::{action_argument::ActionArgument, completions::Shell, input_error::InputError},
+    todos::{priority::Priority, recurrence::Recurrence},
+    utils::general::{levenshtein, string_to_bool},
 };
 
+/// The furthest, in edits, a mistyped command may be from a known command for
+/// [ActionType::closest] to suggest it.
+pub const MAX_CORRECTION_DISTANCE: usize = 2;
+
 /// The types of Actions that can be done to a [TodoList]
 
 #[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Serialize, Deserialize, Clone, Copy)]
 pub enum ActionType {
     Add,
+    AddSub,
+    Age,
+    ApplyPatch,
+    Bump,
     Clear,
+    ClearTags,
+    Color,
+    Combine,
+    Complete,
+    CompleteData,
+    Completions,
+    CopyProfile,
+    DeleteProfile,
+    DiffProfiles,
+    Digest,
+    Dump,
+    Due,
     Edit,
+    Export,
+    Grep,
+    Import,
+    IsEmpty,
     List,
     ListType,
+    ListByDue,
+    ListByPriority,
+    Move,
+    Note,
+    PurgeCompleted,
+    Recover,
     Remove,
+    RenameProfile,
+    Replace,
+    Replay,
+    Serve,
     Set,
+    SetPriority,
+    SetRecurrence,
+    Show,
+    Split,
+    Stale,
+    Tag,
+    Toggle,
+    Triage,
     Other,
 }
 
@@ -26,26 +71,124 @@ impl ActionType {
     pub fn try_parse_cmd(s: &str) -> Result<Self, InputError> {
         match s {
             "add" => Ok(ActionType::Add),
+            "addsub" => Ok(ActionType::AddSub),
+            "age" => Ok(ActionType::Age),
+            "patch" => Ok(ActionType::ApplyPatch),
+            "bump" => Ok(ActionType::Bump),
             "clear" => Ok(ActionType::Clear),
+            "cleartags" => Ok(ActionType::ClearTags),
+            "color" => Ok(ActionType::Color),
+            "combine" => Ok(ActionType::Combine),
+            "complete" => Ok(ActionType::Complete),
+            "__complete" => Ok(ActionType::CompleteData),
+            "completions" => Ok(ActionType::Completions),
+            "profilecp" => Ok(ActionType::CopyProfile),
+            "profilerm" => Ok(ActionType::DeleteProfile),
+            "diffp" => Ok(ActionType::DiffProfiles),
+            "digest" => Ok(ActionType::Digest),
+            "dump" => Ok(ActionType::Dump),
+            "due" => Ok(ActionType::Due),
             "edit" => Ok(ActionType::Edit),
+            "export" => Ok(ActionType::Export),
+            "grep" => Ok(ActionType::Grep),
+            "import" => Ok(ActionType::Import),
+            "empty" => Ok(ActionType::IsEmpty),
             "ls" => Ok(ActionType::List),
             "lss" => Ok(ActionType::ListType),
+            "lsd" => Ok(ActionType::ListByDue),
+            "lsp" => Ok(ActionType::ListByPriority),
+            "mv" => Ok(ActionType::Move),
+            "note" => Ok(ActionType::Note),
+            "purge" => Ok(ActionType::PurgeCompleted),
+            "recover" => Ok(ActionType::Recover),
             "rm" => Ok(ActionType::Remove),
+            "profilemv" => Ok(ActionType::RenameProfile),
+            "replace" => Ok(ActionType::Replace),
+            "replay" => Ok(ActionType::Replay),
+            "serve" => Ok(ActionType::Serve),
             "set" => Ok(ActionType::Set),
+            "priority" => Ok(ActionType::SetPriority),
+            "recur" => Ok(ActionType::SetRecurrence),
+            "show" => Ok(ActionType::Show),
+            "split" => Ok(ActionType::Split),
+            "stale" => Ok(ActionType::Stale),
+            "tag" => Ok(ActionType::Tag),
+            "toggle" | "done" => Ok(ActionType::Toggle),
+            "triage" => Ok(ActionType::Triage),
             "" => Err(InputError::cmd_empty()),
-            _ => Err(InputError::cmd_unknown(s)),
+            _ => match ActionType::closest(s) {
+                Some((closest, _)) => Err(InputError::cmd_unknown_suggest(s, &closest.get_input_string())),
+                None => Err(InputError::cmd_unknown(s)),
+            },
         }
     }
 
+    /// Returns the action whose command is closest to the mistyped command `input`, along
+    /// with its distance in edits, or `None` if no command is within
+    /// [MAX_CORRECTION_DISTANCE] edits. Input shorter than the distance is never corrected,
+    /// and ties go to the command closest in length to `input`.
+    pub fn closest(input: &str) -> Option<(ActionType, usize)> {
+        let input = input.to_lowercase();
+        let input_len = input.chars().count();
+        ActionType::visible_actions()
+            .into_iter()
+            .map(|action| {
+                let cmd = action.get_input_string();
+                let length_diff = cmd.chars().count().abs_diff(input_len);
+                (action, levenshtein(&input, &cmd), length_diff)
+            })
+            .filter(|(_, distance, _)| *distance <= MAX_CORRECTION_DISTANCE && *distance < input_len)
+            .min_by_key(|(_, distance, length_diff)| (*distance, *length_diff))
+            .map(|(action, distance, _)| (action, distance))
+    }
+
     pub fn try_parse_name(s: &str) -> Result<Self, InputError> {
         match s {
             "Add" => Ok(ActionType::Add),
+            "AddSub" => Ok(ActionType::AddSub),
+            "Age" => Ok(ActionType::Age),
+            "ApplyPatch" => Ok(ActionType::ApplyPatch),
+            "Bump" => Ok(ActionType::Bump),
             "Clear" => Ok(ActionType::Clear),
+            "ClearTags" => Ok(ActionType::ClearTags),
+            "Color" => Ok(ActionType::Color),
+            "Combine" => Ok(ActionType::Combine),
+            "Complete" => Ok(ActionType::Complete),
+            "CompleteData" => Ok(ActionType::CompleteData),
+            "Completions" => Ok(ActionType::Completions),
+            "CopyProfile" => Ok(ActionType::CopyProfile),
+            "DeleteProfile" => Ok(ActionType::DeleteProfile),
+            "DiffProfiles" => Ok(ActionType::DiffProfiles),
+            "Digest" => Ok(ActionType::Digest),
+            "Dump" => Ok(ActionType::Dump),
+            "Due" => Ok(ActionType::Due),
             "Edit" => Ok(ActionType::Edit),
+            "Export" => Ok(ActionType::Export),
+            "Grep" => Ok(ActionType::Grep),
+            "Import" => Ok(ActionType::Import),
+            "IsEmpty" => Ok(ActionType::IsEmpty),
             "List" => Ok(ActionType::List),
             "ListType" => Ok(ActionType::ListType),
+            "ListByDue" => Ok(ActionType::ListByDue),
+            "ListByPriority" => Ok(ActionType::ListByPriority),
+            "Move" => Ok(ActionType::Move),
+            "Note" => Ok(ActionType::Note),
+            "PurgeCompleted" => Ok(ActionType::PurgeCompleted),
+            "Recover" => Ok(ActionType::Recover),
             "Remove" => Ok(ActionType::Remove),
+            "RenameProfile" => Ok(ActionType::RenameProfile),
+            "Replace" => Ok(ActionType::Replace),
+            "Replay" => Ok(ActionType::Replay),
+            "Serve" => Ok(ActionType::Serve),
             "Set" => Ok(ActionType::Set),
+            "SetPriority" => Ok(ActionType::SetPriority),
+            "SetRecurrence" => Ok(ActionType::SetRecurrence),
+            "Show" => Ok(ActionType::Show),
+            "Split" => Ok(ActionType::Split),
+            "Stale" => Ok(ActionType::Stale),
+            "Tag" => Ok(ActionType::Tag),
+            "Toggle" => Ok(ActionType::Toggle),
+            "Triage" => Ok(ActionType::Triage),
             _ => Err(InputError::bad_cmd_with(format!(
                 "Unknown action type {:?}",
                 s
@@ -53,24 +196,153 @@ impl ActionType {
         }
     }
 
-    pub fn try_create_payload(&self, args: &Vec<String>) -> Result<ActionPayload, InputError> {
-        if self.get_arg_count() != args.len() {
+    pub fn try_create_payload(&self, args: &[String]) -> Result<ActionPayload, InputError> {
+        if !self.get_arg_count().accepts(args.len()) {
             return Err(self.arg_count_error(args.len()));
         }
 
         match self {
             ActionType::Add => {
-                if let Some(add_value) = args.first() {
-                    if add_value.is_empty() {
-                        Err(InputError::bad_arg())
+                let todos: Vec<String> = args.iter().map(|add_value| add_value.trim().to_string()).collect();
+                if todos.iter().all(|add_value| add_value.is_empty()) {
+                    Err(InputError::bad_arg_str("Unable to add empty todo."))
+                } else if todos.len() == 1 {
+                    Ok(ActionPayload::Add(todos[0].clone()))
+                } else {
+                    Ok(ActionPayload::AddMany(todos))
+                }
+            }
+            ActionType::AddSub => {
+                if let (Some(parent), Some(todo)) = (args.first(), args.last()) {
+                    if parent.is_empty() || todo.trim().is_empty() {
+                        Err(InputError::bad_arg_str("Subtasks must be given a parent and a todo."))
                     } else {
-                        Ok(ActionPayload::Add(add_value.clone()))
+                        Ok(ActionPayload::AddSub(parent.clone(), todo.trim().to_string()))
                     }
                 } else {
-                    Err(InputError::bad_arg_str("Unable to add empty todo."))
+                    Err(InputError::bad_arg())
+                }
+            }
+            ActionType::Age => Ok(ActionPayload::Age),
+            ActionType::ApplyPatch => {
+                if let Some(path) = args.first() {
+                    if path.is_empty() {
+                        Err(InputError::bad_arg_str("Patch must be given a file path."))
+                    } else {
+                        Ok(ActionPayload::ApplyPatch(path.clone()))
+                    }
+                } else {
+                    Err(InputError::bad_arg())
+                }
+            }
+            ActionType::Bump => {
+                if let (Some(key), Some(direction)) = (args.first(), args.last()) {
+                    match direction.trim().to_lowercase().as_str() {
+                        _ if key.is_empty() => Err(InputError::bad_arg_str("Bump must be given a todo.")),
+                        "up" | "u" | "+" => Ok(ActionPayload::Bump(key.clone(), true)),
+                        "down" | "d" | "-" => Ok(ActionPayload::Bump(key.clone(), false)),
+                        _ => Err(InputError::bad_arg_str("Bump direction must be up or down.")),
+                    }
+                } else {
+                    Err(InputError::bad_arg())
                 }
             }
             ActionType::Clear => Ok(ActionPayload::Clear),
+            ActionType::ClearTags => Ok(ActionPayload::ClearTags(
+                args.first().filter(|tag| !tag.is_empty()).cloned(),
+            )),
+            ActionType::Color => {
+                if let (Some(key), Some(color)) = (args.first(), args.last()) {
+                    if key.is_empty() || color.is_empty() {
+                        Err(InputError::bad_arg_str("Color must be given a todo and a color name."))
+                    } else {
+                        Ok(ActionPayload::Color(key.clone(), color.clone()))
+                    }
+                } else {
+                    Err(InputError::bad_arg())
+                }
+            }
+            ActionType::Combine => {
+                if args.len() < 2 {
+                    Err(InputError::bad_arg_str("Combine must be given at least two todos."))
+                } else if args.iter().any(|todo| todo.is_empty()) {
+                    Err(InputError::bad_arg())
+                } else {
+                    Ok(ActionPayload::Combine(args.to_vec()))
+                }
+            }
+            ActionType::Complete => {
+                if args.iter().any(|todo| todo.is_empty()) {
+                    Err(InputError::bad_arg())
+                } else {
+                    Ok(ActionPayload::Complete(args.to_vec()))
+                }
+            }
+            ActionType::CompleteData => Ok(ActionPayload::CompleteData(
+                args.first().filter(|prefix| !prefix.is_empty()).cloned(),
+            )),
+            ActionType::Completions => match Shell::from_name(&args[0]) {
+                Some(shell) => Ok(ActionPayload::Completions(shell)),
+                None => Err(InputError::bad_arg_with(format!(
+                    "Unknown shell {:?}, expected one of {}.",
+                    args[0],
+                    Shell::all().iter().map(|shell| shell.to_string()).collect::<Vec<_>>().join(", ")
+                ))),
+            },
+            ActionType::CopyProfile => {
+                if let (Some(source), Some(dest)) = (args.first(), args.last()) {
+                    if source.is_empty() || dest.is_empty() {
+                        Err(InputError::bad_arg_str("Profile names cannot be empty."))
+                    } else {
+                        Ok(ActionPayload::CopyProfile(source.clone(), dest.clone()))
+                    }
+                } else {
+                    Err(InputError::bad_arg())
+                }
+            }
+            ActionType::DeleteProfile => {
+                if let Some(name) = args.first() {
+                    if name.is_empty() {
+                        Err(InputError::bad_arg_str("Profile name cannot be empty."))
+                    } else {
+                        Ok(ActionPayload::DeleteProfile(name.clone()))
+                    }
+                } else {
+                    Err(InputError::bad_arg())
+                }
+            }
+            ActionType::DiffProfiles => {
+                if let (Some(this), Some(that)) = (args.first(), args.last()) {
+                    if this.is_empty() || that.is_empty() {
+                        Err(InputError::bad_arg_str("Profile names cannot be empty."))
+                    } else {
+                        Ok(ActionPayload::DiffProfiles(this.clone(), that.clone()))
+                    }
+                } else {
+                    Err(InputError::bad_arg())
+                }
+            }
+            ActionType::Digest => Ok(ActionPayload::Digest),
+            ActionType::Dump => Ok(ActionPayload::Dump),
+            ActionType::Due => {
+                if let (Some(key), Some(date)) = (args.first(), args.last()) {
+                    if key.is_empty() {
+                        Err(InputError::bad_arg_str("Due must be given a todo."))
+                    } else if date.trim().is_empty() || date.trim().eq_ignore_ascii_case("none") {
+                        Ok(ActionPayload::Due(key.clone(), None))
+                    } else {
+                        match NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d") {
+                            Ok(due) => Ok(ActionPayload::Due(key.clone(), Some(due))),
+                            Err(_) => Err(InputError::bad_arg_with(format!(
+                                "{:?} is not a date, expected YYYY-MM-DD.",
+                                date
+                            ))),
+                        }
+                    }
+                } else {
+                    Err(InputError::bad_arg())
+                }
+            }
             ActionType::Edit => {
                 let existing = args.first();
                 let editted = args.last();
@@ -82,14 +354,53 @@ impl ActionType {
                 let ex_unw = existing.unwrap();
                 let ed_unw = editted.unwrap();
 
-                if ex_unw.is_empty() || ed_unw.is_empty() {
+                if ex_unw.is_empty() || ed_unw.trim().is_empty() {
                     return Err(InputError::bad_arg_str(
                         "Edit cannot be passed empty strings",
                     ));
                 }
 
-                Ok(ActionPayload::Edit(ex_unw.clone(), ed_unw.clone()))
+                Ok(ActionPayload::Edit(ex_unw.clone(), ed_unw.trim().to_string()))
+            }
+            ActionType::Export => {
+                if let Some(path) = args.first() {
+                    if path.is_empty() {
+                        Err(InputError::bad_arg_str("Export must be given a file path."))
+                    } else {
+                        Ok(ActionPayload::Export(path.clone()))
+                    }
+                } else {
+                    Err(InputError::bad_arg())
+                }
+            }
+            ActionType::Grep => match args.first() {
+                Some(pattern) if !pattern.is_empty() => match Regex::new(pattern) {
+                    Ok(_) => Ok(ActionPayload::Grep(pattern.clone())),
+                    Err(err) => Err(InputError::bad_arg_with(format!("Invalid pattern {:?}: {}", pattern, err))),
+                },
+                _ => Err(InputError::bad_arg_str("Grep must be given a pattern.")),
+            },
+            ActionType::Import => {
+                if let Some(path) = args.first() {
+                    if path.is_empty() {
+                        Err(InputError::bad_arg_str("Import must be given a file path."))
+                    } else {
+                        Ok(ActionPayload::Import(path.clone()))
+                    }
+                } else {
+                    Err(InputError::bad_arg())
+                }
             }
+            ActionType::IsEmpty => match args.first().filter(|status| !status.is_empty()) {
+                None => Ok(ActionPayload::IsEmpty(None)),
+                Some(status_raw) => match string_to_bool(status_raw) {
+                    Some(status) => Ok(ActionPayload::IsEmpty(Some(status))),
+                    None => Err(InputError::bad_arg_with(format!(
+                        "Unable to parse {:?} to valid boolean value.",
+                        status_raw
+                    ))),
+                },
+            },
             ActionType::List => Ok(ActionPayload::List),
             ActionType::ListType => {
                 if let Some(lss_value_raw) = args.first() {
@@ -105,6 +416,39 @@ impl ActionType {
                     Err(InputError::bad_arg())
                 }
             }
+            ActionType::ListByDue => Ok(ActionPayload::ListByDue),
+            ActionType::ListByPriority => match args.first().and_then(Priority::try_parse) {
+                Some(priority) => Ok(ActionPayload::ListByPriority(priority)),
+                None => Err(InputError::bad_arg_str("Priority must be one of low, medium or high.")),
+            },
+            ActionType::Move => {
+                if let (Some(key), Some(position_raw)) = (args.first(), args.last()) {
+                    match position_raw.parse::<usize>() {
+                        Ok(position) if position > 0 => Ok(ActionPayload::Move(key.clone(), position - 1)),
+                        _ => Err(InputError::bad_arg_with(format!(
+                            "Unable to parse {:?} to a valid position, positions start at 1.",
+                            position_raw
+                        ))),
+                    }
+                } else {
+                    Err(InputError::bad_arg())
+                }
+            }
+            ActionType::Note => {
+                if let (Some(key), Some(note)) = (args.first(), args.last()) {
+                    if key.is_empty() {
+                        Err(InputError::bad_arg_str("Note must be given a todo."))
+                    } else if note.trim().is_empty() {
+                        Ok(ActionPayload::Note(key.clone(), None))
+                    } else {
+                        Ok(ActionPayload::Note(key.clone(), Some(note.clone())))
+                    }
+                } else {
+                    Err(InputError::bad_arg())
+                }
+            }
+            ActionType::PurgeCompleted => Ok(ActionPayload::PurgeCompleted),
+            ActionType::Recover => Ok(ActionPayload::Recover),
             ActionType::Remove => {
                 if let Some(rm_value) = args.first() {
                     if rm_value.is_empty() {
@@ -116,9 +460,45 @@ impl ActionType {
                     Err(InputError::bad_arg())
                 }
             }
+            ActionType::RenameProfile => {
+                if let (Some(old), Some(new)) = (args.first(), args.last()) {
+                    if old.is_empty() || new.is_empty() {
+                        Err(InputError::bad_arg_str("Profile names cannot be empty."))
+                    } else {
+                        Ok(ActionPayload::RenameProfile(old.clone(), new.clone()))
+                    }
+                } else {
+                    Err(InputError::bad_arg())
+                }
+            }
+            ActionType::Replace => {
+                if let (Some(pattern), Some(replacement)) = (args.first(), args.last()) {
+                    match Regex::new(pattern) {
+                        _ if pattern.is_empty() => Err(InputError::bad_arg_str("Replace must be given a pattern.")),
+                        Ok(_) => Ok(ActionPayload::Replace(pattern.clone(), replacement.clone())),
+                        Err(err) => Err(InputError::bad_arg_with(format!("Invalid pattern {:?}: {}", pattern, err))),
+                    }
+                } else {
+                    Err(InputError::bad_arg())
+                }
+            }
+            ActionType::Replay => {
+                if let Some(path) = args.first() {
+                    if path.is_empty() {
+                        Err(InputError::bad_arg_str("Replay must be given an action log."))
+                    } else {
+                        Ok(ActionPayload::Replay(path.clone()))
+                    }
+                } else {
+                    Err(InputError::bad_arg())
+                }
+            }
+            ActionType::Serve => Ok(ActionPayload::Serve),
             ActionType::Set => {
                 if let (Some(set_key), Some(set_value_raw)) = (args.first(), args.last()) {
-                    if let Some(set_value) = string_to_bool(set_value_raw) {
+                    if set_key.trim().is_empty() {
+                        Err(InputError::bad_arg_str("Unable to set the status of an empty todo."))
+                    } else if let Some(set_value) = string_to_bool(set_value_raw) {
                         Ok(ActionPayload::Set(set_key.clone(), set_value))
                     } else {
                         Err(InputError::bad_arg_with(format!(
@@ -130,6 +510,93 @@ impl ActionType {
                     Err(InputError::bad_arg())
                 }
             }
+            ActionType::SetPriority => {
+                if let (Some(key), Some(priority)) = (args.first(), args.last()) {
+                    if key.is_empty() {
+                        Err(InputError::bad_arg_str("Priority must be given a todo."))
+                    } else if priority.trim().is_empty() || priority.trim().eq_ignore_ascii_case("none") {
+                        Ok(ActionPayload::SetPriority(key.clone(), None))
+                    } else {
+                        match Priority::try_parse(priority.trim()) {
+                            Some(priority) => Ok(ActionPayload::SetPriority(key.clone(), Some(priority))),
+                            None => Err(InputError::bad_arg_str("Priority must be one of low, medium, high or none.")),
+                        }
+                    }
+                } else {
+                    Err(InputError::bad_arg())
+                }
+            }
+            ActionType::SetRecurrence => {
+                if let (Some(key), Some(recurrence)) = (args.first(), args.last()) {
+                    if key.is_empty() {
+                        Err(InputError::bad_arg_str("Recur must be given a todo."))
+                    } else if recurrence.trim().is_empty() || recurrence.trim().eq_ignore_ascii_case("none") {
+                        Ok(ActionPayload::SetRecurrence(key.clone(), None))
+                    } else {
+                        match Recurrence::try_parse(recurrence) {
+                            Some(recurrence) => Ok(ActionPayload::SetRecurrence(key.clone(), Some(recurrence))),
+                            None => Err(InputError::bad_arg_str(
+                                "Recurrence must be daily, weekly, a number of days or none.",
+                            )),
+                        }
+                    }
+                } else {
+                    Err(InputError::bad_arg())
+                }
+            }
+            ActionType::Show => {
+                if let Some(key) = args.first() {
+                    if key.is_empty() {
+                        Err(InputError::bad_arg())
+                    } else {
+                        Ok(ActionPayload::Show(key.clone()))
+                    }
+                } else {
+                    Err(InputError::bad_arg())
+                }
+            }
+            ActionType::Split => match args.split_first() {
+                Some((key, parts)) if !key.is_empty() && !parts.is_empty() => {
+                    if parts.iter().any(|part| part.trim().is_empty()) {
+                        Err(InputError::bad_arg_str("Unable to split into an empty todo."))
+                    } else {
+                        Ok(ActionPayload::Split(key.clone(), parts.iter().map(|part| part.trim().to_string()).collect()))
+                    }
+                }
+                _ => Err(InputError::bad_arg_str("Split must be given a todo and the todos to split it into.")),
+            },
+            ActionType::Stale => match args.first().map(|days| days.trim().parse::<i64>()) {
+                Some(Ok(days)) if days < 0 => Err(InputError::bad_arg_str("Stale days cannot be negative.")),
+                Some(Ok(days)) => Ok(ActionPayload::Stale(days as usize)),
+                Some(Err(_)) => Err(InputError::bad_arg_with(format!(
+                    "{:?} is not a number of days.",
+                    args[0]
+                ))),
+                None => Err(InputError::bad_arg()),
+            },
+            ActionType::Tag => {
+                if let (Some(key), Some(tag)) = (args.first(), args.last()) {
+                    if key.is_empty() || tag.trim().is_empty() {
+                        Err(InputError::bad_arg_str("Tag must be given a todo and a non-empty tag."))
+                    } else {
+                        Ok(ActionPayload::Tag(key.clone(), tag.trim().to_string()))
+                    }
+                } else {
+                    Err(InputError::bad_arg())
+                }
+            }
+            ActionType::Toggle => {
+                if let Some(key) = args.first() {
+                    if key.is_empty() {
+                        Err(InputError::bad_arg())
+                    } else {
+                        Ok(ActionPayload::Toggle(key.clone()))
+                    }
+                } else {
+                    Err(InputError::bad_arg())
+                }
+            }
+            ActionType::Triage => Ok(ActionPayload::Triage),
             ActionType::Other => Ok(ActionPayload::Other(args.join(" "))),
         }
     }
@@ -137,18 +604,98 @@ impl ActionType {
     pub fn get_arguments(&self) -> Vec<ActionArgument> {
         match self {
             ActionType::Add => vec![ActionArgument::string("todo", 0)],
+            ActionType::AddSub => vec![
+                ActionArgument::existing("parent", 0),
+                ActionArgument::string("subtask", 1),
+            ],
+            ActionType::Age => vec![],
+            ActionType::ApplyPatch => vec![ActionArgument::string("patch file", 0)],
+            ActionType::Bump => vec![
+                ActionArgument::existing("todo", 0),
+                ActionArgument::string("direction (up or down)", 1),
+            ],
             ActionType::Clear => vec![],
+            ActionType::ClearTags => vec![ActionArgument::string("tag (empty for all tags)", 0)],
+            ActionType::Color => vec![
+                ActionArgument::existing("todo", 0),
+                ActionArgument::string("color", 1),
+            ],
+            ActionType::Combine => vec![ActionArgument::existing("todo", 0)],
+            ActionType::Complete => vec![ActionArgument::existing("todo", 0)],
+            ActionType::CompleteData => vec![ActionArgument::string("prefix (empty for all)", 0)],
+            ActionType::Completions => vec![ActionArgument::string("shell", 0)],
+            ActionType::CopyProfile => vec![
+                ActionArgument::string("profile", 0),
+                ActionArgument::string("copy name", 1),
+            ],
+            ActionType::DeleteProfile => vec![ActionArgument::string("profile", 0)],
+            ActionType::DiffProfiles => vec![
+                ActionArgument::string("profile", 0),
+                ActionArgument::string("other profile", 1),
+            ],
+            ActionType::Digest => vec![],
+            ActionType::Dump => vec![],
+            ActionType::Due => vec![
+                ActionArgument::existing("todo", 0),
+                ActionArgument::string("due date (YYYY-MM-DD, empty to clear)", 1),
+            ],
             ActionType::Edit => vec![
                 ActionArgument::existing("todo", 0),
                 ActionArgument::string("new text", 1),
             ],
+            ActionType::Export => vec![ActionArgument::string("file", 0)],
+            ActionType::Grep => vec![ActionArgument::string("pattern", 0)],
+            ActionType::Import => vec![ActionArgument::string("file", 0)],
+            ActionType::IsEmpty => vec![ActionArgument::boolean("status (empty for any)", 0)],
             ActionType::List => vec![],
             ActionType::ListType => vec![ActionArgument::boolean("status", 0)],
+            ActionType::ListByDue => vec![],
+            ActionType::ListByPriority => vec![ActionArgument::string("priority (low, medium or high)", 0)],
+            ActionType::Move => vec![
+                ActionArgument::existing("todo", 0),
+                ActionArgument::number("position", 1),
+            ],
+            ActionType::Note => vec![
+                ActionArgument::existing("todo", 0),
+                ActionArgument::string("note (empty to clear)", 1),
+            ],
+            ActionType::PurgeCompleted => vec![],
+            ActionType::Recover => vec![],
             ActionType::Remove => vec![ActionArgument::existing("todo", 0)],
+            ActionType::RenameProfile => vec![
+                ActionArgument::string("profile", 0),
+                ActionArgument::string("new name", 1),
+            ],
+            ActionType::Replace => vec![
+                ActionArgument::string("pattern", 0),
+                ActionArgument::string("replacement", 1),
+            ],
+            ActionType::Replay => vec![ActionArgument::string("action log", 0)],
+            ActionType::Serve => vec![],
             ActionType::Set => vec![
                 ActionArgument::existing("todo", 0),
                 ActionArgument::boolean("status", 1),
             ],
+            ActionType::SetPriority => vec![
+                ActionArgument::existing("todo", 0),
+                ActionArgument::string("priority (low, medium, high or none)", 1),
+            ],
+            ActionType::SetRecurrence => vec![
+                ActionArgument::existing("todo", 0),
+                ActionArgument::string("recurrence (daily, weekly or a number of days, empty to stop)", 1),
+            ],
+            ActionType::Show => vec![ActionArgument::existing("todo", 0)],
+            ActionType::Split => vec![
+                ActionArgument::existing("todo", 0),
+                ActionArgument::string("new todos", 1),
+            ],
+            ActionType::Stale => vec![ActionArgument::number("days", 0)],
+            ActionType::Tag => vec![
+                ActionArgument::existing("todo", 0),
+                ActionArgument::string("tag", 1),
+            ],
+            ActionType::Toggle => vec![ActionArgument::existing("todo", 0)],
+            ActionType::Triage => vec![],
             ActionType::Other => vec![ActionArgument::string("input", 0)],
         }
     }
@@ -156,12 +703,50 @@ impl ActionType {
     pub fn get_action_name(&self) -> String {
         match self {
             ActionType::Add => "Add".to_string(),
+            ActionType::AddSub => "AddSub".to_string(),
+            ActionType::Age => "Age".to_string(),
+            ActionType::ApplyPatch => "ApplyPatch".to_string(),
+            ActionType::Bump => "Bump".to_string(),
             ActionType::Clear => "Clear".to_string(),
+            ActionType::ClearTags => "ClearTags".to_string(),
+            ActionType::Color => "Color".to_string(),
+            ActionType::Combine => "Combine".to_string(),
+            ActionType::Complete => "Complete".to_string(),
+            ActionType::CompleteData => "CompleteData".to_string(),
+            ActionType::Completions => "Completions".to_string(),
+            ActionType::CopyProfile => "CopyProfile".to_string(),
+            ActionType::DeleteProfile => "DeleteProfile".to_string(),
+            ActionType::DiffProfiles => "DiffProfiles".to_string(),
+            ActionType::Digest => "Digest".to_string(),
+            ActionType::Dump => "Dump".to_string(),
+            ActionType::Due => "Due".to_string(),
             ActionType::Edit => "Edit".to_string(),
+            ActionType::Export => "Export".to_string(),
+            ActionType::Grep => "Grep".to_string(),
+            ActionType::Import => "Import".to_string(),
+            ActionType::IsEmpty => "IsEmpty".to_string(),
             ActionType::List => "List".to_string(),
             ActionType::ListType => "ListType".to_string(),
+            ActionType::ListByDue => "ListByDue".to_string(),
+            ActionType::ListByPriority => "ListByPriority".to_string(),
+            ActionType::Move => "Move".to_string(),
+            ActionType::Note => "Note".to_string(),
+            ActionType::PurgeCompleted => "PurgeCompleted".to_string(),
+            ActionType::Recover => "Recover".to_string(),
             ActionType::Remove => "Remove".to_string(),
+            ActionType::RenameProfile => "RenameProfile".to_string(),
+            ActionType::Replace => "Replace".to_string(),
+            ActionType::Replay => "Replay".to_string(),
+            ActionType::Serve => "Serve".to_string(),
             ActionType::Set => "Set".to_string(),
+            ActionType::SetPriority => "SetPriority".to_string(),
+            ActionType::SetRecurrence => "SetRecurrence".to_string(),
+            ActionType::Show => "Show".to_string(),
+            ActionType::Split => "Split".to_string(),
+            ActionType::Stale => "Stale".to_string(),
+            ActionType::Tag => "Tag".to_string(),
+            ActionType::Toggle => "Toggle".to_string(),
+            ActionType::Triage => "Triage".to_string(),
             ActionType::Other => "Other".to_string(),
         }
     }
@@ -169,46 +754,252 @@ impl ActionType {
     pub fn get_input_string(&self) -> String {
         match self {
             ActionType::Add => "add".to_string(),
+            ActionType::AddSub => "addsub".to_string(),
+            ActionType::Age => "age".to_string(),
+            ActionType::ApplyPatch => "patch".to_string(),
+            ActionType::Bump => "bump".to_string(),
             ActionType::Clear => "clear".to_string(),
+            ActionType::ClearTags => "cleartags".to_string(),
+            ActionType::Color => "color".to_string(),
+            ActionType::Combine => "combine".to_string(),
+            ActionType::Complete => "complete".to_string(),
+            ActionType::CompleteData => "__complete".to_string(),
+            ActionType::Completions => "completions".to_string(),
+            ActionType::CopyProfile => "profilecp".to_string(),
+            ActionType::DeleteProfile => "profilerm".to_string(),
+            ActionType::DiffProfiles => "diffp".to_string(),
+            ActionType::Digest => "digest".to_string(),
+            ActionType::Dump => "dump".to_string(),
+            ActionType::Due => "due".to_string(),
             ActionType::Edit => "edit".to_string(),
+            ActionType::Export => "export".to_string(),
+            ActionType::Grep => "grep".to_string(),
+            ActionType::Import => "import".to_string(),
+            ActionType::IsEmpty => "empty".to_string(),
             ActionType::List => "ls".to_string(),
             ActionType::ListType => "lss".to_string(),
+            ActionType::ListByDue => "lsd".to_string(),
+            ActionType::ListByPriority => "lsp".to_string(),
+            ActionType::Move => "mv".to_string(),
+            ActionType::Note => "note".to_string(),
+            ActionType::PurgeCompleted => "purge".to_string(),
+            ActionType::Recover => "recover".to_string(),
             ActionType::Remove => "rm".to_string(),
+            ActionType::RenameProfile => "profilemv".to_string(),
+            ActionType::Replace => "replace".to_string(),
+            ActionType::Replay => "replay".to_string(),
+            ActionType::Serve => "serve".to_string(),
             ActionType::Set => "set".to_string(),
+            ActionType::SetPriority => "priority".to_string(),
+            ActionType::SetRecurrence => "recur".to_string(),
+            ActionType::Show => "show".to_string(),
+            ActionType::Split => "split".to_string(),
+            ActionType::Stale => "stale".to_string(),
+            ActionType::Tag => "tag".to_string(),
+            ActionType::Toggle => "toggle".to_string(),
+            ActionType::Triage => "triage".to_string(),
             ActionType::Other => "secret".to_string(),
         }
     }
 
-    pub fn get_arg_count(&self) -> usize {
+    /// Returns how many arguments this action accepts. Most actions take an exact number of
+    /// arguments, but `add` and `complete` act on each of their arguments and the tag given
+    /// to `cleartags` is optional.
+    pub fn get_arg_count(&self) -> ArgCount {
         match self {
-            ActionType::Add => 1,
-            ActionType::Clear => 0,
-            ActionType::Edit => 2,
-            ActionType::List => 0,
-            ActionType::ListType => 1,
-            ActionType::Remove => 1,
-            ActionType::Set => 2,
-            ActionType::Other => 1,
+            ActionType::Add => ArgCount::AtLeast(1),
+            ActionType::AddSub => ArgCount::Exact(2),
+            ActionType::Age => ArgCount::Exact(0),
+            ActionType::ApplyPatch => ArgCount::Exact(1),
+            ActionType::Bump => ArgCount::Exact(2),
+            ActionType::Clear => ArgCount::Exact(0),
+            ActionType::ClearTags => ArgCount::AtMost(1),
+            ActionType::Color => ArgCount::Exact(2),
+            ActionType::Combine => ArgCount::AtLeast(2),
+            ActionType::Complete => ArgCount::AtLeast(1),
+            ActionType::CompleteData => ArgCount::AtMost(1),
+            ActionType::Completions => ArgCount::Exact(1),
+            ActionType::CopyProfile => ArgCount::Exact(2),
+            ActionType::DeleteProfile => ArgCount::Exact(1),
+            ActionType::DiffProfiles => ArgCount::Exact(2),
+            ActionType::Digest => ArgCount::Exact(0),
+            ActionType::Dump => ArgCount::Exact(0),
+            ActionType::Due => ArgCount::Exact(2),
+            ActionType::Edit => ArgCount::Exact(2),
+            ActionType::Export => ArgCount::Exact(1),
+            ActionType::Grep => ArgCount::Exact(1),
+            ActionType::Import => ArgCount::Exact(1),
+            ActionType::IsEmpty => ArgCount::AtMost(1),
+            ActionType::List => ArgCount::Exact(0),
+            ActionType::ListType => ArgCount::Exact(1),
+            ActionType::ListByDue => ArgCount::Exact(0),
+            ActionType::ListByPriority => ArgCount::Exact(1),
+            ActionType::Move => ArgCount::Exact(2),
+            ActionType::Note => ArgCount::Exact(2),
+            ActionType::PurgeCompleted => ArgCount::Exact(0),
+            ActionType::Recover => ArgCount::Exact(0),
+            ActionType::Remove => ArgCount::Exact(1),
+            ActionType::RenameProfile => ArgCount::Exact(2),
+            ActionType::Replace => ArgCount::Exact(2),
+            ActionType::Replay => ArgCount::Exact(1),
+            ActionType::Serve => ArgCount::Exact(0),
+            ActionType::Set => ArgCount::Exact(2),
+            ActionType::SetPriority => ArgCount::Exact(2),
+            ActionType::SetRecurrence => ArgCount::Exact(2),
+            ActionType::Show => ArgCount::Exact(1),
+            ActionType::Split => ArgCount::AtLeast(2),
+            ActionType::Stale => ArgCount::Exact(1),
+            ActionType::Tag => ArgCount::Exact(2),
+            ActionType::Toggle => ArgCount::Exact(1),
+            ActionType::Triage => ArgCount::Exact(0),
+            ActionType::Other => ArgCount::Exact(1),
         }
     }
 
     pub fn arg_count_error(&self, input_count: usize) -> InputError {
-        InputError::InvalidArgument(Some(format!("Invalid argument count - the {:?} command expects {:?} argument{}, but {:?} {} received.", self.get_input_string(), self.get_arg_count(), if self.get_arg_count() > 1 { "s" } else { "" }, input_count, if input_count == 1 { "was" } else { "were" })))
+        InputError::InvalidArgument(Some(format!("Invalid argument count - the {:?} command expects {}, but {:?} {} received.", self.get_input_string(), self.get_arg_count(), input_count, if input_count == 1 { "was" } else { "were" })))
+    }
+
+    /// Returns `true` if this action operates on profile database files rather than on
+    /// a loaded [TodoList], see [`crate::todos::profile::Profile::apply_action`].
+    pub fn is_profile_action(&self) -> bool {
+        matches!(
+            self,
+            ActionType::CopyProfile
+                | ActionType::DeleteProfile
+                | ActionType::DiffProfiles
+                | ActionType::Recover
+                | ActionType::RenameProfile
+        )
+    }
+
+    /// Returns `true` if this action deletes todos in bulk, which is worth taking a backup
+    /// before doing.
+    pub fn is_destructive(&self) -> bool {
+        matches!(self, ActionType::Clear | ActionType::PurgeCompleted)
+    }
+
+    /// Returns `true` if this action can change the todos or profiles, `false` for queries
+    /// that only read them. Nothing needs saving after a query. The match is exhaustive so
+    /// that every new action has to be classified.
+    pub fn is_mutating(&self) -> bool {
+        match self {
+            ActionType::Age
+            | ActionType::CompleteData
+            | ActionType::Completions
+            | ActionType::DiffProfiles
+            | ActionType::Digest
+            | ActionType::Dump
+            | ActionType::Export
+            | ActionType::Grep
+            | ActionType::IsEmpty
+            | ActionType::List
+            | ActionType::ListType
+            | ActionType::ListByDue
+            | ActionType::ListByPriority
+            | ActionType::Replay
+            | ActionType::Show
+            | ActionType::Stale
+            | ActionType::Other => false,
+            ActionType::Add
+            | ActionType::AddSub
+            | ActionType::ApplyPatch
+            | ActionType::Bump
+            | ActionType::Clear
+            | ActionType::ClearTags
+            | ActionType::Color
+            | ActionType::Combine
+            | ActionType::Complete
+            | ActionType::CopyProfile
+            | ActionType::DeleteProfile
+            | ActionType::Due
+            | ActionType::Edit
+            | ActionType::Import
+            | ActionType::Move
+            | ActionType::Note
+            | ActionType::PurgeCompleted
+            | ActionType::Recover
+            | ActionType::Remove
+            | ActionType::RenameProfile
+            | ActionType::Replace
+            | ActionType::Serve
+            | ActionType::Set
+            | ActionType::SetPriority
+            | ActionType::SetRecurrence
+            | ActionType::Split
+            | ActionType::Tag
+            | ActionType::Toggle
+            | ActionType::Triage => true,
+        }
+    }
+
+    /// Returns `true` if this action is meant for scripts rather than people, such as
+    /// feeding shell completion. Hidden actions can be run by name but are never offered
+    /// in the REPL or suggested for a mistyped command.
+    pub fn is_hidden(&self) -> bool {
+        matches!(self, ActionType::CompleteData | ActionType::Completions)
     }
 
     pub fn all_actions() -> Vec<Self> {
         vec![
             ActionType::Add,
+            ActionType::AddSub,
+            ActionType::Age,
+            ActionType::ApplyPatch,
+            ActionType::Bump,
             ActionType::Clear,
+            ActionType::ClearTags,
+            ActionType::Color,
+            ActionType::Combine,
+            ActionType::Complete,
+            ActionType::CompleteData,
+            ActionType::Completions,
+            ActionType::CopyProfile,
+            ActionType::DeleteProfile,
+            ActionType::DiffProfiles,
+            ActionType::Digest,
+            ActionType::Dump,
+            ActionType::Due,
             ActionType::Edit,
+            ActionType::Export,
+            ActionType::Grep,
+            ActionType::Import,
+            ActionType::IsEmpty,
             ActionType::List,
             ActionType::ListType,
+            ActionType::ListByDue,
+            ActionType::ListByPriority,
+            ActionType::Move,
+            ActionType::Note,
+            ActionType::PurgeCompleted,
+            ActionType::Recover,
             ActionType::Remove,
+            ActionType::RenameProfile,
+            ActionType::Replace,
+            ActionType::Replay,
+            ActionType::Serve,
             ActionType::Set,
+            ActionType::SetPriority,
+            ActionType::SetRecurrence,
+            ActionType::Show,
+            ActionType::Split,
+            ActionType::Stale,
+            ActionType::Tag,
+            ActionType::Toggle,
+            ActionType::Triage,
             ActionType::Other,
         ]
     }
 
+    /// Returns every action that is meant for people, leaving out the hidden actions and
+    /// [ActionType::Other].
+    pub fn visible_actions() -> Vec<Self> {
+        ActionType::all_actions()
+            .into_iter()
+            .filter(|action| *action != ActionType::Other && !action.is_hidden())
+            .collect()
+    }
+
     pub fn all_action_names() -> Vec<String> {
         ActionType::all_actions()
             .iter_mut()
@@ -216,3 +1007,97 @@ impl ActionType {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(raw: &[&str]) -> Vec<String> {
+        raw.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn queries_are_not_mutating() {
+        let queries = [
+            ActionType::Age,
+            ActionType::CompleteData,
+            ActionType::Completions,
+            ActionType::DiffProfiles,
+            ActionType::Digest,
+            ActionType::Dump,
+            ActionType::Export,
+            ActionType::Grep,
+            ActionType::IsEmpty,
+            ActionType::List,
+            ActionType::ListType,
+            ActionType::ListByDue,
+            ActionType::ListByPriority,
+            ActionType::Replay,
+            ActionType::Show,
+            ActionType::Stale,
+            ActionType::Other,
+        ];
+        for action in ActionType::all_actions() {
+            assert_eq!(action.is_mutating(), !queries.contains(&action), "{:?}", action);
+        }
+    }
+
+    #[test]
+    fn add_accepts_many_todos() {
+        assert_eq!(
+            ActionType::Add.try_create_payload(&args(&["a"])),
+            Ok(ActionPayload::Add("a".to_string()))
+        );
+        assert_eq!(
+            ActionType::Add.try_create_payload(&args(&["a", "b"])),
+            Ok(ActionPayload::AddMany(args(&["a", "b"])))
+        );
+        assert!(ActionType::Add.try_create_payload(&args(&[])).is_err());
+        assert_eq!(
+            ActionType::Add.try_create_payload(&args(&["a", ""])),
+            Ok(ActionPayload::AddMany(args(&["a", ""])))
+        );
+        assert!(ActionType::Add.try_create_payload(&args(&["", " "])).is_err());
+        assert!(ActionType::Add.try_create_payload(&args(&["  "])).is_err());
+        assert!(ActionType::Edit.try_create_payload(&args(&["a", "  "])).is_err());
+        assert!(ActionType::Set.try_create_payload(&args(&[" ", "true"])).is_err());
+        assert_eq!(
+            ActionType::Edit.try_create_payload(&args(&["a", " b "])),
+            Ok(ActionPayload::Edit("a".to_string(), "b".to_string()))
+        );
+        assert_eq!(
+            ActionType::Add.try_create_payload(&args(&[" a "])),
+            Ok(ActionPayload::Add("a".to_string()))
+        );
+        assert!(ActionType::Remove.try_create_payload(&args(&["a", "b"])).is_err());
+    }
+
+    #[test]
+    fn closest_corrects_typos() {
+        assert_eq!(ActionType::closest("lst"), Some((ActionType::ListType, 1)));
+        assert_eq!(ActionType::closest("ad"), Some((ActionType::Add, 1)));
+        assert_eq!(ActionType::closest("clera"), Some((ActionType::Clear, 2)));
+        assert_eq!(ActionType::closest("Exprot"), Some((ActionType::Export, 2)));
+        assert_eq!(ActionType::closest("purg"), Some((ActionType::PurgeCompleted, 1)));
+
+        assert_eq!(ActionType::closest("x"), None);
+        assert_eq!(ActionType::closest("frobnicate"), None);
+        assert_eq!(
+            ActionType::try_parse_cmd("lst"),
+            Err(InputError::cmd_unknown_suggest("lst", "lss"))
+        );
+    }
+
+    #[test]
+    fn completions_rejects_unknown_shell() {
+        assert_eq!(
+            ActionType::Completions.try_create_payload(&args(&["Bash"])),
+            Ok(ActionPayload::Completions(Shell::Bash))
+        );
+        assert!(matches!(
+            ActionType::Completions.try_create_payload(&args(&["powershell"])),
+            Err(InputError::InvalidArgument(Some(msg))) if msg.contains("bash, fish, zsh")
+        ));
+        assert_eq!(ActionType::closest("completion"), None);
+    }
+}
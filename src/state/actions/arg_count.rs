@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+/// The number of arguments an [ActionType](super::action_type::ActionType) accepts.
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Serialize, Deserialize, Clone, Copy)]
+pub enum ArgCount {
+    /// Exactly this many arguments.
+    Exact(usize),
+    /// This many arguments or more.
+    AtLeast(usize),
+    /// This many arguments or fewer, used for optional arguments.
+    AtMost(usize),
+}
+
+impl ArgCount {
+    /// Returns `true` if `count` arguments are acceptable.
+    pub fn accepts(&self, count: usize) -> bool {
+        match self {
+            ArgCount::Exact(n) => count == *n,
+            ArgCount::AtLeast(n) => count >= *n,
+            ArgCount::AtMost(n) => count <= *n,
+        }
+    }
+
+    /// Returns the fewest arguments that are acceptable.
+    pub fn min(&self) -> usize {
+        match self {
+            ArgCount::Exact(n) | ArgCount::AtLeast(n) => *n,
+            ArgCount::AtMost(_) => 0,
+        }
+    }
+}
+
+impl std::fmt::Display for ArgCount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (prefix, n) = match self {
+            ArgCount::Exact(n) => ("", n),
+            ArgCount::AtLeast(n) => ("at least ", n),
+            ArgCount::AtMost(n) => ("at most ", n),
+        };
+
+        write!(f, "{}{} argument{}", prefix, n, if *n == 1 { "" } else { "s" })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_counts() {
+        assert!(ArgCount::Exact(2).accepts(2));
+        assert!(!ArgCount::Exact(2).accepts(3));
+        assert!(ArgCount::AtLeast(1).accepts(1));
+        assert!(ArgCount::AtLeast(1).accepts(5));
+        assert!(!ArgCount::AtLeast(1).accepts(0));
+        assert!(ArgCount::AtMost(1).accepts(0));
+        assert!(!ArgCount::AtMost(1).accepts(2));
+    }
+}
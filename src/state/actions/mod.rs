@@ -1,2 +1,4 @@
+pub mod action_outcome;
 pub mod action_payload;
-pub mod action_type;
\ No newline at end of file
+pub mod action_type;
+pub mod arg_count;
\ No newline at end of file
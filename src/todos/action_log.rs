@@ -0,0 +1,84 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::Path,
+};
+
+use super::command_error::CommandError;
+use crate::state::actions::{action_outcome::ActionOutcome, action_payload::ActionPayload};
+
+/// Stateless struct used to group functions operating on action logs. An action log is an
+/// append-only JSON Lines file holding every [ActionPayload] that changed a profile, one
+/// per line, oldest first. Replaying a log onto an empty list rebuilds the profile, see
+/// [`TodoList::rebuild_from_log`](super::todolist::TodoList::rebuild_from_log).
+pub struct ActionLog;
+
+impl ActionLog {
+    /// Appends `action` to the log at `log`, creating the log if it does not exist.
+    pub fn append<TLog: AsRef<Path>>(log: TLog, action: &ActionPayload) -> Result<(), CommandError> {
+        let mut file = OpenOptions::new().create(true).append(true).open(log)?;
+        let line = serde_json::to_string(action).map_err(|err| CommandError::IoError(err.to_string()))?;
+        writeln!(file, "{}", line)?;
+
+        Ok(())
+    }
+
+    /// Appends `action` to the log at `log` if its `outcome` shows that it changed the list.
+    pub fn record<TLog: AsRef<Path>>(
+        log: TLog,
+        action: &ActionPayload,
+        outcome: &ActionOutcome,
+    ) -> Result<(), CommandError> {
+        if outcome.is_change() {
+            ActionLog::append(log, action)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads every action in the log at `log`, oldest first. Blank lines are skipped.
+    pub fn read<TLog: AsRef<Path>>(log: TLog) -> Result<Vec<ActionPayload>, CommandError> {
+        let reader = BufReader::new(File::open(log)?);
+        let mut actions = Vec::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let action = serde_json::from_str(&line).map_err(|err| {
+                CommandError::InputInvalid(format!(
+                    "Malformed action log on line {}: {}",
+                    index + 1,
+                    err
+                ))
+            })?;
+            actions.push(action);
+        }
+
+        Ok(actions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::temp_dir;
+
+    #[test]
+    fn records_only_changes() {
+        let log = temp_dir("action-log-record").join("work.actions.jsonl");
+        let add = ActionPayload::Add("one".to_string());
+
+        ActionLog::record(&log, &add, &ActionOutcome::Added("one".to_string())).unwrap();
+        ActionLog::record(&log, &ActionPayload::List, &ActionOutcome::Listing(String::new())).unwrap();
+        ActionLog::record(
+            &log,
+            &ActionPayload::Clear,
+            &ActionOutcome::NeedsConfirmation("Sure?".to_string()),
+        )
+        .unwrap();
+
+        assert_eq!(ActionLog::read(&log), Ok(vec![add]));
+    }
+}
@@ -0,0 +1,420 @@
+use std::{
+    cell::Ref,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use chrono::Utc;
+
+use super::{
+    action_log::ActionLog,
+    command_error::CommandError,
+    profile::Profile,
+    stats::{StatsReport, STATS_FILE},
+    todolist::TodoList,
+};
+use crate::{
+    config::settings::{DEFAULT_BACKUP_RETENTION, DEFAULT_LOCK_TIMEOUT_MS},
+    state::actions::{action_outcome::ActionOutcome, action_payload::ActionPayload},
+    utils::{lock::DatabaseLock, timed::TimedValue},
+};
+
+/// The default amount of time a [CachedTodoList] holds on to its list before reloading it.
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// A [TodoList] loaded from a profile and cached in memory for a configurable TTL, after
+/// which it is reloaded from disk. Every action applied through
+/// [CachedTodoList::apply_action] marks the cache dirty and refreshes its expiration. A
+/// dirty cache is saved before it is reloaded so changes are never thrown away, and
+/// [CachedTodoList::flush] saves it on demand. Unless disabled with
+/// [CachedTodoList::with_backup], the profile is backed up before destructive actions,
+/// keeping the number of backups set with [CachedTodoList::with_backup_retention].
+/// Actions that change the list are recorded in the profile's [ActionLog]. The profile is
+/// locked with a [DatabaseLock] while it is saved, see [CachedTodoList::with_lock_timeout].
+/// Before an action is applied the list is also reloaded if another instance saved the
+/// profile since it was loaded, so that long lived caches such as the daemon's don't
+/// overwrite changes made elsewhere.
+pub struct CachedTodoList {
+    dir: PathBuf,
+    profile: String,
+    ttl: Duration,
+    cache: TimedValue<TodoList>,
+    dirty: bool,
+    backup: bool,
+    backup_retention: usize,
+    lock_timeout: Duration,
+    date_format: Option<String>,
+    status_symbols: Option<(String, String)>,
+    normalize_tags: bool,
+    case_insensitive_duplicates: bool,
+    colorize: bool,
+    stats_log: bool,
+    passphrase: Option<String>,
+    /// When the profile's database was last modified as of the last load or save.
+    disk_modified: Option<SystemTime>,
+}
+
+impl CachedTodoList {
+    /// Loads the profile `profile` inside `dir` and caches it for `ttl`. The `ttl` should be
+    /// non-zero, otherwise every read will reload the list.
+    pub fn load<TDir: AsRef<Path>, TName: AsRef<str>>(
+        dir: TDir,
+        profile: TName,
+        ttl: Duration,
+    ) -> Result<Self, String> {
+        CachedTodoList::load_with(dir, profile, ttl, None)
+    }
+
+    /// Loads the profile `profile` inside `dir` like [`CachedTodoList::load`], decrypting it
+    /// with `passphrase`, see [`TodoList::load_from_profile_with`].
+    pub fn load_with<TDir: AsRef<Path>, TName: AsRef<str>>(
+        dir: TDir,
+        profile: TName,
+        ttl: Duration,
+        passphrase: Option<String>,
+    ) -> Result<Self, String> {
+        let list = TodoList::load_from_profile_with(&dir, &profile, passphrase.as_deref())?;
+        let disk_modified = modified_on_disk(&dir, &profile);
+
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+            profile: profile.as_ref().to_string(),
+            ttl,
+            cache: TimedValue::new(list, Instant::now() + ttl),
+            dirty: false,
+            backup: true,
+            backup_retention: DEFAULT_BACKUP_RETENTION,
+            lock_timeout: Duration::from_millis(DEFAULT_LOCK_TIMEOUT_MS),
+            date_format: None,
+            status_symbols: None,
+            normalize_tags: false,
+            case_insensitive_duplicates: false,
+            colorize: false,
+            stats_log: false,
+            passphrase,
+            disk_modified,
+        })
+    }
+
+    /// Sets whether the profile is backed up before destructive actions are applied.
+    pub fn with_backup(mut self, backup: bool) -> Self {
+        self.backup = backup;
+        self
+    }
+
+    /// Sets the number of backups of the profile that are kept, see
+    /// [`Profile::prune_backups`]. `0` keeps every backup.
+    pub fn with_backup_retention(mut self, retention: usize) -> Self {
+        self.backup_retention = retention;
+        self
+    }
+
+    /// Sets how long saving waits for another instance to release its lock on the profile
+    /// before failing, see [`DatabaseLock::acquire`].
+    pub fn with_lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = timeout;
+        self
+    }
+
+    /// Sets the chrono format string dates are rendered in, see [`TodoList::set_date_format`].
+    pub fn with_date_format<S: AsRef<str>>(mut self, format: S) -> Self {
+        if let Some(mut list) = self.cache.value_mut() {
+            list.set_date_format(&format);
+        }
+        self.date_format = Some(format.as_ref().to_string());
+        self
+    }
+
+    /// Sets the symbols complete and incomplete todos are marked with, see
+    /// [`TodoList::set_status_symbols`].
+    pub fn with_status_symbols<S: AsRef<str>>(mut self, complete: S, incomplete: S) -> Self {
+        if let Some(mut list) = self.cache.value_mut() {
+            list.set_status_symbols(&complete, &incomplete);
+        }
+        self.status_symbols = Some((complete.as_ref().to_string(), incomplete.as_ref().to_string()));
+        self
+    }
+
+    /// Sets whether added todos are checked for duplicates ignoring case and surrounding
+    /// whitespace, see [`TodoList::set_case_insensitive_duplicates`].
+    pub fn with_case_insensitive_duplicates(mut self, case_insensitive: bool) -> Self {
+        if let Some(mut list) = self.cache.value_mut() {
+            list.set_case_insensitive_duplicates(case_insensitive);
+        }
+        self.case_insensitive_duplicates = case_insensitive;
+        self
+    }
+
+    /// Sets whether the listings of applied actions are colored, see
+    /// [`TodoList::set_colorize`].
+    pub fn with_colorize(mut self, colorize: bool) -> Self {
+        self.set_colorize(colorize);
+        self
+    }
+
+    /// Sets whether the listings of applied actions are colored, for a cache that is shared
+    /// by clients that each decide for themselves, such as the daemon's.
+    pub fn set_colorize(&mut self, colorize: bool) {
+        if let Some(mut list) = self.cache.value_mut() {
+            list.set_colorize(colorize);
+        }
+        self.colorize = colorize;
+    }
+
+    /// Sets whether the tags of the list are normalized whenever it is loaded, see
+    /// [`TodoList::normalize_tags`]. Merging tags counts as an unsaved change.
+    pub fn with_tag_normalization(mut self, normalize: bool) -> Self {
+        self.normalize_tags = normalize;
+        if normalize {
+            if let Some(mut list) = self.cache.value_mut() {
+                self.dirty |= list.normalize_tags() > 0;
+            }
+        }
+        self
+    }
+
+    /// Sets whether a [StatsReport] is appended to the [STATS_FILE] in the profile's
+    /// directory after each save.
+    pub fn with_stats_log(mut self, stats_log: bool) -> Self {
+        self.stats_log = stats_log;
+        self
+    }
+
+    /// Returns `true` if the cached list has changes that have not been saved.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Returns the cached list, reloading it from disk first if it has expired.
+    pub fn list(&mut self) -> Result<Ref<'_, TodoList>, String> {
+        self.refresh()?;
+        self.cache
+            .value()
+            .ok_or_else(|| "Cached Todo-List expired before it could be read".to_string())
+    }
+
+    pub fn len(&mut self) -> Result<usize, String> {
+        self.list().map(|list| list.len())
+    }
+
+    pub fn is_empty(&mut self) -> Result<bool, String> {
+        self.list().map(|list| list.is_empty())
+    }
+
+    pub fn get_todos_text(&mut self) -> Result<Vec<String>, String> {
+        self.list()
+            .map(|list| list.get_todos_text().into_iter().cloned().collect())
+    }
+
+    pub fn get_todos_with_status(&mut self, status: bool) -> Result<Vec<String>, String> {
+        self.list()
+            .map(|list| list.get_todos_with_status(status).into_iter().cloned().collect())
+    }
+
+    /// Applies `action` to the cached list, see [`TodoList::apply_action`]. On success the
+    /// cache is marked dirty and its expiration is refreshed.
+    pub fn apply_action(&mut self, action: ActionPayload) -> Result<ActionOutcome, CommandError> {
+        self.apply_action_with(action, false)
+    }
+
+    /// Applies `action` to the cached list, skipping any confirmation it would otherwise
+    /// need, see [`TodoList::apply_confirmed`].
+    pub fn apply_confirmed(&mut self, action: ActionPayload) -> Result<ActionOutcome, CommandError> {
+        self.apply_action_with(action, true)
+    }
+
+    fn apply_action_with(&mut self, action: ActionPayload, confirmed: bool) -> Result<ActionOutcome, CommandError> {
+        if !self.dirty && modified_on_disk(&self.dir, &self.profile) != self.disk_modified {
+            self.cache.set_expiration(Instant::now());
+        }
+        self.refresh().map_err(CommandError::IoError)?;
+        let applies = confirmed || self.list().map_or(true, |list| list.needs_confirmation(&action).is_none());
+        if applies {
+            self.backup_before(&action)?;
+        }
+
+        let logged = action.clone();
+        let outcome = match self.cache.value_mut() {
+            Some(mut list) if confirmed => list.apply_confirmed(action)?,
+            Some(mut list) => list.apply_action(action)?,
+            None => {
+                return Err(CommandError::IoError(
+                    "Cached Todo-List expired before it could be modified".to_string(),
+                ))
+            }
+        };
+
+        self.dirty = true;
+        self.cache.set_expiration(Instant::now() + self.ttl);
+        ActionLog::record(Profile::log_path_for(&self.dir, &self.profile), &logged, &outcome)?;
+        Ok(outcome)
+    }
+
+    /// Saves the cached list to disk if it has unsaved changes.
+    pub fn flush(&mut self) -> Result<(), String> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        let _lock = DatabaseLock::acquire(Profile::lock_path_for(&self.dir, &self.profile), self.lock_timeout)
+            .map_err(|err| err.to_string())?;
+
+        // The cached value may have expired, so take it out of the cache to save it rather
+        // than going through `TimedValue::value`.
+        let list = self.cache.replace(TodoList::new());
+        let saved = list.save_to_profile(&self.dir, &self.profile);
+        let report = StatsReport::of(&list, Utc::now());
+        self.cache.set_value(list);
+        saved?;
+        self.disk_modified = modified_on_disk(&self.dir, &self.profile);
+
+        if self.stats_log {
+            report.append(self.dir.join(STATS_FILE)).map_err(|err| err.to_string())?;
+        }
+
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Backs up the profile, including any unsaved changes, if `action` is destructive and
+    /// backups are enabled. Returns the path of the backup if one was taken.
+    fn backup_before(&mut self, action: &ActionPayload) -> Result<Option<PathBuf>, CommandError> {
+        if !self.backup || !action.get_action_type().is_destructive() {
+            return Ok(None);
+        }
+
+        self.flush().map_err(CommandError::IoError)?;
+        if !Profile::exists(&self.dir, &self.profile) {
+            return Ok(None);
+        }
+
+        let backup = Profile::backup(&self.dir, &self.profile)?;
+        Profile::prune_backups(&self.dir, &self.profile, self.backup_retention)?;
+        Ok(Some(backup))
+    }
+
+    /// Reloads the list from disk if the cache has expired, saving any unsaved changes first.
+    fn refresh(&mut self) -> Result<(), String> {
+        if self.cache.valid() {
+            return Ok(());
+        }
+
+        self.flush()?;
+        let mut list = TodoList::load_from_profile_with(&self.dir, &self.profile, self.passphrase.as_deref())?;
+        self.disk_modified = modified_on_disk(&self.dir, &self.profile);
+        if let Some(format) = &self.date_format {
+            list.set_date_format(format);
+        }
+        if let Some((complete, incomplete)) = &self.status_symbols {
+            list.set_status_symbols(complete, incomplete);
+        }
+        list.set_case_insensitive_duplicates(self.case_insensitive_duplicates);
+        list.set_colorize(self.colorize);
+        if self.normalize_tags {
+            self.dirty |= list.normalize_tags() > 0;
+        }
+        self.cache.set_value(list);
+        self.cache.set_expiration(Instant::now() + self.ttl);
+        Ok(())
+    }
+}
+
+/// Returns when the database of the profile `profile` inside `dir` was last modified, or
+/// `None` if it does not exist.
+fn modified_on_disk<TDir: AsRef<Path>, TName: AsRef<str>>(dir: TDir, profile: TName) -> Option<SystemTime> {
+    std::fs::metadata(Profile::path_for(dir, profile))
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::temp_dir;
+
+    #[test]
+    fn reloads_after_expiry() {
+        let dir = temp_dir("cached-reload");
+        let mut list = TodoList::new();
+        list.add_todo("one", false).unwrap();
+        list.save_to_profile(&dir, "work").unwrap();
+
+        let mut cached = CachedTodoList::load(&dir, "work", Duration::from_millis(50)).unwrap();
+        assert_eq!(cached.len(), Ok(1));
+
+        list.add_todo("two", false).unwrap();
+        list.save_to_profile(&dir, "work").unwrap();
+        assert_eq!(cached.len(), Ok(1));
+
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(cached.len(), Ok(2));
+    }
+
+    #[test]
+    fn appends_stats_row_per_save() {
+        let dir = temp_dir("cached-stats");
+        TodoList::new().save_to_profile(&dir, "work").unwrap();
+
+        let mut cached = CachedTodoList::load(&dir, "work", DEFAULT_CACHE_TTL).unwrap().with_stats_log(true);
+        cached.apply_action(ActionPayload::Add("one".to_string())).unwrap();
+        cached.flush().unwrap();
+        cached.apply_action(ActionPayload::Add("two".to_string())).unwrap();
+        cached.apply_action(ActionPayload::Set("two".to_string(), true)).unwrap();
+        cached.flush().unwrap();
+
+        let stats = std::fs::read_to_string(dir.join(STATS_FILE)).unwrap();
+        let lines: Vec<&str> = stats.lines().collect();
+        assert_eq!(lines.len(), 3, "{}", stats);
+        assert_eq!(lines[0], "timestamp,total,completed,incomplete");
+        assert!(lines[1].ends_with(",1,0,1"), "{}", stats);
+        assert!(lines[2].ends_with(",2,1,1"), "{}", stats);
+    }
+
+    #[test]
+    fn saves_dirty_list_before_reload() {
+        let dir = temp_dir("cached-dirty");
+        TodoList::new().save_to_profile(&dir, "work").unwrap();
+
+        let mut cached = CachedTodoList::load(&dir, "work", Duration::from_millis(50)).unwrap();
+        cached.apply_action(ActionPayload::Add("one".to_string())).unwrap();
+        assert!(cached.is_dirty());
+
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(cached.len(), Ok(1));
+        assert!(!cached.is_dirty());
+        assert_eq!(TodoList::load_from_profile(&dir, "work").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn reloads_changes_saved_elsewhere_before_applying() {
+        let dir = temp_dir("cached-external");
+        TodoList::new().save_to_profile(&dir, "work").unwrap();
+        let mut cached = CachedTodoList::load(&dir, "work", DEFAULT_CACHE_TTL).unwrap();
+
+        std::thread::sleep(Duration::from_millis(20));
+        let mut elsewhere = TodoList::new();
+        elsewhere.add_todo("one", false).unwrap();
+        elsewhere.save_to_profile(&dir, "work").unwrap();
+
+        cached.apply_action(ActionPayload::Add("two".to_string())).unwrap();
+        cached.flush().unwrap();
+        assert_eq!(TodoList::load_from_profile(&dir, "work").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn backs_up_before_destructive_actions() {
+        let dir = temp_dir("cached-backup");
+        TodoList::new().save_to_profile(&dir, "work").unwrap();
+
+        let mut cached = CachedTodoList::load(&dir, "work", DEFAULT_CACHE_TTL).unwrap();
+        cached.apply_action(ActionPayload::Add("one".to_string())).unwrap();
+        assert_eq!(cached.backup_before(&ActionPayload::List), Ok(None));
+
+        let backup = cached.backup_before(&ActionPayload::PurgeCompleted).unwrap().unwrap();
+        assert_eq!(TodoList::load_from_profile(&dir, "work").unwrap().len(), 1);
+        assert!(backup.is_file());
+
+        let mut cached = cached.with_backup(false);
+        assert_eq!(cached.backup_before(&ActionPayload::PurgeCompleted), Ok(None));
+    }
+}
@@ -5,14 +5,32 @@ pub enum CommandError {
     TodoAlreadyExists,
     TodoNotFound,
     InputInvalid(String),
+    ProfileNotFound(String),
+    ProfileAlreadyExists(String),
+    IoError(String),
 }
 
 impl CommandError {
-    pub fn to_string(&self) -> String {
+    fn message(&self) -> String {
         match self {
             CommandError::TodoAlreadyExists => "Todo already exists with that name".to_string(),
             CommandError::TodoNotFound => "Todo with that name not found".to_string(),
             CommandError::InputInvalid(msg) => format!("Input invalid, {}", msg),
+            CommandError::ProfileNotFound(name) => format!("Profile {:?} not found", name),
+            CommandError::ProfileAlreadyExists(name) => format!("Profile {:?} already exists", name),
+            CommandError::IoError(msg) => format!("I/O error, {}", msg),
         }
     }
-}
\ No newline at end of file
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl From<std::io::Error> for CommandError {
+    fn from(err: std::io::Error) -> Self {
+        CommandError::IoError(err.to_string())
+    }
+}
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    input::input_error::InputError,
+    state::actions::{action_outcome::ActionOutcome, action_payload::ActionPayload, action_type::ActionType},
+};
+
+/// A follow-up offered by `--interactive` once a CLI action has been applied, so related
+/// changes can be made before the list is saved. Each follow-up other than
+/// [`FollowUp::Done`] asks for one value and turns it into an action, see
+/// [`FollowUp::action_for`].
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Serialize, Deserialize, Clone)]
+pub enum FollowUp {
+    /// Add another todo.
+    AddAnother,
+    /// Tag the todo given.
+    Tag(String),
+    /// Set the priority of the todo given.
+    Priority(String),
+    /// Set the due date of the todo given.
+    Due(String),
+    /// Stop following up and save.
+    Done,
+}
+
+impl FollowUp {
+    /// Returns the follow-ups offered after an action with `outcome`, the last always being
+    /// [`FollowUp::Done`]. An outcome without follow-ups gives none, which ends the loop.
+    pub fn offered_after(outcome: &ActionOutcome) -> Vec<FollowUp> {
+        let todo = match outcome {
+            ActionOutcome::Added(todo) | ActionOutcome::AddedSub(_, todo) => {
+                return vec![
+                    FollowUp::AddAnother,
+                    FollowUp::Tag(todo.clone()),
+                    FollowUp::Priority(todo.clone()),
+                    FollowUp::Due(todo.clone()),
+                    FollowUp::Done,
+                ]
+            }
+            ActionOutcome::Edited(_, todo) => todo,
+            _ => return Vec::new(),
+        };
+
+        vec![
+            FollowUp::Tag(todo.clone()),
+            FollowUp::Priority(todo.clone()),
+            FollowUp::Due(todo.clone()),
+            FollowUp::Done,
+        ]
+    }
+
+    /// Returns the follow-ups to offer once this one has been applied with `outcome`.
+    /// Follow-ups that change a todo, such as tagging it, keep offering the `current` ones
+    /// so that several changes can be made to the same todo.
+    pub fn next(current: Vec<FollowUp>, outcome: &ActionOutcome) -> Vec<FollowUp> {
+        match FollowUp::offered_after(outcome) {
+            next if next.is_empty() => current,
+            next => next,
+        }
+    }
+
+    /// Returns the prompt for the value this follow-up needs, or `None` for [`FollowUp::Done`].
+    pub fn prompt(&self) -> Option<String> {
+        match self {
+            FollowUp::AddAnother => Some("Text of the new todo".to_string()),
+            FollowUp::Tag(todo) => Some(format!("Tag for {:?}", todo)),
+            FollowUp::Priority(todo) => Some(format!("Priority for {:?}", todo)),
+            FollowUp::Due(todo) => Some(format!("Due date for {:?} (YYYY-MM-DD)", todo)),
+            FollowUp::Done => None,
+        }
+    }
+
+    /// Returns the action carrying out this follow-up with the `value` entered, parsed like
+    /// the arguments of the matching command. [`FollowUp::Done`] has no action.
+    pub fn action_for(&self, value: &str) -> Result<Option<ActionPayload>, InputError> {
+        let (action, args) = match self {
+            FollowUp::AddAnother => (ActionType::Add, vec![value.to_string()]),
+            FollowUp::Tag(todo) => (ActionType::Tag, vec![todo.clone(), value.to_string()]),
+            FollowUp::Priority(todo) => (ActionType::SetPriority, vec![todo.clone(), value.to_string()]),
+            FollowUp::Due(todo) => (ActionType::Due, vec![todo.clone(), value.to_string()]),
+            FollowUp::Done => return Ok(None),
+        };
+
+        action.try_create_payload(&args).map(Some)
+    }
+}
+
+impl std::fmt::Display for FollowUp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FollowUp::AddAnother => write!(f, "Add another todo"),
+            FollowUp::Tag(todo) => write!(f, "Tag {:?}", todo),
+            FollowUp::Priority(todo) => write!(f, "Set the priority of {:?}", todo),
+            FollowUp::Due(todo) => write!(f, "Set the due date of {:?}", todo),
+            FollowUp::Done => write!(f, "Done"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follow_ups_after_add() {
+        let offered = FollowUp::offered_after(&ActionOutcome::Added("buy milk".to_string()));
+        assert_eq!(
+            offered,
+            vec![
+                FollowUp::AddAnother,
+                FollowUp::Tag("buy milk".to_string()),
+                FollowUp::Priority("buy milk".to_string()),
+                FollowUp::Due("buy milk".to_string()),
+                FollowUp::Done,
+            ]
+        );
+        assert!(FollowUp::offered_after(&ActionOutcome::Removed("buy milk".to_string())).is_empty());
+
+        let tagged = ActionOutcome::Tagged("buy milk".to_string(), "errand".to_string());
+        assert_eq!(FollowUp::next(offered.clone(), &tagged), offered);
+        let added = ActionOutcome::Added("buy eggs".to_string());
+        assert_eq!(FollowUp::next(offered, &added)[1], FollowUp::Tag("buy eggs".to_string()));
+
+        assert_eq!(
+            FollowUp::Tag("buy milk".to_string()).action_for("errand"),
+            Ok(Some(ActionPayload::Tag("buy milk".to_string(), "errand".to_string())))
+        );
+        assert!(FollowUp::Due("buy milk".to_string()).action_for("tomorrow").is_err());
+        assert_eq!(FollowUp::Done.action_for(""), Ok(None));
+    }
+}
@@ -1,2 +1,15 @@
+pub mod action_log;
+pub mod cached_todolist;
 pub mod command_error;
-pub mod todolist;
\ No newline at end of file
+pub mod follow_up;
+pub mod priority;
+pub mod profile;
+pub mod recurrence;
+pub mod render_options;
+pub mod run_state;
+pub mod stats;
+pub mod todo;
+pub mod todo_patch;
+pub mod todolist;
+pub mod todolist_builder;
+pub mod triage;
\ No newline at end of file
@@ -0,0 +1,111 @@
+use owo_colors::{AnsiColors, OwoColorize};
+use serde::{Deserialize, Serialize};
+
+/// How important a todo is. Priorities are ordered from [Priority::Low] up to
+/// [Priority::High] by the order of their variants, so keep them declared lowest first.
+/// They are serialized by name rather than by that order.
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Serialize, Deserialize, Clone, Copy)]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl Priority {
+    pub fn all() -> [Priority; 3] {
+        [Priority::Low, Priority::Medium, Priority::High]
+    }
+
+    /// Parses a [Priority] from its name, ignoring case. Also accepts the first letter of
+    /// the name, i.e. `h`, `m`, or `l`.
+    pub fn try_parse<S: AsRef<str>>(s: S) -> Option<Priority> {
+        match s.as_ref().to_lowercase().as_str() {
+            "l" | "low" => Some(Priority::Low),
+            "m" | "medium" => Some(Priority::Medium),
+            "h" | "high" => Some(Priority::High),
+            _ => None,
+        }
+    }
+
+    /// Returns the next level up from this one, staying at [Priority::High].
+    pub fn raised(&self) -> Priority {
+        match self {
+            Priority::Low => Priority::Medium,
+            Priority::Medium | Priority::High => Priority::High,
+        }
+    }
+
+    /// Returns the next level down from this one, staying at [Priority::Low].
+    pub fn lowered(&self) -> Priority {
+        match self {
+            Priority::High => Priority::Medium,
+            Priority::Medium | Priority::Low => Priority::Low,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Priority::Low => "Low",
+            Priority::Medium => "Medium",
+            Priority::High => "High",
+        }
+    }
+}
+
+impl std::fmt::Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+/// Returns the color `priority` is displayed with. Every view that shows a priority goes
+/// through this so that they all agree.
+pub fn priority_style(priority: Priority) -> AnsiColors {
+    match priority {
+        Priority::High => AnsiColors::Red,
+        Priority::Medium => AnsiColors::Yellow,
+        Priority::Low => AnsiColors::Green,
+    }
+}
+
+/// Returns the label for `priority`, colored with [priority_style] if `colorize` is `true`.
+pub fn priority_label(priority: Priority, colorize: bool) -> String {
+    if colorize {
+        priority.label().color(priority_style(priority)).to_string()
+    } else {
+        priority.label().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn styles_map_to_intended_colors() {
+        assert!(matches!(priority_style(Priority::High), AnsiColors::Red));
+        assert!(matches!(priority_style(Priority::Medium), AnsiColors::Yellow));
+        assert!(matches!(priority_style(Priority::Low), AnsiColors::Green));
+    }
+
+    #[test]
+    fn priorities_order_from_low_to_high() {
+        assert!(Priority::High > Priority::Medium);
+        assert!(Priority::Medium > Priority::Low);
+
+        let mut priorities = vec![Priority::Medium, Priority::High, Priority::Low, Priority::High];
+        priorities.sort_by(|a, b| b.cmp(a));
+        assert_eq!(priorities, vec![Priority::High, Priority::High, Priority::Medium, Priority::Low]);
+
+        assert_eq!(serde_json::to_string(&Priority::High).unwrap(), r#""High""#);
+        assert_eq!(serde_json::from_str::<Priority>(r#""Low""#).unwrap(), Priority::Low);
+    }
+
+    #[test]
+    fn uncolored_label_is_plain() {
+        for priority in Priority::all() {
+            assert_eq!(priority_label(priority, false), priority.label());
+            assert_ne!(priority_label(priority, true), priority.label());
+        }
+    }
+}
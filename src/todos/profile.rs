@@ -0,0 +1,519 @@
+use std::path::{Path, PathBuf};
+
+use super::{
+    action_log::ActionLog,
+    command_error::CommandError,
+    todolist::{DiffResult, TodoList, DEFAULT_ENCODING},
+};
+use crate::{
+    state::actions::{action_outcome::ActionOutcome, action_payload::ActionPayload},
+    utils::cereal::EncodingType,
+};
+
+/// The name of the profile used when no other profile is requested.
+pub const DEFAULT_PROFILE: &str = "data";
+
+/// The name of the directory, relative to the data directory, that holds profile backups.
+pub const BACKUP_DIR: &str = "backups";
+
+/// Stateless struct used to group functions operating on profiles. A profile is a
+/// named [TodoList] database, stored in the data directory as `{name}.{ext}` where
+/// `ext` is the file extension of the [EncodingType] it was saved with, usually
+/// [DEFAULT_ENCODING]. Backups of a profile live in
+/// `{BACKUP_DIR}/{name}/`, and its [ActionLog] in `{name}.actions.jsonl`.
+pub struct Profile;
+
+impl Profile {
+    /// Returns the path of the database file for the profile `name` inside `dir`, in the
+    /// encoding given by [`Profile::encoding_of`].
+    pub fn path_for<TDir: AsRef<Path>, TName: AsRef<str>>(dir: TDir, name: TName) -> PathBuf {
+        let encoding = Profile::encoding_of(&dir, &name);
+        Profile::path_for_encoding(dir, name, encoding)
+    }
+
+    /// Returns the path the profile `name` inside `dir` has when saved with `encoding`.
+    pub fn path_for_encoding<TDir: AsRef<Path>, TName: AsRef<str>>(
+        dir: TDir,
+        name: TName,
+        encoding: EncodingType,
+    ) -> PathBuf {
+        dir.as_ref()
+            .join(format!("{}.{}", name.as_ref(), encoding.get_file_ext()))
+    }
+
+    /// Returns the encoding the profile `name` inside `dir` was saved with, judged by the
+    /// extension of its database file. Returns [DEFAULT_ENCODING] if the profile does not
+    /// exist yet.
+    pub fn encoding_of<TDir: AsRef<Path>, TName: AsRef<str>>(dir: TDir, name: TName) -> EncodingType {
+        std::iter::once(DEFAULT_ENCODING)
+            .chain(EncodingType::all())
+            .find(|encoding| Profile::path_for_encoding(&dir, &name, *encoding).is_file())
+            .unwrap_or(DEFAULT_ENCODING)
+    }
+
+    /// Returns the path of the directory holding backups for the profile `name` inside `dir`.
+    pub fn backup_dir_for<TDir: AsRef<Path>, TName: AsRef<str>>(dir: TDir, name: TName) -> PathBuf {
+        dir.as_ref().join(BACKUP_DIR).join(name.as_ref())
+    }
+
+    /// Returns the path of the [ActionLog] for the profile `name` inside `dir`.
+    pub fn log_path_for<TDir: AsRef<Path>, TName: AsRef<str>>(dir: TDir, name: TName) -> PathBuf {
+        dir.as_ref().join(format!("{}.actions.jsonl", name.as_ref()))
+    }
+
+    /// Returns the path of the lock file for the profile `name` inside `dir`, see
+    /// [DatabaseLock](crate::utils::lock::DatabaseLock).
+    pub fn lock_path_for<TDir: AsRef<Path>, TName: AsRef<str>>(dir: TDir, name: TName) -> PathBuf {
+        dir.as_ref().join(format!("{}.lock", name.as_ref()))
+    }
+
+    /// Returns the path of the socket the daemon serving the profile `name` inside `dir`
+    /// listens on, see [UnixService](crate::service::unix_service::UnixService).
+    pub fn socket_path_for<TDir: AsRef<Path>, TName: AsRef<str>>(dir: TDir, name: TName) -> PathBuf {
+        dir.as_ref().join(format!("{}.sock", name.as_ref()))
+    }
+
+    /// Returns the path of the [RunState](super::run_state::RunState) for the profile `name`
+    /// inside `dir`.
+    pub fn state_path_for<TDir: AsRef<Path>, TName: AsRef<str>>(dir: TDir, name: TName) -> PathBuf {
+        dir.as_ref().join(format!("{}.state.json", name.as_ref()))
+    }
+
+    /// Returns `true` if a database file exists for the profile `name` inside `dir`.
+    pub fn exists<TDir: AsRef<Path>, TName: AsRef<str>>(dir: TDir, name: TName) -> bool {
+        Profile::path_for(dir, name).is_file()
+    }
+
+    /// Validates that `name` can be used as a profile name. Profile names cannot be empty
+    /// and cannot contain path separators.
+    pub fn validate_name<TName: AsRef<str>>(name: TName) -> Result<(), CommandError> {
+        let name = name.as_ref();
+        if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+            return Err(CommandError::InputInvalid(format!(
+                "{:?} is not a valid profile name",
+                name
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Renames the profile `old` to `new` inside `dir`, moving its backups and action log
+    /// along with it.
+    /// Fails if `old` does not exist or if `new` already exists.
+    pub fn rename<TDir: AsRef<Path>>(dir: TDir, old: &str, new: &str) -> Result<(), CommandError> {
+        let dir = dir.as_ref();
+        Profile::validate_name(old)?;
+        Profile::validate_name(new)?;
+
+        if !Profile::exists(dir, old) {
+            return Err(CommandError::ProfileNotFound(old.to_string()));
+        }
+
+        let old_backups = Profile::backup_dir_for(dir, old);
+        let new_backups = Profile::backup_dir_for(dir, new);
+        if Profile::exists(dir, new) || (old_backups.exists() && new_backups.exists()) {
+            return Err(CommandError::ProfileAlreadyExists(new.to_string()));
+        }
+
+        let encoding = Profile::encoding_of(dir, old);
+        std::fs::rename(
+            Profile::path_for_encoding(dir, old, encoding),
+            Profile::path_for_encoding(dir, new, encoding),
+        )?;
+        if old_backups.exists() {
+            std::fs::rename(old_backups, new_backups)?;
+        }
+        if Profile::log_path_for(dir, old).exists() {
+            std::fs::rename(Profile::log_path_for(dir, old), Profile::log_path_for(dir, new))?;
+        }
+
+        Ok(())
+    }
+
+    /// Copies the profile `source` to a new profile `dest` inside `dir` by loading the source
+    /// list and saving it under the new name, along with its action log. Fails if `dest`
+    /// already exists.
+    pub fn copy<TDir: AsRef<Path>>(dir: TDir, source: &str, dest: &str) -> Result<(), CommandError> {
+        let dir = dir.as_ref();
+        Profile::validate_name(source)?;
+        Profile::validate_name(dest)?;
+
+        if !Profile::exists(dir, source) {
+            return Err(CommandError::ProfileNotFound(source.to_string()));
+        }
+
+        if Profile::exists(dir, dest) {
+            return Err(CommandError::ProfileAlreadyExists(dest.to_string()));
+        }
+
+        TodoList::load_from_profile(dir, source)
+            .and_then(|list| list.save_to_profile(dir, dest))
+            .map_err(CommandError::IoError)?;
+        if Profile::log_path_for(dir, source).exists() {
+            std::fs::copy(Profile::log_path_for(dir, source), Profile::log_path_for(dir, dest))?;
+        }
+
+        Ok(())
+    }
+
+    /// Copies the database file of the profile `name` into its backup directory, returning
+    /// the path of the new backup. Backups are named by the time they were taken so that
+    /// they sort oldest-first.
+    pub fn backup<TDir: AsRef<Path>>(dir: TDir, name: &str) -> Result<PathBuf, CommandError> {
+        let dir = dir.as_ref();
+        if !Profile::exists(dir, name) {
+            return Err(CommandError::ProfileNotFound(name.to_string()));
+        }
+
+        let backup_dir = Profile::backup_dir_for(dir, name);
+        std::fs::create_dir_all(&backup_dir)?;
+        let backup = backup_dir.join(format!(
+            "{}.{}",
+            chrono::Utc::now().format("%Y%m%d%H%M%S%3f"),
+            Profile::encoding_of(dir, name).get_file_ext()
+        ));
+        std::fs::copy(Profile::path_for(dir, name), &backup)?;
+
+        Ok(backup)
+    }
+
+    /// Deletes the oldest backups of the profile `name` inside `dir` so that at most
+    /// `retention` are kept, see [`Profile::backups_to_prune`]. Returns the paths deleted.
+    pub fn prune_backups<TDir: AsRef<Path>>(
+        dir: TDir,
+        name: &str,
+        retention: usize,
+    ) -> Result<Vec<PathBuf>, CommandError> {
+        let backup_dir = Profile::backup_dir_for(dir, name);
+        if !backup_dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut backups = Vec::new();
+        for entry in std::fs::read_dir(&backup_dir)? {
+            let path = entry?.path();
+            if path.is_file() {
+                backups.push(path);
+            }
+        }
+
+        let pruned = Profile::backups_to_prune(backups, retention);
+        for path in &pruned {
+            std::fs::remove_file(path)?;
+        }
+
+        Ok(pruned)
+    }
+
+    /// Returns the backups in `backups` that fall outside the newest `retention`, oldest
+    /// first. Backups are named after the time they were taken, see [`Profile::backup`], so
+    /// they are ordered by file name. A `retention` of `0` keeps every backup.
+    pub fn backups_to_prune(mut backups: Vec<PathBuf>, retention: usize) -> Vec<PathBuf> {
+        if retention == 0 || backups.len() <= retention {
+            return Vec::new();
+        }
+
+        backups.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+        backups.truncate(backups.len() - retention);
+        backups
+    }
+
+    /// Deletes the profile `name` inside `dir` and its action log after backing it up,
+    /// returning the path of the backup. The `active` profile, the one currently in use,
+    /// cannot be deleted.
+    pub fn delete<TDir: AsRef<Path>>(dir: TDir, name: &str, active: &str) -> Result<PathBuf, CommandError> {
+        let dir = dir.as_ref();
+        Profile::validate_name(name)?;
+
+        if name == active {
+            return Err(CommandError::InputInvalid(format!(
+                "profile {:?} is currently active and cannot be deleted",
+                name
+            )));
+        }
+
+        let backup = Profile::backup(dir, name)?;
+        std::fs::remove_file(Profile::path_for(dir, name))?;
+        if Profile::log_path_for(dir, name).exists() {
+            std::fs::remove_file(Profile::log_path_for(dir, name))?;
+        }
+
+        Ok(backup)
+    }
+
+    /// Rebuilds the profile `name` inside `dir` by replaying its [ActionLog], for when its
+    /// database is corrupt. The existing database is backed up first if there is one.
+    /// Returns the number of actions replayed.
+    /// Compares the profile `this` with the profile `that`, see [`TodoList::diff_with`].
+    pub fn diff<TDir: AsRef<Path>>(dir: TDir, this: &str, that: &str) -> Result<DiffResult, CommandError> {
+        let dir = dir.as_ref();
+        let load = |name: &str| {
+            Profile::validate_name(name)?;
+            if !Profile::exists(dir, name) {
+                return Err(CommandError::ProfileNotFound(name.to_string()));
+            }
+            TodoList::load_from_profile(dir, name).map_err(CommandError::IoError)
+        };
+
+        let this_list = load(this)?;
+        Ok(this_list.diff_with(&load(that)?))
+    }
+
+    /// Renders the result of [`Profile::diff`] for the profiles `this` and `that`, ending
+    /// with its [summary](DiffResult::summary), colored if `colorize` is `true`.
+    pub fn render_diff(this: &str, that: &str, diff: &DiffResult, colorize: bool) -> String {
+        match diff {
+            DiffResult::Same => format!("Profiles {:?} and {:?} are the same.\n", this, that),
+            DiffResult::Changes(changes) => {
+                let mut output = format!(
+                    "Comparing {:?} (this) with {:?} (that), {} differences\n",
+                    this,
+                    that,
+                    changes.len()
+                );
+                for change in changes {
+                    output.push_str(&format!("\t{}\n", change));
+                }
+                output.push_str(&format!("{}\n", diff.summary().render(colorize)));
+                output
+            }
+        }
+    }
+
+    pub fn recover<TDir: AsRef<Path>>(dir: TDir, name: &str) -> Result<usize, CommandError> {
+        let dir = dir.as_ref();
+        let log = Profile::log_path_for(dir, name);
+        if !log.exists() {
+            return Err(CommandError::InputInvalid(format!(
+                "profile {:?} has no action log to recover from",
+                name
+            )));
+        }
+
+        let count = ActionLog::read(&log)?.len();
+        let list = TodoList::rebuild_from_log(&log)?;
+        if Profile::exists(dir, name) {
+            Profile::backup(dir, name)?;
+        }
+        list.save_to_profile(dir, name).map_err(CommandError::IoError)?;
+
+        Ok(count)
+    }
+
+    /// Applies a profile [ActionPayload] to the profiles stored in `dir`. Profile actions
+    /// operate on database files rather than on a loaded [TodoList].
+    /// `active` is the name of the profile currently in use. Deleting a profile returns
+    /// [`ActionOutcome::NeedsConfirmation`] unless `confirmed` is `true`.
+    pub fn apply_action<TDir: AsRef<Path>>(
+        dir: TDir,
+        active: &str,
+        action: ActionPayload,
+        confirmed: bool,
+    ) -> Result<ActionOutcome, CommandError> {
+        match action {
+            ActionPayload::CopyProfile(source, dest) => {
+                Profile::copy(dir, &source, &dest)?;
+                Ok(ActionOutcome::ProfileCopied(source, dest))
+            }
+            ActionPayload::DeleteProfile(name) if !confirmed => Ok(ActionOutcome::NeedsConfirmation(
+                format!("Are you sure you want to delete profile {:?}?", name),
+            )),
+            ActionPayload::DeleteProfile(name) => {
+                let backup = Profile::delete(dir, &name, active)?;
+                Ok(ActionOutcome::ProfileDeleted { name, backup })
+            }
+            ActionPayload::DiffProfiles(this, that) => {
+                let diff = Profile::diff(dir, &this, &that)?;
+                Ok(ActionOutcome::Listing(Profile::render_diff(&this, &that, &diff, true)))
+            }
+            ActionPayload::Recover => {
+                let count = Profile::recover(dir, active)?;
+                Ok(ActionOutcome::ProfileRecovered {
+                    name: active.to_string(),
+                    count,
+                })
+            }
+            ActionPayload::RenameProfile(old, new) => {
+                Profile::rename(dir, &old, &new)?;
+                Ok(ActionOutcome::ProfileRenamed(old, new))
+            }
+            _ => Err(CommandError::InputInvalid(format!(
+                "{:?} is not a profile command",
+                action.input_cmd_string()
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::temp_dir;
+
+    #[test]
+    fn rename_moves_profile() {
+        let dir = temp_dir("profile-rename");
+        let mut list = TodoList::new();
+        list.add_todo("buy milk", false).unwrap();
+        list.save_to_profile(&dir, "work").unwrap();
+
+        Profile::rename(&dir, "work", "personal").unwrap();
+
+        assert!(!Profile::exists(&dir, "work"));
+        let loaded = TodoList::load_from_profile(&dir, "personal").unwrap();
+        assert_eq!(loaded.len(), 1);
+    }
+
+    #[test]
+    fn rename_refuses_existing_target() {
+        let dir = temp_dir("profile-rename-existing");
+        TodoList::new().save_to_profile(&dir, "work").unwrap();
+        TodoList::new().save_to_profile(&dir, "personal").unwrap();
+
+        assert_eq!(
+            Profile::rename(&dir, "work", "personal"),
+            Err(CommandError::ProfileAlreadyExists("personal".to_string()))
+        );
+        assert!(Profile::exists(&dir, "work"));
+    }
+
+    #[test]
+    fn copy_creates_identical_profile() {
+        let dir = temp_dir("profile-copy");
+        let mut list = TodoList::new();
+        list.add_todo("buy milk", false).unwrap();
+        list.add_todo("buy eggs", true).unwrap();
+        list.save_to_profile(&dir, "work").unwrap();
+
+        Profile::copy(&dir, "work", "experiment").unwrap();
+
+        let copy = TodoList::load_from_profile(&dir, "experiment").unwrap();
+        assert_eq!(list.diff_with(&copy), DiffResult::Same);
+        assert_eq!(
+            Profile::copy(&dir, "work", "experiment"),
+            Err(CommandError::ProfileAlreadyExists("experiment".to_string()))
+        );
+    }
+
+    #[test]
+    fn diff_compares_two_profiles() {
+        let dir = temp_dir("profile-diff");
+        let mut work = TodoList::new();
+        work.add_todo("buy milk", false).unwrap();
+        work.add_todo("call bob", true).unwrap();
+        work.save_to_profile(&dir, "work").unwrap();
+        let mut personal = TodoList::new();
+        personal.add_todo("buy milk", true).unwrap();
+        personal.add_todo("walk the dog", false).unwrap();
+        personal.save_to_profile(&dir, "personal").unwrap();
+
+        let outcome = Profile::apply_action(
+            &dir,
+            DEFAULT_PROFILE,
+            ActionPayload::DiffProfiles("work".to_string(), "personal".to_string()),
+            false,
+        )
+        .unwrap();
+
+        let listing = outcome.to_string();
+        assert!(listing.contains("3 differences"), "{}", listing);
+        assert!(listing.contains("\"buy milk\" is marked as incomplete in this"), "{}", listing);
+        assert!(listing.contains("\"call bob\" is in this but not that"), "{}", listing);
+        assert!(listing.contains("\"walk the dog\" is in that but not this"), "{}", listing);
+        assert_eq!(
+            Profile::diff(&dir, "work", "missing"),
+            Err(CommandError::ProfileNotFound("missing".to_string()))
+        );
+        assert_eq!(Profile::diff(&dir, "work", "work"), Ok(DiffResult::Same));
+    }
+
+    #[test]
+    fn delete_removes_inactive_profile() {
+        let dir = temp_dir("profile-delete");
+        TodoList::new().save_to_profile(&dir, "work").unwrap();
+
+        let backup = Profile::delete(&dir, "work", DEFAULT_PROFILE).unwrap();
+
+        assert!(!Profile::exists(&dir, "work"));
+        assert!(backup.is_file());
+    }
+
+    #[test]
+    fn delete_refuses_active_profile() {
+        let dir = temp_dir("profile-delete-active");
+        TodoList::new().save_to_profile(&dir, "work").unwrap();
+
+        assert!(Profile::delete(&dir, "work", "work").is_err());
+        assert!(Profile::exists(&dir, "work"));
+    }
+
+    #[test]
+    fn recover_replays_log_over_corrupt_snapshot() {
+        let dir = temp_dir("profile-recover");
+        let log = Profile::log_path_for(&dir, "work");
+        let actions = vec![
+            ActionPayload::AddMany(vec!["one".to_string(), "two".to_string(), "three".to_string()]),
+            ActionPayload::Toggle("two".to_string()),
+            ActionPayload::Remove("three".to_string()),
+            ActionPayload::Clear,
+            ActionPayload::Add("four".to_string()),
+            ActionPayload::Set("five".to_string(), true),
+        ];
+        let mut expected = TodoList::new();
+        for action in actions {
+            expected.apply_confirmed(action.clone()).unwrap();
+            ActionLog::append(&log, &action).unwrap();
+        }
+
+        std::fs::write(Profile::path_for(&dir, "work"), b"not a todo list").unwrap();
+        assert!(TodoList::load_from_profile(&dir, "work").is_err());
+
+        assert_eq!(Profile::recover(&dir, "work"), Ok(6));
+        let recovered = TodoList::load_from_profile(&dir, "work").unwrap();
+        assert_eq!(recovered.diff_with(&expected), DiffResult::Same);
+        assert_eq!(recovered.get_todos_text(), vec!["four", "five"]);
+    }
+
+    #[test]
+    fn prune_keeps_newest_backups() {
+        let dir = temp_dir("profile-prune");
+        let backup_dir = Profile::backup_dir_for(&dir, "work");
+        std::fs::create_dir_all(&backup_dir).unwrap();
+        let backups = (1..=5)
+            .map(|n| backup_dir.join(format!("2021120{}120000000.cbor", n)))
+            .collect::<Vec<_>>();
+        for backup in backups.iter().rev() {
+            std::fs::write(backup, b"").unwrap();
+        }
+
+        let pruned = Profile::prune_backups(&dir, "work", 3).unwrap();
+
+        assert_eq!(pruned, backups[..2].to_vec());
+        assert!(backups[..2].iter().all(|backup| !backup.exists()));
+        assert!(backups[2..].iter().all(|backup| backup.is_file()));
+        assert!(Profile::backups_to_prune(backups, 0).is_empty());
+    }
+
+    #[test]
+    fn saved_encoding_is_picked_up_on_load() {
+        let dir = temp_dir("profile-encoding");
+        let mut list = TodoList::new();
+        list.add_todo("buy milk", true).unwrap();
+        list.save_to_profile(&dir, "work").unwrap();
+
+        list.save_to_profile_as(&dir, "work", EncodingType::Json).unwrap();
+        assert_eq!(Profile::encoding_of(&dir, "work"), EncodingType::Json);
+        assert!(!Profile::path_for_encoding(&dir, "work", DEFAULT_ENCODING).exists());
+        let loaded = TodoList::load_from_profile(&dir, "work").unwrap();
+        assert_eq!(loaded.diff_with(&list), DiffResult::Same);
+
+        list.save_to_profile(&dir, "work").unwrap();
+        assert_eq!(Profile::encoding_of(&dir, "work"), EncodingType::Json);
+        assert!(!Profile::path_for_encoding(&dir, "work", DEFAULT_ENCODING).exists());
+
+        list.save_to_profile_as(&dir, "work", DEFAULT_ENCODING).unwrap();
+        assert_eq!(Profile::encoding_of(&dir, "work"), DEFAULT_ENCODING);
+        assert!(!Profile::path_for_encoding(&dir, "work", EncodingType::Json).exists());
+    }
+}
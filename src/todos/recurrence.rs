@@ -0,0 +1,72 @@
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/// How often a recurring todo comes back once it is completed, see
+/// [`TodoList::recur`](super::todolist::TodoList::recur).
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Serialize, Deserialize, Clone, Copy)]
+pub enum Recurrence {
+    Daily,
+    Weekly,
+    EveryNDays(u32),
+}
+
+impl Recurrence {
+    /// Parses a [Recurrence] from `daily`, `weekly` or a number of days such as `3` or
+    /// `3d`, ignoring case. Zero days is not a recurrence.
+    pub fn try_parse<S: AsRef<str>>(s: S) -> Option<Recurrence> {
+        let s = s.as_ref().trim().to_lowercase();
+        match s.as_str() {
+            "daily" => Some(Recurrence::Daily),
+            "weekly" => Some(Recurrence::Weekly),
+            _ => s
+                .trim_end_matches('d')
+                .parse::<u32>()
+                .ok()
+                .filter(|days| *days > 0)
+                .map(Recurrence::EveryNDays),
+        }
+    }
+
+    /// Returns the number of days between occurrences.
+    pub fn days(&self) -> u32 {
+        match self {
+            Recurrence::Daily => 1,
+            Recurrence::Weekly => 7,
+            Recurrence::EveryNDays(days) => *days,
+        }
+    }
+
+    /// Returns the due date of the occurrence after one due on `due`.
+    pub fn next_due(&self, due: NaiveDate) -> NaiveDate {
+        due + Duration::days(self.days() as i64)
+    }
+}
+
+impl std::fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekly => write!(f, "weekly"),
+            Recurrence::EveryNDays(days) => write!(f, "every {} days", days),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_names_and_day_counts() {
+        assert_eq!(Recurrence::try_parse("Daily"), Some(Recurrence::Daily));
+        assert_eq!(Recurrence::try_parse("weekly"), Some(Recurrence::Weekly));
+        assert_eq!(Recurrence::try_parse("3d"), Some(Recurrence::EveryNDays(3)));
+        assert_eq!(Recurrence::try_parse("10"), Some(Recurrence::EveryNDays(10)));
+        assert_eq!(Recurrence::try_parse("0"), None);
+        assert_eq!(Recurrence::try_parse("monthly"), None);
+        assert_eq!(
+            Recurrence::Weekly.next_due(NaiveDate::from_ymd(2021, 12, 28)),
+            NaiveDate::from_ymd(2022, 1, 4)
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use crate::utils::color::should_color;
+
+/// How [`TodoList::render_list`](super::todolist::TodoList::render_list) renders a list.
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Clone, Copy, Default)]
+pub struct RenderOptions {
+    /// Render without any color, for output that is not going to a terminal.
+    pub no_color: bool,
+    /// Only render the todos with this status, or every todo if `None`.
+    pub status: Option<bool>,
+    /// Render a table of aligned columns with headers instead of rows, see
+    /// [render_table](crate::utils::general::render_table).
+    pub table: bool,
+}
+
+impl RenderOptions {
+    /// Options for rendering every todo without color, such as when writing to a file.
+    pub fn plain() -> Self {
+        Self {
+            no_color: true,
+            status: None,
+            table: false,
+        }
+    }
+
+    /// Options for rendering to stdout, colored unless [should_color] says otherwise.
+    pub fn detect() -> Self {
+        Self {
+            no_color: !should_color(),
+            status: None,
+            table: false,
+        }
+    }
+
+    /// Returns these options limited to the todos with `status`.
+    pub fn with_status(self, status: bool) -> Self {
+        Self {
+            status: Some(status),
+            ..self
+        }
+    }
+
+    /// Returns these options rendering a table if `table` is `true`.
+    pub fn with_table(self, table: bool) -> Self {
+        Self { table, ..self }
+    }
+
+    /// Returns `true` if the rendering should be colored.
+    pub fn colorize(&self) -> bool {
+        !self.no_color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_and_default_options() {
+        assert!(!RenderOptions::plain().colorize());
+        assert!(RenderOptions::default().colorize());
+        assert_eq!(RenderOptions::plain().with_status(true).status, Some(true));
+    }
+}
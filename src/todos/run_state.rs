@@ -0,0 +1,49 @@
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::command_error::CommandError;
+use crate::utils::fs::FileSystem;
+
+/// What the program remembers between runs of a profile, stored as JSON next to it, see
+/// [`Profile::state_path_for`](super::profile::Profile::state_path_for).
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RunState {
+    /// When the program last finished running, `None` if it never has.
+    pub last_opened: Option<DateTime<Utc>>,
+}
+
+impl RunState {
+    /// Reads the state saved at `file`. A missing or unreadable file gives the default
+    /// state, as if the program had never run.
+    pub fn load<TFilePath: AsRef<Path>>(file: TFilePath) -> RunState {
+        FileSystem::load_bytes(file)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    /// Writes this state to `file` as JSON.
+    pub fn save<TFilePath: AsRef<Path>>(&self, file: TFilePath) -> Result<(), CommandError> {
+        let json = serde_json::to_vec_pretty(self).map_err(|err| CommandError::IoError(err.to_string()))?;
+        Ok(FileSystem::save_bytes(file, json)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::temp_dir;
+
+    #[test]
+    fn round_trips_and_defaults_when_missing() {
+        let file = temp_dir("run-state").join("work.state.json");
+        assert_eq!(RunState::load(&file), RunState::default());
+
+        let state = RunState { last_opened: Some(Utc::now()) };
+        state.save(&file).unwrap();
+        assert_eq!(RunState::load(&file), state);
+    }
+}
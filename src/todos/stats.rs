@@ -0,0 +1,75 @@
+use std::{fs::OpenOptions, io::Write, path::Path};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use super::{command_error::CommandError, todolist::TodoList};
+
+/// The name of the CSV file, inside the data directory, that [StatsReport]s are appended
+/// to when [`AppSettings::stats_log`](crate::config::settings::AppSettings::stats_log) is set.
+pub const STATS_FILE: &str = "stats.csv";
+
+/// The header row of [STATS_FILE], naming the columns of [`StatsReport::to_csv_row`].
+pub const STATS_HEADER: &str = "timestamp,total,completed,incomplete";
+
+/// How many todos a list held at a point in time, recorded after each save so that
+/// progress can be charted.
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub struct StatsReport {
+    pub timestamp: DateTime<Utc>,
+    pub total: usize,
+    pub completed: usize,
+    pub incomplete: usize,
+}
+
+impl StatsReport {
+    /// Counts the todos in `list` as of `timestamp`.
+    pub fn of(list: &TodoList, timestamp: DateTime<Utc>) -> Self {
+        let completed = list.get_todos_with_status(true).len();
+        Self {
+            timestamp,
+            total: list.len(),
+            completed,
+            incomplete: list.len() - completed,
+        }
+    }
+
+    /// Returns this report as a row of [STATS_FILE], without a line ending. The timestamp
+    /// is in RFC 3339 format.
+    pub fn to_csv_row(&self) -> String {
+        format!(
+            "{},{},{},{}",
+            self.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+            self.total,
+            self.completed,
+            self.incomplete
+        )
+    }
+
+    /// Appends this report to the stats file at `file`, writing [STATS_HEADER] first if the
+    /// file is new.
+    pub fn append<TFilePath: AsRef<Path>>(&self, file: TFilePath) -> Result<(), CommandError> {
+        let is_new = !file.as_ref().exists();
+        let mut file = OpenOptions::new().create(true).append(true).open(file)?;
+        if is_new {
+            writeln!(file, "{}", STATS_HEADER)?;
+        }
+        writeln!(file, "{}", self.to_csv_row())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todos::todolist_builder::TodoListBuilder;
+    use chrono::TimeZone;
+
+    #[test]
+    fn csv_row_has_counts() {
+        let list = TodoListBuilder::new().todo("one").done("two").todo("three").build();
+        let report = StatsReport::of(&list, Utc.ymd(2021, 12, 24).and_hms(9, 30, 0));
+
+        assert_eq!(report.to_csv_row(), "2021-12-24T09:30:00Z,3,1,2");
+    }
+}
@@ -0,0 +1,271 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use std::cmp::Ordering;
+use owo_colors::AnsiColors;
+use serde::{
+    de::{self, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+
+use super::{command_error::CommandError, priority::Priority, recurrence::Recurrence};
+
+/// The named colors a todo can be displayed in, see [color_style].
+pub const COLOR_PALETTE: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// The state of a single todo in a [TodoList](super::todolist::TodoList), which keys its
+/// todos by their text. Databases written before todos carried anything beyond their
+/// status stored a bare `bool`, which still deserializes into a [Todo], see [TodoVisitor].
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Serialize, Clone, Default)]
+pub struct Todo {
+    pub completed: bool,
+    /// The name of the color, one of [COLOR_PALETTE], the todo is displayed in.
+    pub color: Option<String>,
+    /// Free-form labels used to group todos.
+    pub tags: Vec<String>,
+    /// Longer, possibly multi-line, detail about the todo.
+    pub description: Option<String>,
+    /// How important the todo is.
+    pub priority: Option<Priority>,
+    /// The day the todo should be done by.
+    pub due: Option<NaiveDate>,
+    /// When the todo was added, `None` for todos saved before this was recorded.
+    pub created_at: Option<DateTime<Utc>>,
+    /// When the todo was last marked complete, `None` while it is incomplete.
+    pub completed_at: Option<DateTime<Utc>>,
+    /// How often the todo comes back once completed, `None` if it does not.
+    pub recurrence: Option<Recurrence>,
+    /// The text of the todo this is a subtask of, `None` for top level todos. Todos are
+    /// keyed by their text, so this follows the parent when it is renamed.
+    pub parent: Option<String>,
+}
+
+impl Todo {
+    pub fn new(completed: bool) -> Self {
+        Self {
+            completed,
+            ..Self::default()
+        }
+    }
+
+    /// Creates a todo that was added just now, see [Todo::created_at].
+    pub fn created(completed: bool) -> Self {
+        let mut todo = Self {
+            created_at: Some(Utc::now()),
+            ..Self::default()
+        };
+        todo.set_completed(completed);
+        todo
+    }
+
+    /// Sets the status of this todo, recording when it was completed.
+    pub fn set_completed(&mut self, completed: bool) {
+        if completed && !self.completed {
+            self.completed_at = Some(Utc::now());
+        } else if !completed {
+            self.completed_at = None;
+        }
+        self.completed = completed;
+    }
+
+    /// Returns the color this todo is displayed in, if it has one.
+    pub fn style(&self) -> Option<AnsiColors> {
+        self.color.as_deref().and_then(color_style)
+    }
+}
+
+/// The fields of a stored [Todo] when it is written as a map.
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "snake_case")]
+enum TodoField {
+    Completed,
+    Color,
+    Tags,
+    Description,
+    Priority,
+    Due,
+    CreatedAt,
+    CompletedAt,
+    Recurrence,
+    Parent,
+    #[serde(other)]
+    Unknown,
+}
+
+/// Reads a [Todo] stored either as a bare `bool` or as the full struct, which the binary
+/// encodings write as a sequence of its fields rather than a map.
+struct TodoVisitor;
+
+impl<'de> Visitor<'de> for TodoVisitor {
+    type Value = Todo;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a todo or the bool status of a todo")
+    }
+
+    fn visit_bool<E: de::Error>(self, completed: bool) -> Result<Todo, E> {
+        Ok(Todo::new(completed))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Todo, A::Error> {
+        let completed = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        // Fields added after a database was written are missing from the end of it.
+        Ok(Todo {
+            completed,
+            color: seq.next_element()?.unwrap_or_default(),
+            tags: seq.next_element()?.unwrap_or_default(),
+            description: seq.next_element()?.unwrap_or_default(),
+            priority: seq.next_element()?.unwrap_or_default(),
+            due: seq.next_element()?.unwrap_or_default(),
+            created_at: seq.next_element()?.unwrap_or_default(),
+            completed_at: seq.next_element()?.unwrap_or_default(),
+            recurrence: seq.next_element()?.unwrap_or_default(),
+            parent: seq.next_element()?.unwrap_or_default(),
+        })
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Todo, A::Error> {
+        let mut completed = None;
+        let mut todo = Todo::default();
+        while let Some(field) = map.next_key()? {
+            match field {
+                TodoField::Completed => completed = Some(map.next_value()?),
+                TodoField::Color => todo.color = map.next_value()?,
+                TodoField::Tags => todo.tags = map.next_value()?,
+                TodoField::Description => todo.description = map.next_value()?,
+                TodoField::Priority => todo.priority = map.next_value()?,
+                TodoField::Due => todo.due = map.next_value()?,
+                TodoField::CreatedAt => todo.created_at = map.next_value()?,
+                TodoField::CompletedAt => todo.completed_at = map.next_value()?,
+                TodoField::Recurrence => todo.recurrence = map.next_value()?,
+                TodoField::Parent => todo.parent = map.next_value()?,
+                TodoField::Unknown => {
+                    map.next_value::<de::IgnoredAny>()?;
+                }
+            }
+        }
+        todo.completed = completed.ok_or_else(|| de::Error::missing_field("completed"))?;
+        Ok(todo)
+    }
+}
+
+impl<'de> Deserialize<'de> for Todo {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(TodoVisitor)
+    }
+}
+
+/// Orders due dates soonest first, with todos that have no due date after every todo
+/// that does.
+pub fn due_date_ord(a: &Option<NaiveDate>, b: &Option<NaiveDate>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Returns the color for the palette color `name`, ignoring case, or `None` if `name` is
+/// not in [COLOR_PALETTE].
+pub fn color_style<S: AsRef<str>>(name: S) -> Option<AnsiColors> {
+    match name.as_ref().to_lowercase().as_str() {
+        "black" => Some(AnsiColors::Black),
+        "red" => Some(AnsiColors::Red),
+        "green" => Some(AnsiColors::Green),
+        "yellow" => Some(AnsiColors::Yellow),
+        "blue" => Some(AnsiColors::Blue),
+        "magenta" => Some(AnsiColors::Magenta),
+        "cyan" => Some(AnsiColors::Cyan),
+        "white" => Some(AnsiColors::White),
+        _ => None,
+    }
+}
+
+/// Validates that `name` is a color in [COLOR_PALETTE], returning the color if it is.
+pub fn validate_color<S: AsRef<str>>(name: S) -> Result<AnsiColors, CommandError> {
+    color_style(&name).ok_or_else(|| {
+        CommandError::InputInvalid(format!(
+            "{:?} is not a known color, expected one of {}",
+            name.as_ref(),
+            COLOR_PALETTE.join(", ")
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::cereal::{Cereal, EncodingType};
+    use indexmap::IndexMap;
+
+    #[test]
+    fn palette_colors_are_valid() {
+        for name in COLOR_PALETTE {
+            assert!(color_style(name).is_some());
+        }
+        assert!(matches!(color_style("Blue"), Some(AnsiColors::Blue)));
+        assert!(color_style("chartreuse").is_none());
+    }
+
+    #[test]
+    fn recurrence_round_trips() {
+        let todo = Todo {
+            recurrence: Some(Recurrence::EveryNDays(3)),
+            ..Todo::new(false)
+        };
+        let json = serde_json::to_string(&todo).unwrap();
+
+        assert_eq!(serde_json::from_str::<Todo>(&json).unwrap(), todo);
+        assert_eq!(serde_json::from_str::<Todo>("true").unwrap().recurrence, None);
+    }
+
+    #[test]
+    fn full_and_legacy_todos_round_trip_in_every_encoding() {
+        let full = Todo {
+            color: Some("red".to_string()),
+            tags: vec!["home".to_string()],
+            description: Some("semi-skimmed".to_string()),
+            priority: Some(Priority::High),
+            due: Some(NaiveDate::from_ymd(2021, 10, 20)),
+            recurrence: Some(Recurrence::EveryNDays(3)),
+            parent: Some("shopping".to_string()),
+            ..Todo::created(true)
+        };
+        let todos: IndexMap<String, Todo> = vec![
+            ("milk".to_string(), full),
+            ("eggs".to_string(), Todo::new(false)),
+        ]
+        .into_iter()
+        .collect();
+        let legacy: IndexMap<String, bool> = vec![("milk".to_string(), true), ("eggs".to_string(), false)]
+            .into_iter()
+            .collect();
+
+        for encoding in EncodingType::all() {
+            let bytes = Cereal::serialize_with(encoding, &todos).unwrap();
+            assert_eq!(
+                Cereal::deserialize_with::<IndexMap<String, Todo>>(encoding, &bytes).unwrap(),
+                todos,
+                "{}",
+                encoding
+            );
+
+            let bytes = Cereal::serialize_with(encoding, &legacy).unwrap();
+            let read = Cereal::deserialize_with::<IndexMap<String, Todo>>(encoding, &bytes).unwrap();
+            assert_eq!(read["milk"], Todo::new(true), "{}", encoding);
+            assert_eq!(read["eggs"], Todo::new(false), "{}", encoding);
+        }
+    }
+
+    #[test]
+    fn due_dates_sort_with_undated_last() {
+        let day = |d: u32| Some(NaiveDate::from_ymd(2021, 10, d));
+        let mut dates = vec![None, day(20), day(3), None, day(11)];
+        dates.sort_by(due_date_ord);
+
+        assert_eq!(dates, vec![day(3), day(11), day(20), None, None]);
+    }
+}
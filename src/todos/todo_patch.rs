@@ -0,0 +1,38 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::command_error::CommandError;
+use crate::{state::actions::action_payload::ActionPayload, utils::fs::FileSystem};
+
+/// A list of actions that turns one [TodoList](super::todolist::TodoList) into another,
+/// created from the result of a diff with
+/// [`DiffResult::to_patch`](super::todolist::DiffResult::to_patch) and applied with
+/// [`TodoList::apply_patch`](super::todolist::TodoList::apply_patch).
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TodoPatch {
+    pub actions: Vec<ActionPayload>,
+}
+
+impl TodoPatch {
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Reads a patch written by [`TodoPatch::save`] from the file at `file`.
+    pub fn load<TFilePath: AsRef<Path>>(file: TFilePath) -> Result<TodoPatch, CommandError> {
+        let bytes = FileSystem::load_bytes(file)?;
+        serde_json::from_slice(&bytes)
+            .map_err(|err| CommandError::InputInvalid(format!("Malformed patch file: {}", err)))
+    }
+
+    /// Writes this patch to the file at `file` as JSON.
+    pub fn save<TFilePath: AsRef<Path>>(&self, file: TFilePath) -> Result<(), CommandError> {
+        let json = serde_json::to_vec_pretty(self).map_err(|err| CommandError::IoError(err.to_string()))?;
+        Ok(FileSystem::save_bytes(file, json)?)
+    }
+}
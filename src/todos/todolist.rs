@@ -1,23 +1,39 @@
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use indexmap::IndexMap;
 use itertools::Itertools;
-use owo_colors::{colors, OwoColorize};
+use owo_colors::{AnsiColors, OwoColorize, Style};
 use rand::Rng;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs::File,
-    io::Read,
+    io::{BufRead, BufWriter, Read, Write},
+    path::Path,
     str::FromStr,
     time::{Duration, Instant},
 };
 
-use super::command_error::CommandError;
+use super::{
+    action_log::ActionLog,
+    command_error::CommandError,
+    priority::{priority_label, Priority},
+    profile::{Profile, DEFAULT_PROFILE},
+    recurrence::Recurrence,
+    render_options::RenderOptions,
+    todo::{due_date_ord, validate_color, Todo},
+    todo_patch::TodoPatch,
+};
 use crate::{
-    input::prompter::{Prompter, ResponseBool},
-    state::actions::action_payload::ActionPayload,
+    config::settings::{DEFAULT_COMPLETE_SYMBOL, DEFAULT_DATE_FORMAT, DEFAULT_INCOMPLETE_SYMBOL},
+    input::{completions::Completions, input_error::InputError},
+    state::actions::{action_outcome::ActionOutcome, action_payload::ActionPayload},
     utils::{
-        cereal::{Cereal, EncodingType},
+        cereal::{Cereal, EncodingType, COMPRESSED_FILE_EXT},
+        color::{paint, should_color},
+        crypto::Crypto,
         fs::FileSystem,
-        general::s,
+        general::{escape_field, render_table, s, string_to_bool, truncate_to_width, unescape_field},
     },
 };
 
@@ -27,6 +43,8 @@ pub enum DiffEntry {
         todo: String,
         this_has: bool,
         that_has: bool,
+        /// The status of the todo in the list that has it.
+        completed: bool,
     },
     TodoStatusMistake {
         todo: String,
@@ -36,12 +54,13 @@ pub enum DiffEntry {
 }
 
 impl DiffEntry {
-    pub fn to_string(&self) -> String {
+    fn message(&self) -> String {
         match self {
             DiffEntry::TodoNotFound {
                 todo,
                 this_has,
                 that_has,
+                ..
             } => format!(
                 "Todo {:?} is in {} but not {}.",
                 todo,
@@ -64,7 +83,7 @@ impl DiffEntry {
 
 impl std::fmt::Display for DiffEntry {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.to_string())
+        write!(f, "{}", self.message())
     }
 }
 
@@ -74,18 +93,393 @@ pub enum DiffResult {
     Changes(Vec<DiffEntry>),
 }
 
+impl DiffResult {
+    /// Converts the result of `this.diff_with(&that)` into a [TodoPatch] that turns `this`
+    /// into `that` when applied with [`TodoList::apply_patch`].
+    pub fn to_patch(&self) -> TodoPatch {
+        let changes = match self {
+            DiffResult::Same => return TodoPatch::default(),
+            DiffResult::Changes(changes) => changes,
+        };
+
+        let actions = changes
+            .iter()
+            .map(|change| match change {
+                DiffEntry::TodoNotFound { todo, this_has: true, .. } => ActionPayload::Remove(todo.clone()),
+                DiffEntry::TodoNotFound { todo, completed, .. } => ActionPayload::Set(todo.clone(), *completed),
+                DiffEntry::TodoStatusMistake { todo, that_status, .. } => ActionPayload::Set(todo.clone(), *that_status),
+            })
+            .collect();
+
+        TodoPatch { actions }
+    }
+
+    /// Counts the changes in the result of `this.diff_with(&that)`, a todo only `that` has
+    /// is added, one only `this` has is removed and one with a different status is changed.
+    pub fn summary(&self) -> DiffSummary {
+        let mut summary = DiffSummary::default();
+        if let DiffResult::Changes(changes) = self {
+            for change in changes {
+                match change {
+                    DiffEntry::TodoNotFound { this_has: true, .. } => summary.removed += 1,
+                    DiffEntry::TodoNotFound { .. } => summary.added += 1,
+                    DiffEntry::TodoStatusMistake { .. } => summary.changed += 1,
+                }
+            }
+        }
+        summary
+    }
+
+    /// Renders the result of `saved.diff_with(&current)` as the changes being saved, a line
+    /// per todo marked `+` if it was added, `-` if it was removed and `~` if its status
+    /// changed, followed by the [summary](DiffResult::summary). The markers are colored like
+    /// the summary if `colorize` is `true`.
+    pub fn render_changes(&self, colorize: bool) -> String {
+        let changes = match self {
+            DiffResult::Same => return s("No changes.\n"),
+            DiffResult::Changes(changes) => changes,
+        };
+
+        let mut output = String::new();
+        for change in changes {
+            let (marker, style, line) = match change {
+                DiffEntry::TodoNotFound { todo, this_has: true, .. } => {
+                    ("-", AnsiColors::Red, format!("{:?}", todo))
+                }
+                DiffEntry::TodoNotFound { todo, .. } => ("+", AnsiColors::Green, format!("{:?}", todo)),
+                DiffEntry::TodoStatusMistake { todo, that_status, .. } => (
+                    "~",
+                    AnsiColors::Yellow,
+                    format!("{:?} is now {}complete", todo, if *that_status { "" } else { "in" }),
+                ),
+            };
+            let marker = if colorize { marker.color(style).to_string() } else { s(marker) };
+            output.push_str(&format!("\t{} {}\n", marker, line));
+        }
+        output.push_str(&format!("{}\n", self.summary().render(colorize)));
+
+        output
+    }
+}
+
+/// Counts of the todos added, removed and changed between two lists, see
+/// [`DiffResult::summary`].
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Serialize, Deserialize, Clone, Copy, Default)]
+pub struct DiffSummary {
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+}
+
+impl DiffSummary {
+    /// Renders the summary as "3 added, 1 removed, 2 changed", with the counts in green, red
+    /// and yellow if `colorize` is `true`.
+    pub fn render(&self, colorize: bool) -> String {
+        let count = |count: usize, style: AnsiColors| {
+            if colorize {
+                count.color(style).to_string()
+            } else {
+                count.to_string()
+            }
+        };
+
+        format!(
+            "{} added, {} removed, {} changed",
+            count(self.added, AnsiColors::Green),
+            count(self.removed, AnsiColors::Red),
+            count(self.changed, AnsiColors::Yellow)
+        )
+    }
+}
+
+impl std::fmt::Display for DiffSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.render(false))
+    }
+}
+
+/// Summary of an import, counting the todos that were added to the list and the todos
+/// that were skipped because they already existed.
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Serialize, Deserialize, Clone, Copy, Default)]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub skipped: usize,
+}
+
+impl std::fmt::Display for ImportSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Imported {} {}, skipped {} duplicate{}.",
+            self.imported,
+            if self.imported == 1 { "todo" } else { "todos" },
+            self.skipped,
+            if self.skipped == 1 { "" } else { "s" }
+        )
+    }
+}
+
+/// Summary of adding several todos at once, listing the todos that were added, the
+/// todos that were skipped because they already existed and how many blank items were left out.
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AddSummary {
+    pub added: Vec<String>,
+    pub skipped: Vec<String>,
+    #[serde(default)]
+    pub blank: usize,
+}
+
+impl std::fmt::Display for AddSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Added {} {}, skipped {} duplicate{}.",
+            self.added.len(),
+            if self.added.len() == 1 { "todo" } else { "todos" },
+            self.skipped.len(),
+            if self.skipped.len() == 1 { "" } else { "s" }
+        )?;
+        if self.blank > 0 {
+            write!(
+                f,
+                " Left out {} blank {}.",
+                self.blank,
+                if self.blank == 1 { "item" } else { "items" }
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// How [`TodoList::merge`] settles a todo that is in both lists with a different status.
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Serialize, Deserialize, Clone, Copy)]
+pub enum MergeStrategy {
+    /// Keep the status in this list.
+    PreferSelf,
+    /// Take the status from the other list.
+    PreferOther,
+    /// The todo is complete if it is complete in either list.
+    PreferCompleted,
+    /// The todo is incomplete if it is incomplete in either list.
+    PreferIncomplete,
+}
+
+impl MergeStrategy {
+    /// Returns the status to keep when this list has `this_status` and the other list has
+    /// `that_status`.
+    pub fn resolve(&self, this_status: bool, that_status: bool) -> bool {
+        match self {
+            MergeStrategy::PreferSelf => this_status,
+            MergeStrategy::PreferOther => that_status,
+            MergeStrategy::PreferCompleted => this_status || that_status,
+            MergeStrategy::PreferIncomplete => this_status && that_status,
+        }
+    }
+}
+
+/// Summary of a merge, counting the todos that were added from the other list and the
+/// todos whose status differed between the two lists.
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Serialize, Deserialize, Clone, Copy, Default)]
+pub struct MergeReport {
+    pub added: usize,
+    pub conflicts: usize,
+}
+
+impl std::fmt::Display for MergeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Added {} {}, resolved {} conflict{}.",
+            self.added,
+            if self.added == 1 { "todo" } else { "todos" },
+            self.conflicts,
+            if self.conflicts == 1 { "" } else { "s" }
+        )
+    }
+}
+
+/// A todo that both sides of a three-way merge changed differently, see
+/// [`TodoList::merge_3way`]. Each field is the status of the todo in that list, or `None`
+/// if the list does not have it.
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Serialize, Deserialize, Clone)]
+pub struct MergeConflict {
+    pub todo: String,
+    pub base: Option<bool>,
+    pub ours: Option<bool>,
+    pub theirs: Option<bool>,
+}
+
+impl std::fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = |status: Option<bool>| match status {
+            Some(true) => "complete",
+            Some(false) => "incomplete",
+            None => "missing",
+        };
+        write!(
+            f,
+            "Todo {:?} was {} but is {} in ours and {} in theirs.",
+            self.todo,
+            state(self.base),
+            state(self.ours),
+            state(self.theirs)
+        )
+    }
+}
+
+/// The result of [`TodoList::merge_3way`]. Conflicting todos are left as they are in ours
+/// in the merged list, for the caller to decide on.
+#[derive(Debug, Clone)]
+pub struct ThreeWayMerge {
+    pub merged: TodoList,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl ThreeWayMerge {
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// The todos a regular expression replacement renames, see [`TodoList::replace`]. Each
+/// pair holds the old and the new text of a todo.
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ReplaceReport {
+    pub renamed: Vec<(String, String)>,
+    /// Renames that were skipped because the new text is empty or would be shared with
+    /// another todo.
+    pub conflicts: Vec<(String, String)>,
+}
+
+impl std::fmt::Display for ReplaceReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (old, new) in &self.conflicts {
+            writeln!(f, "Skipping {:?}, it cannot be renamed to {:?}.", old, new)?;
+        }
+        write!(
+            f,
+            "Renamed {} {}, skipped {} conflict{}.",
+            self.renamed.len(),
+            if self.renamed.len() == 1 { "todo" } else { "todos" },
+            self.conflicts.len(),
+            if self.conflicts.len() == 1 { "" } else { "s" }
+        )
+    }
+}
+
+/// A report on the calendar week, Monday to Sunday, around a given day, see
+/// [`TodoList::weekly_digest`]. Each section holds the text of the todos in it.
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct WeeklyDigest {
+    /// Todos completed this week, up to and including today.
+    pub completed: Vec<String>,
+    /// Todos added this week, up to and including today.
+    pub added: Vec<String>,
+    /// Incomplete todos that were due before today.
+    pub overdue: Vec<String>,
+    /// Incomplete todos due from today until the end of the week.
+    pub due: Vec<String>,
+}
+
+impl std::fmt::Display for WeeklyDigest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sections = [
+            ("Completed this week", &self.completed),
+            ("Added this week", &self.added),
+            ("Overdue", &self.overdue),
+            ("Due this week", &self.due),
+        ];
+        for (title, todos) in sections {
+            writeln!(f, "\n{} ({})", title, todos.len())?;
+            if todos.is_empty() {
+                writeln!(f, "    Nothing.")?;
+            }
+            for todo in todos {
+                writeln!(f, "    {:?}", todo)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 pub const DEFAULT_ENCODING: EncodingType = EncodingType::MsgPack;
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+/// What goes between the text of each todo when they are combined, see
+/// [`TodoList::combine_todos`].
+pub const COMBINED_SEPARATOR: &str = "; ";
+
+/// How many lines [`TodoList::import_jsonl_with`] reads between progress reports.
+pub const IMPORT_PROGRESS_INTERVAL: usize = 1000;
+
+/// Returns a one line preview of what applying `action` to `list` will do, such as
+/// `Will add "buy milk".`, shown in the REPL before an action is applied. It is built from
+/// [`TodoList::apply_action_dry`], so an action that would fail previews its error instead.
+pub fn payload_preview(action: &ActionPayload, list: &TodoList) -> String {
+    match action {
+        ActionPayload::Serve => "Will keep the list loaded and serve other instances.".to_string(),
+        ActionPayload::Triage => "Will start triaging the incomplete todos.".to_string(),
+        action if action.get_action_type().is_profile_action() => {
+            format!("Will run {:?} on the profiles.", action.get_action_type().get_input_string())
+        }
+        action => match list.apply_action_dry(action) {
+            Ok(description) => match description.strip_prefix("Would ") {
+                Some(rest) => format!("Will {}", rest),
+                None => description,
+            },
+            Err(err) => format!("Will fail, {}", err),
+        },
+    }
+}
+
+/// The Todo-List, an insertion-ordered collection of todos keyed by their text. Todos
+/// keep the position they were added at, which is preserved through serialization.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct TodoList {
-    map: HashMap<String, bool>,
+    map: IndexMap<String, Todo>,
+    /// Whether actions that need confirmation should assume the answer is yes, see
+    /// [`TodoList::needs_confirmation`].
+    #[serde(skip)]
+    assume_yes: bool,
+    /// The chrono format string dates are rendered in, [DEFAULT_DATE_FORMAT] if `None`.
+    #[serde(skip)]
+    date_format: Option<String>,
+    /// The symbols complete and incomplete todos are marked with, [DEFAULT_COMPLETE_SYMBOL]
+    /// and [DEFAULT_INCOMPLETE_SYMBOL] if `None`.
+    #[serde(skip)]
+    status_symbols: Option<(String, String)>,
+    /// Whether added todos are refused when they match an existing todo ignoring case, see [`TodoList::set_case_insensitive_duplicates`].
+    #[serde(skip)]
+    case_insensitive_duplicates: bool,
+    /// Whether list listings are rendered as a table, see [`TodoList::set_table_view`].
+    #[serde(skip)]
+    table_view: bool,
+    /// How many characters of each todo's text listings show, `0` shows all of it.
+    #[serde(skip)]
+    max_width: usize,
+    /// Whether the listings returned by [`TodoList::apply_action`] are colored, see
+    /// [`TodoList::set_colorize`].
+    #[serde(skip)]
+    colorize: bool,
+    /// The passphrase the list is encrypted with when saved, not encrypted if `None`.
+    #[serde(skip)]
+    passphrase: Option<String>,
 }
 
 impl TodoList {
     pub fn new() -> Self {
-        return Self {
-            map: HashMap::new(),
-        };
+        Self {
+            map: IndexMap::new(),
+            assume_yes: false,
+            date_format: None,
+            status_symbols: None,
+            case_insensitive_duplicates: false,
+            table_view: false,
+            max_width: 0,
+            colorize: false,
+            passphrase: None,
+        }
     }
 
     pub fn len(&self) -> usize {
@@ -100,59 +494,283 @@ impl TodoList {
         self.map.is_empty()
     }
 
+    /// Sets whether actions that need confirmation, such as `clear`, should be applied
+    /// straight away rather than returning [`ActionOutcome::NeedsConfirmation`].
+    pub fn set_assume_yes(&mut self, assume_yes: bool) {
+        self.assume_yes = assume_yes;
+    }
+
+    /// Sets the chrono format string that dates are rendered in, such as
+    /// [`AppSettings::date_format`](crate::config::settings::AppSettings::date_format).
+    pub fn set_date_format<S: AsRef<str>>(&mut self, format: S) {
+        self.date_format = Some(format.as_ref().to_string());
+    }
+
+    /// Sets the symbols that complete and incomplete todos are marked with in listings, such
+    /// as [`AppSettings::complete_symbol`](crate::config::settings::AppSettings::complete_symbol).
+    pub fn set_status_symbols<S: AsRef<str>>(&mut self, complete: S, incomplete: S) {
+        self.status_symbols = Some((complete.as_ref().to_string(), incomplete.as_ref().to_string()));
+    }
+
+    /// Sets whether added todos are refused with [`CommandError::TodoAlreadyExists`] when they
+    /// match an existing todo ignoring case and surrounding whitespace, such as `"Buy Milk"`
+    /// when `" buy milk "` exists. The existing todo keeps its casing.
+    pub fn set_case_insensitive_duplicates(&mut self, case_insensitive: bool) {
+        self.case_insensitive_duplicates = case_insensitive;
+    }
+
+    /// Sets whether the `ls` and `lss` listings are rendered as a table of aligned columns
+    /// rather than rows, see [`RenderOptions::table`].
+    pub fn set_table_view(&mut self, table_view: bool) {
+        self.table_view = table_view;
+    }
+
+    /// Sets how many characters of each todo's text are shown in listings, longer todos are
+    /// cut off with [truncate_to_width]. `0` shows every todo in full.
+    pub fn set_max_width(&mut self, max_width: usize) {
+        self.max_width = max_width;
+    }
+
+    /// Sets whether the listings returned by [`TodoList::apply_action`] are colored, such as
+    /// [`RenderOptions::detect`] decides for stdout. Listings are plain unless this is set,
+    /// so applying an action never depends on the terminal it happens to run in.
+    pub fn set_colorize(&mut self, colorize: bool) {
+        self.colorize = colorize;
+    }
+
+    /// Sets the passphrase this list is encrypted with when it is saved, or saves it
+    /// unencrypted if `None`.
+    pub fn set_passphrase(&mut self, passphrase: Option<String>) {
+        self.passphrase = passphrase;
+    }
+
+    fn status_symbol(&self, completed: bool) -> &str {
+        match (&self.status_symbols, completed) {
+            (Some((complete, _)), true) => complete,
+            (Some((_, incomplete)), false) => incomplete,
+            (None, true) => DEFAULT_COMPLETE_SYMBOL,
+            (None, false) => DEFAULT_INCOMPLETE_SYMBOL,
+        }
+    }
+
+    fn format_date(&self, date: &NaiveDate) -> String {
+        date.format(self.date_format.as_deref().unwrap_or(DEFAULT_DATE_FORMAT)).to_string()
+    }
+
     pub fn any_with_status(&self, status: bool) -> bool {
         if self.is_empty() {
             return false;
         }
 
         for (k, v) in self.map.iter() {
-            if *v == status {
+            if v.completed == status {
                 return true;
             }
         }
 
-        return false;
+        false
+    }
+
+    /// Returns `true` if there are no todos, or if `status` is given, no todos with that
+    /// status.
+    pub fn is_empty_with(&self, status: Option<bool>) -> bool {
+        match status {
+            Some(status) => !self.any_with_status(status),
+            None => self.is_empty(),
+        }
     }
 
     fn create_backup(&self) -> Result<(), std::io::Error> {
         Ok(())
     }
 
-    /// TODO Refactor to use [`crate::utils::fs::FileSystem`]
-    pub fn save_to_disk(&self) -> Result<(), String> {
-        Cereal::serialize_with(DEFAULT_ENCODING, &self).map_or_else(
-            |err| Err(err),
-            |bytes| {
-                FileSystem::save_bytes(format!("data.{}", DEFAULT_ENCODING.get_file_ext()), &bytes)
-                    .map_err(|io_err| io_err.to_string())
-            },
-        )
+    /// Saves this list to the [DEFAULT_PROFILE] in the data directory `dir`, backing up the
+    /// existing database first if `backup` is `true`. The list is saved with `encoding`, or
+    /// the encoding already on disk if it is `None`, see [`Profile::encoding_of`]. Returns
+    /// the size of the saved database in bytes.
+    pub fn save_to_disk<TDir: AsRef<Path>>(
+        &self,
+        dir: TDir,
+        backup: bool,
+        encoding: Option<EncodingType>,
+    ) -> Result<u64, String> {
+        let dir = dir.as_ref();
+        if backup && Profile::exists(dir, DEFAULT_PROFILE) {
+            Profile::backup(dir, DEFAULT_PROFILE).map_err(|err| err.to_string())?;
+        }
+
+        let encoding = encoding.unwrap_or_else(|| Profile::encoding_of(dir, DEFAULT_PROFILE));
+        self.save_to_profile_as(dir, DEFAULT_PROFILE, encoding)?;
+        std::fs::metadata(Profile::path_for(dir, DEFAULT_PROFILE))
+            .map(|metadata| metadata.len())
+            .map_err(|io_err| io_err.to_string())
+    }
+
+    /// Loads the list saved in the [DEFAULT_PROFILE] in the data directory `dir`.
+    pub fn load_from_disk<TDir: AsRef<Path>>(dir: TDir) -> Result<TodoList, String> {
+        TodoList::load_from_profile(dir, DEFAULT_PROFILE)
+    }
+
+    /// Loads the list saved in the [DEFAULT_PROFILE] in the data directory `dir`, decrypting
+    /// it with `passphrase`, see [`TodoList::load_from_profile_with`].
+    pub fn load_from_disk_with<TDir: AsRef<Path>>(dir: TDir, passphrase: Option<&str>) -> Result<TodoList, String> {
+        TodoList::load_from_profile_with(dir, DEFAULT_PROFILE, passphrase)
+    }
+
+    /// Saves this list to the profile `name` inside `dir`, see [Profile]. The profile keeps
+    /// the encoding it was saved with, see [`Profile::encoding_of`].
+    pub fn save_to_profile<TDir: AsRef<Path>, TName: AsRef<str>>(
+        &self,
+        dir: TDir,
+        name: TName,
+    ) -> Result<(), String> {
+        let encoding = Profile::encoding_of(&dir, &name);
+        self.save_to_profile_as(dir, name, encoding)
+    }
+
+    /// Saves this list to the profile `name` inside `dir` using `encoding`. The encoding is
+    /// recorded in the file extension, and the profile's database in any other encoding is
+    /// removed so that the next load reads this one.
+    pub fn save_to_profile_as<TDir: AsRef<Path>, TName: AsRef<str>>(
+        &self,
+        dir: TDir,
+        name: TName,
+        encoding: EncodingType,
+    ) -> Result<(), String> {
+        let mut bytes = Cereal::serialize_with(encoding, &self)?;
+        if let Some(passphrase) = &self.passphrase {
+            bytes = Crypto::encrypt(passphrase, &bytes)?;
+        }
+        FileSystem::save_bytes_atomic(Profile::path_for_encoding(&dir, &name, encoding), &bytes)
+            .map_err(|io_err| io_err.to_string())?;
+
+        for stale in EncodingType::all().into_iter().filter(|other| *other != encoding) {
+            let path = Profile::path_for_encoding(&dir, &name, stale);
+            if path.is_file() {
+                std::fs::remove_file(path).map_err(|io_err| io_err.to_string())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Saves this list to the profile `name` inside `dir`, first backing up the existing
+    /// database with [`Profile::backup`] if `backup` is `true`.
+    pub fn save_to_profile_with<TDir: AsRef<Path>, TName: AsRef<str>>(
+        &self,
+        dir: TDir,
+        name: TName,
+        backup: bool,
+    ) -> Result<(), String> {
+        if backup && Profile::exists(&dir, &name) {
+            Profile::backup(&dir, name.as_ref()).map_err(|err| err.to_string())?;
+        }
+
+        self.save_to_profile(dir, name)
+    }
+
+    /// Rebuilds a list by replaying every action in the action log at `log` onto an empty
+    /// list, see [ActionLog] and [`TodoList::replay`].
+    pub fn rebuild_from_log<TLog: AsRef<Path>>(log: TLog) -> Result<TodoList, CommandError> {
+        TodoList::replay(ActionLog::read(log)?)
+    }
 
-        // let mut content = String::new();
-        // for (k, v) in &self.map {
-        //     let record = format!("{}\t{}\n", k, v);
-        //     content.push_str(&record);
-        // }
+    /// Applies `actions` in order to an empty list and returns the result. Actions that would
+    /// normally need confirmation are applied as if they were confirmed, and queries are
+    /// skipped since they change nothing, so replaying never prompts or prints. Actions that
+    /// read or write other files, such as `import` and `patch`, are skipped too, since those
+    /// files may have changed since. The log does not record when actions were taken, so
+    /// the replayed todos have no created or completed times and replaying the same actions
+    /// always gives the same list. Commands run outside a list, such as profile commands,
+    /// `triage` and `serve`, cannot be replayed.
+    pub fn replay<I: IntoIterator<Item = ActionPayload>>(actions: I) -> Result<TodoList, CommandError> {
+        let mut list = TodoList::new();
+        for action in actions {
+            let action_type = action.get_action_type();
+            if !action_type.is_mutating() || action.touches_files() {
+                continue;
+            }
+            if action_type.is_profile_action() || matches!(action, ActionPayload::Serve | ActionPayload::Triage) {
+                return Err(CommandError::InputInvalid(format!(
+                    "the {:?} command cannot be replayed",
+                    action_type.get_input_string()
+                )));
+            }
+
+            list.apply_confirmed(action)?;
+        }
+
+        for todo in list.map.values_mut() {
+            todo.created_at = None;
+            todo.completed_at = None;
+        }
+
+        Ok(list)
+    }
 
-        // std::fs::write("db.txt", content)
+    /// Replays the action log at `log` and renders how this list differs from the result,
+    /// with the changes colored if `colorize` is `true`.
+    fn render_replay(&self, log: &str, colorize: bool) -> Result<String, CommandError> {
+        let actions = ActionLog::read(log)?;
+        let count = actions.len();
+        let replayed = TodoList::replay(actions)?;
+
+        Ok(match replayed.diff_with(self) {
+            DiffResult::Same => format!("Replaying the {} actions in {:?} matches the Todo-List.\n", count, log),
+            diff => format!(
+                "The Todo-List has diverged from the {} actions in {:?}:\n{}",
+                count,
+                log,
+                diff.render_changes(colorize)
+            ),
+        })
+    }
+
+    /// Loads the list saved in the profile `name` inside `dir`, see [Profile].
+    pub fn load_from_profile<TDir: AsRef<Path>, TName: AsRef<str>>(
+        dir: TDir,
+        name: TName,
+    ) -> Result<TodoList, String> {
+        TodoList::load_from_profile_with(dir, name, None)
     }
 
-    /// TODO Refactor to use [`crate::utils::fs::FileSystem`]
-    pub fn load_from_disk() -> Result<TodoList, String> {
-        let file_name = format!("data.{}", DEFAULT_ENCODING);
-        let path = std::path::Path::new(&file_name);
+    /// Loads the list saved in the profile `name` inside `dir`, decrypting it with
+    /// `passphrase` if it is encrypted. The list keeps `passphrase` so that it is saved
+    /// encrypted, which also encrypts a profile that was saved before encryption was used.
+    pub fn load_from_profile_with<TDir: AsRef<Path>, TName: AsRef<str>>(
+        dir: TDir,
+        name: TName,
+        passphrase: Option<&str>,
+    ) -> Result<TodoList, String> {
+        let path = Profile::path_for(&dir, &name);
         if !path.exists() {
             return Err(std::io::Error::new(
                 std::io::ErrorKind::NotFound,
-                format!("File {:?} not found!", file_name),
+                format!("File {:?} not found!", path),
             )
             .to_string());
         }
 
-        FileSystem::load_bytes(path).map_or_else(
-            |io_err| Err(io_err.to_string()),
-            |bytes| Cereal::deserialize_with(DEFAULT_ENCODING, &bytes),
-        )
+        let encoding = Profile::encoding_of(&dir, &name);
+        let mut bytes = FileSystem::load_bytes(&path).map_err(|io_err| io_err.to_string())?;
+        if Crypto::is_encrypted(&bytes) {
+            let passphrase = passphrase.ok_or_else(|| format!("{:?} is encrypted, a passphrase is needed to load it", path))?;
+            bytes = Crypto::decrypt(passphrase, &bytes)?;
+        }
+
+        let mut list: TodoList = Cereal::deserialize_with(encoding, &bytes)?;
+        list.set_passphrase(passphrase.map(|passphrase| passphrase.to_string()));
+        Ok(list)
+    }
+
+    /// Returns the [Todo] stored for the text `todo`, if it exists.
+    pub fn get_todo<Text: AsRef<str>>(&self, todo: Text) -> Option<&Todo> {
+        self.map.get(todo.as_ref())
+    }
+
+    /// Returns the status of the todo `todo`, if it exists.
+    pub fn get_status<Text: AsRef<str>>(&self, todo: Text) -> Option<bool> {
+        self.get_todo(todo).map(|item| item.completed)
     }
 
     pub fn get_todos_text(&self) -> Vec<&String> {
@@ -164,19 +782,19 @@ impl TodoList {
             return Vec::new();
         }
 
-        self.map.iter().map(|(k, v)| (k, v)).collect_vec()
+        self.map.iter().map(|(k, v)| (k, &v.completed)).collect_vec()
     }
 
     pub fn get_todos_with_status(&self, status: bool) -> Vec<&String> {
         self.map
             .iter()
-            .filter(|kv| *kv.1 == status)
+            .filter(|kv| kv.1.completed == status)
             .map(|(k, v)| k)
             .collect_vec()
     }
 
     pub fn for_each_todo<Action: Fn(&(&String, &bool))>(&self, action: Action) {
-        self.map.iter().for_each(|(kv)| action(&kv))
+        self.map.iter().for_each(|(k, v)| action(&(k, &v.completed)))
     }
 
     pub fn map_todos<Output, Func: Fn((&String, &bool)) -> Output>(
@@ -185,15 +803,20 @@ impl TodoList {
     ) -> Vec<Output> {
         // self.map.iter().map(func).collect_vec()
         let mut output = Vec::new();
-        for (kv) in self.map.iter() {
-            output.push(func(kv));
+        for (k, v) in self.map.iter() {
+            output.push(func((k, &v.completed)));
         }
 
-        return output;
+        output
     }
 
     pub fn filter_todos<Pred: Fn(&(&String, &bool)) -> bool>(&self, pred: Pred) -> Vec<&String> {
-        self.map.iter().filter(pred).map(|(k, v)| k).collect_vec()
+        self.map
+            .iter()
+            .map(|(k, v)| (k, &v.completed))
+            .filter(pred)
+            .map(|(k, v)| k)
+            .collect_vec()
     }
 
     pub fn filter_map_todos<Output, Pred, Func>(&self, pred: Pred, func: Func) -> Vec<Output>
@@ -203,32 +826,24 @@ impl TodoList {
     {
         self.map
             .iter()
+            .map(|(k, v)| (k, &v.completed))
             .filter(pred)
             .map(|(kv)| func(&kv))
             .collect_vec()
     }
 
-    pub fn clone(&self) -> Self {
-        let mut new_map = self.map.clone();
-        Self { map: new_map }
-    }
-
+    /// Compares this list against `other`, producing one [DiffEntry] for every todo whose
+    /// presence or status differs between the two. A todo is never reported more than once.
     pub fn diff_with(&self, other: &Self) -> DiffResult {
-        if self.is_empty() && other.is_empty() {
-            return DiffResult::Same;
-        }
-
-        let mut this_copy = self.map.clone();
-        let mut that_copy = other.map.clone();
         let mut changes: Vec<DiffEntry> = Vec::new();
 
-        for (this_todo, this_status) in self.map.iter() {
-            match that_copy.remove_entry(this_todo) {
-                Some((that_todo, that_status)) => {
-                    if that_status != *this_status {
+        for (this_todo, this_item) in self.map.iter() {
+            match other.get_status(this_todo) {
+                Some(that_status) => {
+                    if that_status != this_item.completed {
                         changes.push(DiffEntry::TodoStatusMistake {
-                            todo: (*this_todo).clone(),
-                            this_status: *this_status,
+                            todo: this_todo.clone(),
+                            this_status: this_item.completed,
                             that_status,
                         });
                     }
@@ -237,25 +852,19 @@ impl TodoList {
                     changes.push(DiffEntry::TodoNotFound {
                         this_has: true,
                         that_has: false,
-                        todo: (*this_todo).clone(),
+                        todo: this_todo.clone(),
+                        completed: this_item.completed,
                     });
                 }
             }
-            this_copy.remove(this_todo);
         }
 
-        assert_eq!(
-            this_copy.len(),
-            0,
-            "After iterating through self.map, this_copy should be empty. this_copy = {:?}",
-            this_copy
-        );
-
-        for (key, value) in that_copy.iter() {
+        for (that_todo, that_item) in other.map.iter().filter(|(todo, _)| !self.map.contains_key(*todo)) {
             changes.push(DiffEntry::TodoNotFound {
-                todo: (*key).clone(),
+                todo: that_todo.clone(),
                 this_has: false,
                 that_has: true,
+                completed: that_item.completed,
             })
         }
 
@@ -266,411 +875,3863 @@ impl TodoList {
         }
     }
 
+    /// Adds `todo` with the status `status`. The text is trimmed of surrounding whitespace
+    /// and refused if nothing is left, newlines within it are kept as they are.
     pub fn add_todo<S: AsRef<str>>(&mut self, todo: S, status: bool) -> Result<(), CommandError> {
-        if todo.as_ref().is_empty() {
+        let text = self.new_todo_text(todo.as_ref())?;
+        self.map.insert(text, Todo::created(status));
+        Ok(())
+    }
+
+    /// Adds `todo` as an incomplete subtask of the existing todo `parent`.
+    pub fn add_subtask<P: AsRef<str>, S: AsRef<str>>(&mut self, parent: P, todo: S) -> Result<(), CommandError> {
+        if !self.map.contains_key(parent.as_ref()) {
+            return Err(CommandError::TodoNotFound);
+        }
+
+        let text = self.new_todo_text(todo.as_ref())?;
+        let mut item = Todo::created(false);
+        item.parent = Some(parent.as_ref().to_string());
+        self.map.insert(text, item);
+        Ok(())
+    }
+
+    /// Returns `todo` trimmed of surrounding whitespace, or an error if nothing is left.
+    /// Every path that gives a todo new text goes through this, newlines within the text
+    /// are kept as they are.
+    fn clean_text(todo: &str) -> Result<&str, CommandError> {
+        let text = todo.trim();
+        if text.is_empty() {
             return Err(CommandError::InputInvalid("Todo is empty".to_string()));
         }
 
-        if self.map.contains_key(todo.as_ref()) {
+        Ok(text)
+    }
+
+    /// Returns the text `todo` would be added as, which is trimmed, or an error if it is
+    /// empty once trimmed or a duplicate of an existing todo.
+    fn new_todo_text(&self, todo: &str) -> Result<String, CommandError> {
+        let text = TodoList::clean_text(todo)?;
+        if self.duplicate_of(text).is_some() {
             return Err(CommandError::TodoAlreadyExists);
         }
 
-        self.map.insert(todo.as_ref().to_string(), status);
-        Ok(())
+        Ok(text.to_string())
     }
 
-    pub fn remove_todo<Text: AsRef<str>>(&mut self, todo: Text) -> Option<(String, bool)> {
-        self.map.remove_entry(todo.as_ref())
+    /// Returns the existing todo that `text` would duplicate, which is the todo with exactly
+    /// that text or, if duplicates are detected ignoring case, one that matches it ignoring
+    /// case and surrounding whitespace. Every path that gives a todo new text checks this.
+    fn duplicate_of(&self, text: &str) -> Option<&String> {
+        if self.case_insensitive_duplicates {
+            self.map.keys().find(|existing| self.same_text(existing, text))
+        } else {
+            self.map.get_key_value(text).map(|(existing, _)| existing)
+        }
     }
 
-    pub fn clear_todos(&mut self) {
-        self.map.clear()
+    /// Returns `true` if `a` and `b` are the same todo text, ignoring case and surrounding
+    /// whitespace if duplicates are detected ignoring case.
+    fn same_text(&self, a: &str, b: &str) -> bool {
+        if self.case_insensitive_duplicates {
+            a.trim().to_lowercase() == b.trim().to_lowercase()
+        } else {
+            a == b
+        }
     }
 
-    /// TODO Need to clean this up. Figure out whether this function wants to interact with the
-    ///     user or whether it wants to execute commands (i.e. it should not be doing both).
-    pub fn apply_action(&mut self, action: ActionPayload) -> Result<(), CommandError> {
-        match action {
-            ActionPayload::Add(key) => {
-                return self.add_todo(key, false);
-            }
-            ActionPayload::Clear => match Prompter::confirm("Are you sure?") {
-                ResponseBool::Value(value) => {
-                    if value {
-                        println!("Clearing all todos...");
-                        self.clear_todos();
-                        println!("Todos cleared.");
-                    } else {
-                        println!("Cancelling clear operation.");
-                    }
-                }
-                ResponseBool::Cancelled => {
-                    println!("Cancelling clear operation.");
-                }
-                ResponseBool::Error(err) => {
-                    println!("Error during prompt: {:?}", err);
-                }
-            },
-            ActionPayload::Edit(existing, new_text) => {
-                if let Some(status) = self.map.remove(&existing) {
-                    self.map.insert(new_text.to_string(), status);
-                } else {
-                    return Err(CommandError::TodoNotFound);
-                }
-            },
-            ActionPayload::List => {
-                if self.is_empty() {
-                    println!("No todos in database, you're either very on top of things or slacking reallllllly bad.");
-                    return Ok(());
-                }
+    /// Returns the text of the direct subtasks of `parent`, in list order.
+    pub fn subtasks_of<Text: AsRef<str>>(&self, parent: Text) -> Vec<&String> {
+        self.map
+            .iter()
+            .filter(|(_, item)| item.parent.as_deref() == Some(parent.as_ref()))
+            .map(|(todo, _)| todo)
+            .collect()
+    }
 
-                println!();
-                println!("All Todos\n--- -----");
-                self.for_each_todo(|(kv)| {
-                    println!("{} {:?}", if *kv.1 { "[X]" } else { "[ ]" }, kv.0)
-                });
-                println!();
-                return Ok(());
-            }
-            ActionPayload::ListWithStatus(kind) => {
-                // TODO This might have performance implications for very large data-sets, keep an eye out.
-                if !self.any_with_status(kind) {
-                    println!(
-                        "There are no {} todos in the database.",
-                        if kind { "completed" } else { "incomplete" }
-                    );
-                    return Ok(());
-                }
+    /// Returns how many of the direct subtasks of `parent` are incomplete. A todo cannot be
+    /// completed while this is more than zero.
+    pub fn incomplete_subtasks<Text: AsRef<str>>(&self, parent: Text) -> usize {
+        self.subtasks_of(parent)
+            .into_iter()
+            .filter(|todo| !self.map[*todo].completed)
+            .count()
+    }
 
-                println!(
-                    "{} Todos\n{} -----",
-                    if kind { "Completed" } else { "Incomplete" },
-                    if kind { "---------" } else { "----------" }
-                );
-                for (k, v) in self.map.iter().filter(|(kv)| *kv.1 == kind) {
-                    println!("\t* {:?}", *k);
-                }
-            }
-            ActionPayload::Remove(key) => {
-                if key.is_empty() {
-                    return Err(CommandError::InputInvalid(s("Todo is empty")));
-                }
+    /// Points the subtasks of `old` at `new`, after `old` has been renamed to it, or makes
+    /// them top level todos if `new` is `None`, after `old` has been removed.
+    fn reparent_subtasks(&mut self, old: &str, new: Option<&str>) {
+        for item in self.map.values_mut().filter(|item| item.parent.as_deref() == Some(old)) {
+            item.parent = new.map(|new| new.to_string());
+        }
+    }
 
-                if let Some(_) = self.remove_todo(&key) {
-                    return Ok(());
-                } else {
-                    return Err(CommandError::TodoNotFound);
-                }
+    /// Sets the status of the todo `todo`, adding it with [`TodoList::add_todo`] if it does
+    /// not exist.
+    pub fn set_status<Text: AsRef<str>>(&mut self, todo: Text, status: bool) -> Result<(), CommandError> {
+        match self.map.get_mut(todo.as_ref()) {
+            Some(item) => {
+                item.set_completed(status);
+                Ok(())
             }
-            ActionPayload::Set(key, val) => {
-                if key.is_empty() {
-                    return Err(CommandError::InputInvalid(s("Todo is empty")));
-                }
+            None => self.add_todo(todo, status),
+        }
+    }
 
-                self.map.insert(key, val);
-            }
-            ActionPayload::Other(input) => {
-                return self.run_debug_command(input);
+    /// Marks each of the existing `todos` as complete, returning how many were incomplete
+    /// beforehand.
+    pub fn complete_todos<S: AsRef<str>>(&mut self, todos: &[S]) -> usize {
+        let mut completed = 0;
+        for todo in todos {
+            if let Some(item) = self.map.get_mut(todo.as_ref()) {
+                if !item.completed {
+                    item.set_completed(true);
+                    completed += 1;
+                }
             }
         }
 
-        Ok(())
+        completed
     }
-}
 
-/// Debug command functions.
-impl TodoList {
-    fn run_debug_command<S: AsRef<str>>(&self, input: S) -> Result<(), CommandError> {
-        match input.as_ref().to_lowercase().as_str() {
-            "encoding" => {
-                return self.run_encoding_test();
-            }
-            "diff" => {
-                return self.run_diff_test();
-            }
-            _ => {
-                println!("Unknown debug command {:?}", input.as_ref());
-            }
-        }
-        Ok(())
+    /// Returns the text of the incomplete todos at `indices`, as chosen from the list
+    /// returned by [`TodoList::get_todos_with_status`]. Out of range indices are ignored.
+    pub fn chosen_incomplete(&self, indices: &[usize]) -> Vec<String> {
+        let incomplete = self.get_todos_with_status(false);
+        indices
+            .iter()
+            .filter_map(|idx| incomplete.get(*idx).map(|todo| todo.to_string()))
+            .collect()
     }
 
-    fn run_encoding_test(&self) -> Result<(), CommandError> {
-        struct RunTime {
-            se_duration: Duration,
-            de_duration: Duration,
+    /// Flips the status of the existing todo `todo`, returning its new status.
+    pub fn toggle_todo<Text: AsRef<str>>(&mut self, todo: Text) -> Result<bool, CommandError> {
+        match self.map.get_mut(todo.as_ref()) {
+            Some(item) => {
+                item.set_completed(!item.completed);
+                Ok(item.completed)
+            }
+            None => Err(CommandError::TodoNotFound),
         }
+    }
 
-        impl RunTime {
-            fn empty() -> Self {
-                Self {
-                    se_duration: Duration::default(),
-                    de_duration: Duration::default(),
+    /// Adds `tag` to the existing todo `todo`. Tags the todo already has are not repeated.
+    pub fn tag_todo<Text: AsRef<str>, Tag: AsRef<str>>(&mut self, todo: Text, tag: Tag) -> Result<(), CommandError> {
+        match self.map.get_mut(todo.as_ref()) {
+            Some(item) => {
+                if !item.tags.iter().any(|existing| existing == tag.as_ref()) {
+                    item.tags.push(tag.as_ref().to_string());
                 }
+                Ok(())
             }
+            None => Err(CommandError::TodoNotFound),
+        }
+    }
 
-            fn from_se_time(d: Duration) -> Self {
-                Self {
-                    se_duration: d,
-                    de_duration: Duration::default(),
+    /// Lowercases the tags of every todo and removes the duplicates that leaves, so that
+    /// tags such as `Work` and `work` are merged into one. The first occurrence of each tag
+    /// keeps its place. Returns the number of tags merged away.
+    pub fn normalize_tags(&mut self) -> usize {
+        let mut merged = 0;
+        for item in self.map.values_mut() {
+            let mut tags: Vec<String> = Vec::with_capacity(item.tags.len());
+            for tag in item.tags.drain(..) {
+                let tag = tag.to_lowercase();
+                if tags.contains(&tag) {
+                    merged += 1;
+                } else {
+                    tags.push(tag);
                 }
             }
+            item.tags = tags;
+        }
 
-            fn set_se_duration(&mut self, d: Duration) {
-                self.se_duration = d;
+        merged
+    }
+
+    /// Removes the tag `filter` from every todo, or every tag from every todo if `filter`
+    /// is `None`. Returns the number of todos that had tags removed.
+    pub fn clear_tags(&mut self, filter: Option<&str>) -> usize {
+        let mut changed = 0;
+        for item in self.map.values_mut() {
+            let before = item.tags.len();
+            match filter {
+                Some(tag) => item.tags.retain(|existing| existing != tag),
+                None => item.tags.clear(),
             }
 
-            fn set_de_duration(&mut self, d: Duration) {
-                self.de_duration = d;
+            if item.tags.len() != before {
+                changed += 1;
             }
         }
 
-        println!("Running serialization comparison...\n");
-        let mut byte_map: HashMap<EncodingType, Vec<u8>> = HashMap::new();
-        let mut time_map: HashMap<EncodingType, RunTime> = HashMap::new();
+        changed
+    }
 
-        for ty in EncodingType::all() {
-            println!("Starting {}", ty);
-            let start = Instant::now();
-            match Cereal::serialize_with(ty, self) {
-                Ok(data) => {
-                    let duration = start.elapsed();
-                    time_map.insert(ty, RunTime::from_se_time(duration));
-                    byte_map.insert(ty, data);
-                }
-                Err(err) => {
-                    println!("There was an error with {:?}: {}", ty, err);
-                }
+    /// Sets the color of the existing todo `todo` to the palette color `color`, see
+    /// [COLOR_PALETTE](super::todo::COLOR_PALETTE).
+    pub fn set_color<Text: AsRef<str>, Color: AsRef<str>>(&mut self, todo: Text, color: Color) -> Result<(), CommandError> {
+        validate_color(&color)?;
+        match self.map.get_mut(todo.as_ref()) {
+            Some(item) => {
+                item.color = Some(color.as_ref().to_lowercase());
+                Ok(())
             }
+            None => Err(CommandError::TodoNotFound),
+        }
+    }
 
-            println!("{} Serialization Complete.", ty);
+    /// Sets the note on the existing todo `todo`, or removes it if `description` is `None`.
+    pub fn set_description<Text: AsRef<str>>(
+        &mut self,
+        todo: Text,
+        description: Option<String>,
+    ) -> Result<(), CommandError> {
+        match self.map.get_mut(todo.as_ref()) {
+            Some(item) => {
+                item.description = description;
+                Ok(())
+            }
+            None => Err(CommandError::TodoNotFound),
         }
+    }
 
-        for (ty, bytes) in byte_map.iter() {
-            let file_name = format!("./data/{}.dat", ty);
-            match FileSystem::save_bytes(&file_name, bytes) {
-                Ok(_) => println!("Wrote {} successfully!", &file_name),
-                Err(e) => println!("Error writing {}: {}", &file_name, e),
+    /// Sets the priority of the existing todo `todo`, or clears it if `priority` is `None`.
+    pub fn set_priority<Text: AsRef<str>>(&mut self, todo: Text, priority: Option<Priority>) -> Result<(), CommandError> {
+        match self.map.get_mut(todo.as_ref()) {
+            Some(item) => {
+                item.priority = priority;
+                Ok(())
             }
+            None => Err(CommandError::TodoNotFound),
         }
+    }
 
-        for ty in EncodingType::all() {
-            let file_name = format!("./data/{}.dat", ty);
-            let path = std::path::Path::new(&file_name);
-            match path.try_exists() {
-                Ok(exists) => {
-                    if !exists {
-                        println!("Error, path {:?} does not exist!", path);
-                        continue;
-                    }
+    /// Moves the priority of the todo `todo` up one level if `up` is `true`, otherwise down
+    /// one level, clamping at [Priority::High] and [Priority::Low]. A todo without a
+    /// priority is given [Priority::Low]. Returns the new priority, or `None` if the todo
+    /// does not exist.
+    pub fn bump_priority<Text: AsRef<str>>(&mut self, todo: Text, up: bool) -> Option<Priority> {
+        let item = self.map.get_mut(todo.as_ref())?;
+        let bumped = match item.priority {
+            Some(priority) if up => priority.raised(),
+            Some(priority) => priority.lowered(),
+            None => Priority::Low,
+        };
+        item.priority = Some(bumped);
+        Some(bumped)
+    }
 
-                    match FileSystem::load_bytes(path) {
-                        Ok(bytes) => {
-                            let start = Instant::now();
-                            match Cereal::deserialize_with::<TodoList>(ty, &bytes) {
-                                Ok(recreated) => {
-                                    let duration = start.elapsed();
-                                    println!(
-                                        "Encoding {} took {}ms to deserialize.",
-                                        ty,
-                                        duration.as_millis()
-                                    );
-                                    time_map.entry(ty).and_modify(|rt| {
-                                        rt.de_duration = duration;
-                                    });
-                                    match self.diff_with(&recreated) {
-                                        DiffResult::Same => {
-                                            println!("Recreated todo-list from {} data and it matches the original!", ty);
-                                        }
-                                        DiffResult::Changes(diffs) => {
-                                            println!("Recreated todo-list {:?} from {} data and it does NOT match the original.", recreated, ty);
-                                            println!(
-                                                "Diff Results:\n\t{}\n",
-                                                diffs
-                                                    .iter()
-                                                    .map(|diff_entry| diff_entry.to_string())
-                                                    .join("\n\t")
-                                            )
-                                        }
-                                    }
-                                }
-                                Err(e) => println!("Error deserializing todolist: {}", e),
-                            }
-                        }
-                        Err(e) => println!("Error loading bytes from file: {}", e),
-                    }
-                }
-                Err(err) => println!("Error with path {:?}: {}", path, err),
-            }
+    /// Returns the text of every todo starting with `prefix`, or of every todo if it is
+    /// `None`, in list order.
+    pub fn todos_starting_with(&self, prefix: Option<&str>) -> Vec<&String> {
+        self.map
+            .keys()
+            .filter(|todo| prefix.map_or(true, |prefix| todo.starts_with(prefix)))
+            .collect()
+    }
+
+    /// Returns the text of every todo matching `re`, in list order.
+    pub fn grep(&self, re: &Regex) -> Vec<&String> {
+        self.map.keys().filter(|todo| re.is_match(todo)).collect()
+    }
+
+    /// Returns how many todos the search `action` matches without rendering them, for the
+    /// `--count-only` flag. Only [`ActionPayload::Grep`] is a search, any other action is
+    /// rejected.
+    pub fn count_matches(&self, action: &ActionPayload) -> Result<usize, CommandError> {
+        match action {
+            ActionPayload::Grep(pattern) => Ok(self.grep(&compile_pattern(pattern)?).len()),
+            action => Err(CommandError::InputInvalid(format!(
+                "only grep can count its matches, not {:?}",
+                action.get_action_type().get_input_string()
+            ))),
         }
+    }
 
-        println!("Serialization Size Results");
-        println!(
-            "{:^12}{:^7}",
-            "Encoding".fg::<colors::White>().underline(),
-            "Bytes".fg::<colors::Cyan>().underline()
-        );
-        let mut first = true;
-        for (ty, bytes) in byte_map
-            .iter()
-            .sorted_by(|a, b| Ord::cmp(&a.1.len(), &b.1.len()))
-        {
-            if first {
-                println!(
-                    "{:^12}{:^7}",
-                    ty.fg::<colors::White>(),
-                    bytes.len().fg::<colors::BrightGreen>()
-                );
-
-                first = false
+    /// Works out how replacing every match of `re` with `rep` would rename the todos,
+    /// without changing anything. `rep` may refer to capture groups as in
+    /// [`Regex::replace_all`], and the new text is trimmed. A rename is a conflict if the new
+    /// text is empty, duplicates a todo already in the list, or duplicates the new text of
+    /// another rename.
+    pub fn replace(&self, re: &Regex, rep: &str) -> ReplaceReport {
+        let renames: Vec<(String, String)> = self
+            .map
+            .keys()
+            .map(|todo| {
+                let replaced = re.replace_all(todo, rep);
+                let new = TodoList::clean_text(&replaced).unwrap_or_default().to_string();
+                (todo.clone(), new)
+            })
+            .filter(|(old, new)| old != new)
+            .collect();
+
+        let mut report = ReplaceReport::default();
+        for (old, new) in &renames {
+            let shared = renames.iter().filter(|(_, other)| self.same_text(other, new)).count() > 1;
+            let taken = self.duplicate_of(new).map_or(false, |other| other != old);
+            if new.is_empty() || shared || taken {
+                report.conflicts.push((old.clone(), new.clone()));
             } else {
-                println!(
-                    "{:^12}{:^7}",
-                    ty.fg::<colors::White>(),
-                    bytes.len().fg::<colors::Cyan>()
-                );
+                report.renamed.push((old.clone(), new.clone()));
             }
         }
-        println!();
 
-        println!("Serialization Time Results (in MS)");
-        println!(
-            "{:^12}{:^9}{:^9}",
-            "Encoding".fg::<colors::White>().underline(),
-            "Se Time".fg::<colors::Cyan>().underline(),
-            "De Time".fg::<colors::Yellow>().underline(),
-        );
-        first = true;
-        for (ty, rt) in time_map
-            .iter()
-            .sorted_by(|a, b| Ord::cmp(&a.1.se_duration, &b.1.se_duration))
-        {
-            if first {
-                println!(
-                    "{:^12}{:^9}{:^9}",
-                    ty.fg::<colors::White>(),
-                    format!("{:?}", rt.se_duration).fg::<colors::BrightGreen>(),
-                    format!("{:?}", rt.de_duration).fg::<colors::BrightGreen>(),
-                );
-                first = false;
-            } else {
-                println!(
-                    "{:^12}{:^9}{:^9}",
-                    ty.fg::<colors::White>(),
-                    format!("{:?}", rt.se_duration).fg::<colors::Cyan>(),
-                    format!("{:?}", rt.de_duration).fg::<colors::Yellow>(),
-                );
+        report
+    }
+
+    /// Applies the renames of a [ReplaceReport] from [`TodoList::replace`], keeping each
+    /// todo in its place. Returns how many todos were renamed.
+    pub fn apply_replace(&mut self, report: &ReplaceReport) -> usize {
+        let mut count = 0;
+        for (old, new) in &report.renamed {
+            if self.duplicate_of(new).map_or(false, |other| other != old) {
+                continue;
+            }
+            if let Some((index, _, item)) = self.map.shift_remove_full(old) {
+                let (inserted, _) = self.map.insert_full(new.clone(), item);
+                self.move_index(inserted, index);
+                self.reparent_subtasks(old, Some(new));
+                count += 1;
             }
         }
-        println!();
 
-        Ok(())
+        count
     }
 
-    fn run_diff_test(&self) -> Result<(), CommandError> {
-        if self.is_empty() {
-            return Err(CommandError::InputInvalid(
-                "TodoList must have at least 1 entry in order to run diff test!".to_string(),
-            ));
+    /// Renames `existing` to `new_text`, trimmed, keeping its place in the list, its status
+    /// and its metadata. Renaming a todo to its own text does nothing. Returns
+    /// [`CommandError::TodoAlreadyExists`] if `new_text` is already another todo, otherwise
+    /// the text the todo now has.
+    pub fn rename_todo(&mut self, existing: &str, new_text: &str) -> Result<String, CommandError> {
+        if !self.map.contains_key(existing) {
+            return Err(CommandError::TodoNotFound);
+        }
+        let new_text = TodoList::clean_text(new_text)?;
+        if existing == new_text {
+            return Ok(new_text.to_string());
+        }
+        if matches!(self.duplicate_of(new_text), Some(other) if other != existing) {
+            return Err(CommandError::TodoAlreadyExists);
         }
 
-        let mut other = self.clone();
-        println!("Diffing against cloned other...");
-        match self.diff_with(&other) {
-            DiffResult::Same => {
-                println!("Diff returned Same!");
-            }
-            DiffResult::Changes(diffs) => {
-                println!("Uh-oh, diff returned the following changes:");
-                for (i, d) in diffs.iter().enumerate() {
-                    println!("#{}: {}", i + 1, d);
-                }
+        if let Some((index, _, item)) = self.map.shift_remove_full(existing) {
+            let (inserted, _) = self.map.insert_full(new_text.to_string(), item);
+            self.move_index(inserted, index);
+            self.reparent_subtasks(existing, Some(new_text));
+        }
+
+        Ok(new_text.to_string())
+    }
+
+    /// Returns every todo with its due date, soonest first and those without one last, see
+    /// [due_date_ord]. Todos due on the same day keep their order in the list.
+    pub fn todos_by_due(&self) -> Vec<(&String, Option<NaiveDate>)> {
+        let mut todos: Vec<(&String, Option<NaiveDate>)> = self.map.iter().map(|(todo, item)| (todo, item.due)).collect();
+        todos.sort_by(|(_, a), (_, b)| due_date_ord(a, b));
+        todos
+    }
+
+    /// Returns the text of every todo created or completed after `since`, in list order.
+    pub fn changed_since(&self, since: DateTime<Utc>) -> Vec<&String> {
+        self.map
+            .iter()
+            .filter(|(_, item)| item.created_at.max(item.completed_at).map_or(false, |changed| changed > since))
+            .map(|(todo, _)| todo)
+            .collect()
+    }
+
+    /// Returns the text of every todo with the priority `priority`, sorted by text.
+    pub fn todos_with_priority(&self, priority: Priority) -> Vec<&String> {
+        self.map
+            .iter()
+            .filter(|(_, item)| item.priority == Some(priority))
+            .map(|(todo, _)| todo)
+            .sorted()
+            .collect()
+    }
 
-                println!("");
+    /// Sets the day the existing todo `todo` is due, or clears it if `due` is `None`.
+    pub fn set_due<Text: AsRef<str>>(&mut self, todo: Text, due: Option<NaiveDate>) -> Result<(), CommandError> {
+        match self.map.get_mut(todo.as_ref()) {
+            Some(item) => {
+                item.due = due;
+                Ok(())
             }
+            None => Err(CommandError::TodoNotFound),
         }
+    }
 
-        let mut rng = rand::thread_rng();
-        let changes: usize = rng.gen_range(1..self.len());
-        println!("Making {} changes.", changes);
-        for i in 0..changes {
-            match rng.gen_range(0..3) {
-                0 => {
-                    // Change status
-                    let idx = rng.gen_range(0..other.len());
-                    let (todo, status) = match other.map.iter().nth(idx) {
-                        Some((s, b)) => (s.as_str().to_string(), *b),
-                        None => unreachable!(),
-                    };
-                    println!(
-                        "\t- Change #{}: Changing status of {:?} from {} to {}",
-                        i + 1,
-                        &todo,
-                        status,
-                        !status
-                    );
-                    other.map.insert(todo, !status);
-                }
-                1 => {
-                    // Add Todo
-                    let to_add = format!("Here is a random todo I added. {}", rng.gen::<usize>());
-                    let status = rng.gen_bool(0.5);
-                    println!(
-                        "\t- Change #{}: Adding random todo {:?} with status {}",
-                        i + 1,
-                        &to_add,
-                        status
-                    );
-                    other.add_todo(&to_add, status);
-                }
-                2 => {
-                    // Remove Todo
-                    let idx = rng.gen_range(0..other.len());
-                    let existing = match other.map.iter().nth(idx) {
-                        Some((s, b)) => s.to_owned(),
-                        None => unreachable!(),
-                    };
-                    println!(
-                        "\t- Change #{}: Removing random todo {:?}",
-                        i + 1,
-                        &existing
-                    );
-                    other.remove_todo(&existing);
-                }
-                _ => unreachable!(),
+    /// Sets how often the existing todo `todo` recurs, or stops it recurring if
+    /// `recurrence` is `None`.
+    pub fn set_recurrence<Text: AsRef<str>>(&mut self, todo: Text, recurrence: Option<Recurrence>) -> Result<(), CommandError> {
+        match self.map.get_mut(todo.as_ref()) {
+            Some(item) => {
+                item.recurrence = recurrence;
+                Ok(())
             }
+            None => Err(CommandError::TodoNotFound),
         }
+    }
 
-        println!("Diffing against modified other...");
-        match self.diff_with(&other) {
-            DiffResult::Same => {
-                println!("Uh-oh, diff returned Same!");
-            }
-            DiffResult::Changes(diffs) => {
-                // TODO This is throwing false positives when the status is changed on an added todo.
-                if diffs.len() == changes {
-                    println!(
-                        "Hurray, diff returned the correct number of changes ({}).",
-                        changes
-                    );
-                } else {
-                    println!(
-                        "Uh oh, there are {} diff results but {} changes were made.",
-                        diffs.len(),
-                        changes
-                    );
-                }
+    /// Brings back the recurring todo `todo` if it has just been completed. The completed
+    /// todo is kept as a record, renamed to include the day it was done, and takes none of
+    /// the recurrence with it. `todo` itself becomes a fresh incomplete todo in the same
+    /// place, keeping its tags, color, note, priority and recurrence, and due one
+    /// recurrence later if it had a due date. Returns the text the completed record was
+    /// saved as, or `None` if `todo` is not a completed recurring todo.
+    pub fn recur<Text: AsRef<str>>(&mut self, todo: Text) -> Option<String> {
+        let (index, _, item) = self.map.get_full(todo.as_ref())?;
+        let recurrence = item.recurrence.filter(|_| item.completed)?;
 
-                println!();
-                println!("Diff Entries:");
-                for (i, d) in diffs.iter().enumerate() {
-                    println!("#{}: {}", i + 1, d);
-                }
+        let done_on = item.completed_at.unwrap_or_else(Utc::now).date().naive_utc();
+        let mut record_text = format!("{} ({})", todo.as_ref(), done_on.format(DEFAULT_DATE_FORMAT));
+        let mut count = 1;
+        while self.map.contains_key(&record_text) {
+            count += 1;
+            record_text = format!("{} ({} #{})", todo.as_ref(), done_on.format(DEFAULT_DATE_FORMAT), count);
+        }
+
+        let next = Todo {
+            tags: item.tags.clone(),
+            color: item.color.clone(),
+            description: item.description.clone(),
+            priority: item.priority,
+            due: item.due.map(|due| recurrence.next_due(due)),
+            recurrence: Some(recurrence),
+            ..Todo::created(false)
+        };
+        let mut record = std::mem::replace(self.map.get_index_mut(index)?.1, next);
+        record.recurrence = None;
+        let (inserted, _) = self.map.insert_full(record_text.clone(), record);
+        self.move_index(inserted, index + 1);
 
-                println!("");
+        Some(record_text)
+    }
+
+    /// Adds each of `todos` as an incomplete todo. Todos that already exist, including
+    /// repeats within `todos`, are skipped rather than failing the whole batch.
+    pub fn add_todos<S: AsRef<str>>(&mut self, todos: &[S]) -> AddSummary {
+        let mut summary = AddSummary::default();
+        for todo in todos {
+            match self.add_todo(todo, false) {
+                Ok(_) => summary.added.push(todo.as_ref().trim().to_string()),
+                Err(CommandError::InputInvalid(_)) => summary.blank += 1,
+                Err(_) => summary.skipped.push(todo.as_ref().to_string()),
             }
         }
 
-        Ok(())
+        summary
+    }
+
+    pub fn remove_todo<Text: AsRef<str>>(&mut self, todo: Text) -> Option<(String, bool)> {
+        let removed = self
+            .map
+            .shift_remove_full(todo.as_ref())
+            .map(|(_, todo, item)| (todo, item.completed));
+        if removed.is_some() {
+            self.reparent_subtasks(todo.as_ref(), None);
+        }
+
+        removed
+    }
+
+    /// Returns each incomplete todo along with how long it has been open at `now`, in list
+    /// order. The age is `None` for todos saved before creation times were recorded.
+    pub fn open_ages(&self, now: DateTime<Utc>) -> Vec<(&String, Option<chrono::Duration>)> {
+        self.map
+            .iter()
+            .filter(|(_, item)| !item.completed)
+            .map(|(todo, item)| (todo, item.created_at.map(|created_at| now - created_at)))
+            .collect()
+    }
+
+    /// Returns the incomplete todos that have been open for more than `days` days at `now`,
+    /// along with their age in days, oldest first. Todos without a creation time are never
+    /// stale.
+    pub fn stale_todos(&self, now: DateTime<Utc>, days: usize) -> Vec<(&String, i64)> {
+        self.open_ages(now)
+            .into_iter()
+            .filter_map(|(todo, age)| age.map(|age| (todo, age.num_days())))
+            .filter(|(_, age)| *age > days as i64)
+            .sorted_by(|a, b| b.1.cmp(&a.1))
+            .collect()
+    }
+
+    /// Builds the [WeeklyDigest] for the week containing `today`.
+    pub fn weekly_digest(&self, today: NaiveDate) -> WeeklyDigest {
+        let monday = today - chrono::Duration::days(i64::from(today.weekday().num_days_from_monday()));
+        let sunday = monday + chrono::Duration::days(6);
+        let this_week = |stamp: &Option<DateTime<Utc>>| {
+            stamp.map_or(false, |stamp| (monday..=today).contains(&stamp.naive_utc().date()))
+        };
+
+        let mut digest = WeeklyDigest::default();
+        for (todo, item) in self.map.iter() {
+            if item.completed && this_week(&item.completed_at) {
+                digest.completed.push(todo.clone());
+            }
+            if this_week(&item.created_at) {
+                digest.added.push(todo.clone());
+            }
+            match item.due {
+                Some(due) if !item.completed && due < today => digest.overdue.push(todo.clone()),
+                Some(due) if !item.completed && due <= sunday => digest.due.push(todo.clone()),
+                _ => {}
+            }
+        }
+
+        digest
+    }
+
+    /// Applies every action in `patch` to this list, see [`DiffResult::to_patch`]. The
+    /// patch is applied as a whole, if any of its actions fails, for example because it
+    /// removes a todo this list does not have, the list is left unchanged. Returns the
+    /// number of actions applied.
+    pub fn apply_patch(&mut self, patch: &TodoPatch) -> Result<usize, CommandError> {
+        self.map = self.patched(patch)?.map;
+        Ok(patch.len())
+    }
+
+    /// Returns a copy of this list with `patch` applied, see [`TodoList::apply_patch`].
+    fn patched(&self, patch: &TodoPatch) -> Result<TodoList, CommandError> {
+        let mut patched = self.clone();
+        for (index, action) in patch.actions.iter().enumerate() {
+            if let ActionPayload::ApplyPatch(_) = action {
+                return Err(CommandError::InputInvalid(s("Patches cannot apply other patches")));
+            }
+            patched.apply_confirmed(action.clone()).map_err(|err| {
+                CommandError::InputInvalid(format!(
+                    "Patch does not match this list, change {} ({}) failed: {}",
+                    index + 1,
+                    action.input_cmd_string(),
+                    err
+                ))
+            })?;
+        }
+
+        Ok(patched)
+    }
+
+    /// Moves the existing todo `todo` to the zero-based `position` in the list, shifting the
+    /// todos in between. Positions past the end of the list move the todo to the end.
+    pub fn move_todo<Text: AsRef<str>>(&mut self, todo: Text, position: usize) -> Result<(), CommandError> {
+        match self.map.get_full(todo.as_ref()) {
+            Some((index, _, _)) => {
+                self.move_index(index, position);
+                Ok(())
+            }
+            None => Err(CommandError::TodoNotFound),
+        }
+    }
+
+    /// Replaces the todo `todo` with a new, incomplete todo for each of `parts`, in the same
+    /// place in the list. The new todos keep the tags of `todo`. Parts are trimmed, and
+    /// nothing is changed if any part is blank, repeated, or the text of another existing
+    /// todo. Returns the new todos.
+    pub fn split_todo<Text: AsRef<str>, S: AsRef<str>>(&mut self, todo: Text, parts: &[S]) -> Result<Vec<String>, CommandError> {
+        let (index, _, original) = self.map.get_full(todo.as_ref()).ok_or(CommandError::TodoNotFound)?;
+        if parts.is_empty() {
+            return Err(CommandError::InputInvalid("No todos to split into".to_string()));
+        }
+
+        let mut created: Vec<String> = Vec::new();
+        for part in parts.iter().map(|part| part.as_ref()) {
+            let part = TodoList::clean_text(part)?;
+            let duplicate = matches!(self.duplicate_of(part), Some(other) if other != todo.as_ref());
+            if duplicate || created.iter().any(|text| self.same_text(text, part)) {
+                return Err(CommandError::TodoAlreadyExists);
+            }
+            created.push(part.to_string());
+        }
+
+        let tags = original.tags.clone();
+        let parent = original.parent.clone();
+        let mut entries: Vec<(String, Todo)> = std::mem::take(&mut self.map).into_iter().collect();
+        entries.splice(
+            index..=index,
+            created.iter().map(|text| {
+                let mut item = Todo::created(false);
+                item.tags = tags.clone();
+                item.parent = parent.clone();
+                (text.clone(), item)
+            }),
+        );
+        self.map = entries.into_iter().collect();
+        if !created.iter().any(|text| text == todo.as_ref()) {
+            self.reparent_subtasks(todo.as_ref(), None);
+        }
+
+        Ok(created)
+    }
+
+    /// Replaces the existing `todos` with a single todo, in the place of the first of them,
+    /// whose text is theirs joined with [COMBINED_SEPARATOR]. It has every tag of the
+    /// originals and is complete only if all of them were. Returns the combined todo.
+    pub fn combine_todos<S: AsRef<str>>(&mut self, todos: &[S]) -> Result<String, CommandError> {
+        let todos: Vec<&str> = todos.iter().map(|todo| todo.as_ref()).collect();
+        if todos.len() < 2 {
+            return Err(CommandError::InputInvalid("At least two todos are needed to combine".to_string()));
+        }
+        if todos.iter().unique().count() != todos.len() {
+            return Err(CommandError::InputInvalid("Each todo can only be combined once".to_string()));
+        }
+
+        let mut index = self.map.len();
+        let mut tags: Vec<String> = Vec::new();
+        let mut completed = true;
+        for todo in &todos {
+            let (position, _, item) = self.map.get_full(*todo).ok_or(CommandError::TodoNotFound)?;
+            index = index.min(position);
+            completed &= item.completed;
+            for tag in &item.tags {
+                if !tags.contains(tag) {
+                    tags.push(tag.clone());
+                }
+            }
+        }
+
+        let combined = TodoList::clean_text(&todos.join(COMBINED_SEPARATOR))?.to_string();
+        if matches!(self.duplicate_of(&combined), Some(other) if !todos.contains(&other.as_str())) {
+            return Err(CommandError::TodoAlreadyExists);
+        }
+
+        for todo in &todos {
+            self.map.shift_remove(*todo);
+            self.reparent_subtasks(todo, Some(&combined));
+        }
+        let mut item = Todo::created(completed);
+        item.tags = tags;
+        self.map.insert(combined.clone(), item);
+        self.move_index(self.map.len() - 1, index);
+
+        Ok(combined)
+    }
+
+    /// Moves the entry at index `from` to index `to`, shifting the entries in between.
+    fn move_index(&mut self, from: usize, to: usize) {
+        let mut entries: Vec<(String, Todo)> = std::mem::take(&mut self.map).into_iter().collect();
+        let entry = entries.remove(from);
+        entries.insert(to.min(entries.len()), entry);
+        self.map = entries.into_iter().collect();
+    }
+
+    pub fn clear_todos(&mut self) {
+        self.map.clear()
+    }
+
+    /// Removes every completed todo, returning the number of todos removed.
+    pub fn purge_completed(&mut self) -> usize {
+        let completed: Vec<String> = self.get_todos_with_status(true).into_iter().cloned().collect();
+        for todo in &completed {
+            self.remove_todo(todo);
+        }
+
+        completed.len()
+    }
+
+    /// Applies `action` to this list and returns an [ActionOutcome] describing what
+    /// happened. This never prints or prompts, presenting the outcome is up to the caller.
+    /// Actions that delete in bulk return [`ActionOutcome::NeedsConfirmation`] without
+    /// changing anything, unless [`TodoList::set_assume_yes`] was given `true`. Once the
+    /// user agrees, apply the action with [`TodoList::apply_confirmed`].
+    pub fn apply_action(&mut self, action: ActionPayload) -> Result<ActionOutcome, CommandError> {
+        self.apply_action_with(action, self.assume_yes)
+    }
+
+    /// Applies `action` to this list, skipping any confirmation it would otherwise need.
+    pub fn apply_confirmed(&mut self, action: ActionPayload) -> Result<ActionOutcome, CommandError> {
+        self.apply_action_with(action, true)
+    }
+
+    /// Returns the yes or no prompt the user must agree to before `action` is applied, or
+    /// `None` if it can be applied straight away.
+    pub fn needs_confirmation(&self, action: &ActionPayload) -> Option<String> {
+        if self.assume_yes {
+            return None;
+        }
+
+        let todos = |count: usize| if count == 1 { "todo" } else { "todos" };
+        match action {
+            ActionPayload::Clear if !self.is_empty() => Some(format!(
+                "Are you sure you want to delete all {} {}?",
+                self.len(),
+                todos(self.len())
+            )),
+            ActionPayload::ClearTags(None) => Some(s("Remove all tags from every todo?")),
+            ActionPayload::Replace(pattern, rep) => match Regex::new(pattern).map(|re| self.replace(&re, rep).renamed.len()) {
+                Ok(0) | Err(_) => None,
+                Ok(count) => Some(format!("Rename {} {} matching {:?}?", count, todos(count), pattern)),
+            },
+            ActionPayload::PurgeCompleted => match self.get_todos_with_status(true).len() {
+                0 => None,
+                count => Some(format!(
+                    "Are you sure you want to delete {} completed {}?",
+                    count,
+                    todos(count)
+                )),
+            },
+            _ => None,
+        }
+    }
+
+    fn apply_action_with(&mut self, action: ActionPayload, confirmed: bool) -> Result<ActionOutcome, CommandError> {
+        self.validate_action(&action)?;
+
+        if !confirmed {
+            if let Some(prompt) = self.needs_confirmation(&action) {
+                return Ok(ActionOutcome::NeedsConfirmation(prompt));
+            }
+        }
+
+        let outcome = match action {
+            ActionPayload::Add(key) => {
+                self.add_todo(&key, false)?;
+                ActionOutcome::Added(key)
+            }
+            ActionPayload::AddMany(keys) => ActionOutcome::AddedMany(self.add_todos(&keys)),
+            ActionPayload::AddSub(parent, key) => {
+                self.add_subtask(&parent, &key)?;
+                ActionOutcome::AddedSub(parent, key)
+            }
+            ActionPayload::ApplyPatch(file) => {
+                let count = self.apply_patch(&TodoPatch::load(&file)?)?;
+                ActionOutcome::Patched { file, count }
+            }
+            ActionPayload::Bump(key, up) => match self.bump_priority(&key, up) {
+                Some(priority) => ActionOutcome::PrioritySet(key, Some(priority)),
+                None => return Err(CommandError::TodoNotFound),
+            },
+            ActionPayload::Clear => {
+                let count = self.len();
+                self.clear_todos();
+                ActionOutcome::Cleared(count)
+            }
+            ActionPayload::ClearTags(tag) => {
+                let count = self.clear_tags(tag.as_deref());
+                ActionOutcome::TagsCleared { tag, count }
+            }
+            ActionPayload::Color(key, color) => {
+                self.set_color(&key, &color)?;
+                ActionOutcome::Colored(key, color.to_lowercase())
+            }
+            ActionPayload::Combine(keys) => {
+                let combined = self.combine_todos(&keys)?;
+                ActionOutcome::Combined(keys, combined)
+            }
+            ActionPayload::Complete(keys) => {
+                let completed = self.complete_todos(&keys);
+                for key in &keys {
+                    self.recur(key);
+                }
+                ActionOutcome::Completed(completed)
+            }
+            ActionPayload::Edit(existing, new_text) => {
+                let renamed = self.rename_todo(&existing, &new_text)?;
+                ActionOutcome::Edited(existing, renamed)
+            }
+            ActionPayload::Export(file) => {
+                self.export_file(&file)?;
+                ActionOutcome::Exported {
+                    file,
+                    count: self.len(),
+                }
+            }
+            ActionPayload::Import(file) => ActionOutcome::Imported(self.import_file(&file)?),
+            ActionPayload::IsEmpty(status) => ActionOutcome::IsEmpty(self.is_empty_with(status)),
+            ActionPayload::Due(key, due) => {
+                self.set_due(&key, due)?;
+                ActionOutcome::DueSet(key, due)
+            }
+            ActionPayload::Age
+            | ActionPayload::CompleteData(_)
+            | ActionPayload::Completions(_)
+            | ActionPayload::Digest
+            | ActionPayload::Dump
+            | ActionPayload::Grep(_)
+            | ActionPayload::List
+            | ActionPayload::ListSince(_)
+            | ActionPayload::ListWithStatus(_)
+            | ActionPayload::ListByDue
+            | ActionPayload::ListByPriority(_)
+            | ActionPayload::Stale(_) => {
+                ActionOutcome::Listing(self.render_listing(&action, self.colorize).unwrap_or_default())
+            }
+            ActionPayload::Note(key, note) => {
+                let saved = note.is_some();
+                self.set_description(&key, note)?;
+                ActionOutcome::Noted(key, saved)
+            }
+            ActionPayload::Show(_) => ActionOutcome::Listing(self.render_listing(&action, self.colorize).unwrap_or_default()),
+            ActionPayload::Split(key, parts) => {
+                let created = self.split_todo(&key, &parts)?;
+                ActionOutcome::Split(key, created)
+            }
+            ActionPayload::PurgeCompleted => match self.purge_completed() {
+                0 => ActionOutcome::Nothing(s("There are no completed todos to purge.")),
+                removed => ActionOutcome::Purged(removed),
+            },
+            ActionPayload::Remove(key) => {
+                self.remove_todo(&key);
+                ActionOutcome::Removed(key)
+            }
+            ActionPayload::Replace(pattern, rep) => {
+                let report = self.replace(&compile_pattern(&pattern)?, &rep);
+                self.apply_replace(&report);
+                ActionOutcome::Replaced(report)
+            }
+            ActionPayload::Replay(log) => {
+                ActionOutcome::Listing(self.render_replay(&log, self.colorize)?)
+            }
+            ActionPayload::CopyProfile(_, _)
+            | ActionPayload::DeleteProfile(_)
+            | ActionPayload::DiffProfiles(_, _)
+            | ActionPayload::Recover
+            | ActionPayload::RenameProfile(_, _) => {
+                unreachable!("Profile commands are rejected by validate_action")
+            }
+            ActionPayload::Serve => unreachable!("Serve is rejected by validate_action"),
+            ActionPayload::Triage => unreachable!("Triage is rejected by validate_action"),
+            ActionPayload::Set(key, val) => {
+                let key = if self.map.contains_key(&key) {
+                    key
+                } else {
+                    TodoList::clean_text(&key)?.to_string()
+                };
+                self.set_status(&key, val)?;
+                self.recur(&key);
+                ActionOutcome::StatusSet(key, val)
+            }
+            ActionPayload::SetPriority(key, priority) => {
+                self.set_priority(&key, priority)?;
+                ActionOutcome::PrioritySet(key, priority)
+            }
+            ActionPayload::SetRecurrence(key, recurrence) => {
+                self.set_recurrence(&key, recurrence)?;
+                ActionOutcome::RecurrenceSet(key, recurrence)
+            }
+            ActionPayload::Move(key, position) => {
+                self.move_todo(&key, position)?;
+                ActionOutcome::Moved(key, position.min(self.len() - 1))
+            }
+            ActionPayload::Tag(key, tag) => {
+                self.tag_todo(&key, &tag)?;
+                ActionOutcome::Tagged(key, tag)
+            }
+            ActionPayload::Toggle(key) => {
+                let status = self.toggle_todo(&key)?;
+                self.recur(&key);
+                ActionOutcome::Toggled(key, status)
+            }
+            ActionPayload::Other(input) => {
+                self.run_debug_command(&input)?;
+                ActionOutcome::DebugRan(input)
+            }
+        };
+
+        Ok(outcome)
+    }
+}
+
+/// Iterator over the text and status of each todo in a [TodoList], in list order.
+pub type Iter<'a> = std::iter::Map<indexmap::map::Iter<'a, String, Todo>, fn((&'a String, &'a Todo)) -> (&'a String, &'a bool)>;
+
+/// Owning iterator over the text and status of each todo in a [TodoList], in list order.
+pub type IntoIter = std::iter::Map<indexmap::map::IntoIter<String, Todo>, fn((String, Todo)) -> (String, bool)>;
+
+impl TodoList {
+    /// Returns an iterator over the text and status of each todo, in list order.
+    pub fn iter(&self) -> Iter<'_> {
+        self.map.iter().map(status_of as fn(_) -> _)
+    }
+}
+
+fn status_of<'a>((text, todo): (&'a String, &'a Todo)) -> (&'a String, &'a bool) {
+    (text, &todo.completed)
+}
+
+fn into_status((text, todo): (String, Todo)) -> (String, bool) {
+    (text, todo.completed)
+}
+
+impl<'a> IntoIterator for &'a TodoList {
+    type Item = (&'a String, &'a bool);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for TodoList {
+    type Item = (String, bool);
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.into_iter().map(into_status as fn(_) -> _)
+    }
+}
+
+/// Builds a list from text and status pairs, in order. Empty text is skipped, and when the
+/// same text appears more than once the later status wins.
+impl FromIterator<(String, bool)> for TodoList {
+    fn from_iter<I: IntoIterator<Item = (String, bool)>>(iter: I) -> Self {
+        let mut list = TodoList::new();
+        for (text, status) in iter.into_iter().filter(|(text, _)| !text.trim().is_empty()) {
+            let _ = list.set_status(text, status);
+        }
+
+        list
+    }
+}
+
+impl From<Vec<(String, bool)>> for TodoList {
+    fn from(todos: Vec<(String, bool)>) -> Self {
+        todos.into_iter().collect()
+    }
+}
+
+/// Validation and dry-run functions.
+impl TodoList {
+    /// Checks that `action` can be applied to this list, returning the error that
+    /// [`TodoList::apply_action`] would fail with if it cannot. Both `apply_action` and
+    /// [`TodoList::apply_action_dry`] go through this so that they agree.
+    pub fn validate_action(&self, action: &ActionPayload) -> Result<(), CommandError> {
+        let not_empty = |todo: &String| {
+            if todo.trim().is_empty() {
+                Err(CommandError::InputInvalid(s("Todo is empty")))
+            } else {
+                Ok(())
+            }
+        };
+        let exists = |todo: &String| {
+            if self.map.contains_key(todo) {
+                Ok(())
+            } else {
+                Err(CommandError::TodoNotFound)
+            }
+        };
+        // Subtasks being completed by the same action do not stop their parent completing.
+        let completable = |todo: &String, completing: &[String]| match self
+            .subtasks_of(todo)
+            .into_iter()
+            .filter(|subtask| !self.map[*subtask].completed && !completing.contains(subtask))
+            .count()
+        {
+            0 => Ok(()),
+            count => Err(CommandError::InputInvalid(format!(
+                "{:?} has {} incomplete subtasks, complete them first",
+                todo, count
+            ))),
+        };
+
+        match action {
+            ActionPayload::Add(key) => {
+                self.new_todo_text(key)?;
+            }
+            ActionPayload::AddMany(keys) => {
+                if keys.iter().all(|key| key.trim().is_empty()) {
+                    return Err(CommandError::InputInvalid(s("Todo is empty")));
+                }
+            }
+            ActionPayload::AddSub(parent, key) => {
+                exists(parent)?;
+                self.new_todo_text(key)?;
+            }
+            ActionPayload::Color(key, color) => {
+                exists(key)?;
+                validate_color(color)?;
+            }
+            ActionPayload::Combine(keys) => {
+                for key in keys {
+                    exists(key)?;
+                }
+            }
+            ActionPayload::Complete(keys) => {
+                for key in keys {
+                    exists(key)?;
+                    completable(key, keys)?;
+                }
+            }
+            ActionPayload::Grep(pattern) | ActionPayload::Replace(pattern, _) => {
+                compile_pattern(pattern)?;
+            }
+            ActionPayload::Bump(key, _)
+            | ActionPayload::Due(key, _)
+            | ActionPayload::Note(key, _)
+            | ActionPayload::SetPriority(key, _)
+            | ActionPayload::SetRecurrence(key, _)
+            | ActionPayload::Show(key) => exists(key)?,
+            ActionPayload::Split(key, parts) => {
+                exists(key)?;
+                for part in parts {
+                    TodoList::clean_text(part)?;
+                }
+            }
+            ActionPayload::ApplyPatch(file) | ActionPayload::Import(file) | ActionPayload::Replay(file) => {
+                if !Path::new(file).exists() {
+                    return Err(CommandError::InputInvalid(format!("File {:?} not found", file)));
+                }
+            }
+            ActionPayload::Edit(key, new_text) => {
+                exists(key)?;
+                let new_text = TodoList::clean_text(new_text)?;
+                if key != new_text && matches!(self.duplicate_of(new_text), Some(other) if other != key) {
+                    return Err(CommandError::TodoAlreadyExists);
+                }
+            }
+            ActionPayload::Move(key, _) => exists(key)?,
+            ActionPayload::Remove(key) => {
+                not_empty(key)?;
+                exists(key)?;
+            }
+            ActionPayload::Set(key, status) => {
+                if !self.map.contains_key(key) {
+                    self.new_todo_text(key)?;
+                }
+                if *status {
+                    completable(key, &[])?;
+                }
+            }
+            ActionPayload::Tag(key, _) => exists(key)?,
+            ActionPayload::Toggle(key) => {
+                exists(key)?;
+                if self.get_status(key) == Some(false) {
+                    completable(key, &[])?;
+                }
+            }
+            ActionPayload::CopyProfile(_, _)
+            | ActionPayload::DeleteProfile(_)
+            | ActionPayload::DiffProfiles(_, _)
+            | ActionPayload::Recover
+            | ActionPayload::RenameProfile(_, _) => {
+                return Err(CommandError::InputInvalid(s(
+                    "Profile commands must be applied with Profile::apply_action",
+                )));
+            }
+            ActionPayload::Serve => {
+                return Err(CommandError::InputInvalid(s("Serve can only be run from the command line")));
+            }
+            ActionPayload::Triage => {
+                return Err(CommandError::InputInvalid(s("Triage can only be run interactively")));
+            }
+            ActionPayload::Age
+            | ActionPayload::Clear
+            | ActionPayload::ClearTags(_)
+            | ActionPayload::CompleteData(_)
+            | ActionPayload::Completions(_)
+            | ActionPayload::Digest
+            | ActionPayload::Dump
+            | ActionPayload::Export(_)
+            | ActionPayload::IsEmpty(_)
+            | ActionPayload::List
+            | ActionPayload::ListSince(_)
+            | ActionPayload::ListWithStatus(_)
+            | ActionPayload::ListByDue
+            | ActionPayload::ListByPriority(_)
+            | ActionPayload::PurgeCompleted
+            | ActionPayload::Stale(_)
+            | ActionPayload::Other(_) => {}
+        }
+
+        Ok(())
+    }
+
+    /// Describes what applying `action` to this list would do, without changing anything.
+    /// Fails with the same error [`TodoList::apply_action`] would.
+    pub fn apply_action_dry(&self, action: &ActionPayload) -> Result<String, CommandError> {
+        self.validate_action(action)?;
+
+        let description = match action {
+            ActionPayload::Add(key) => format!("Would add {:?}.", key),
+            ActionPayload::AddSub(parent, key) => format!("Would add {:?} under {:?}.", key, parent),
+            ActionPayload::AddMany(keys) => {
+                let summary = self.clone().add_todos(keys);
+                format!(
+                    "Would add {} todos, skipping {} duplicates and {} blank items.",
+                    summary.added.len(),
+                    summary.skipped.len(),
+                    summary.blank
+                )
+            }
+            ActionPayload::Age => format!(
+                "Would show how long {} incomplete todos have been open.",
+                self.get_todos_with_status(false).len()
+            ),
+            ActionPayload::ApplyPatch(file) => {
+                let patch = TodoPatch::load(file)?;
+                self.patched(&patch)?;
+                format!("Would apply {} changes from {:?}.", patch.len(), file)
+            }
+            ActionPayload::Bump(key, up) => format!(
+                "Would move the priority of {:?} {} to {}.",
+                key,
+                if *up { "up" } else { "down" },
+                self.clone().bump_priority(key, *up).map_or_else(String::new, |priority| priority.to_string())
+            ),
+            ActionPayload::Clear => format!(
+                "Would delete all {} {}.",
+                self.len(),
+                if self.len() == 1 { "todo" } else { "todos" }
+            ),
+            ActionPayload::ClearTags(Some(tag)) => format!(
+                "Would remove tag {:?} from {} todos.",
+                tag,
+                self.map.values().filter(|item| item.tags.iter().any(|t| t == tag)).count()
+            ),
+            ActionPayload::ClearTags(None) => format!(
+                "Would remove all tags from {} todos.",
+                self.map.values().filter(|item| !item.tags.is_empty()).count()
+            ),
+            ActionPayload::Color(key, color) => format!("Would color {:?} {}.", key, color),
+            ActionPayload::Combine(keys) => format!(
+                "Would combine {} todos into {:?}.",
+                keys.len(),
+                self.clone().combine_todos(keys)?
+            ),
+            ActionPayload::Complete(keys) => format!(
+                "Would complete {} todos.",
+                self.clone().complete_todos(keys)
+            ),
+            ActionPayload::Digest => s("Would print this week's digest."),
+            ActionPayload::Dump => format!("Would print all {} todos as JSON.", self.len()),
+            ActionPayload::Due(key, Some(due)) => format!("Would set {:?} as due on {}.", key, due),
+            ActionPayload::Due(key, None) => format!("Would clear the due date of {:?}.", key),
+            ActionPayload::Edit(existing, new_text) => {
+                format!("Would change {:?} to {:?}.", existing, new_text)
+            }
+            ActionPayload::Export(file) => {
+                format!("Would export {} todos to {:?}.", self.len(), file)
+            }
+            ActionPayload::Grep(pattern) => format!(
+                "Would list the {} todos matching {:?}.",
+                self.grep(&compile_pattern(pattern)?).len(),
+                pattern
+            ),
+            ActionPayload::IsEmpty(status) => format!(
+                "Would answer that the list is {}empty.",
+                if self.is_empty_with(*status) { "" } else { "not " }
+            ),
+            ActionPayload::Import(file) => {
+                let summary = self.clone().import_file(file)?;
+                format!(
+                    "Would import {} todos from {:?}, skipping {} duplicates.",
+                    summary.imported, file, summary.skipped
+                )
+            }
+            ActionPayload::List => format!("Would list all {} todos.", self.len()),
+            ActionPayload::ListSince(since) => format!(
+                "Would list the {} todos changed since {}.",
+                self.changed_since(*since).len(),
+                self.format_date(&since.date().naive_utc())
+            ),
+            ActionPayload::ListWithStatus(status) => format!(
+                "Would list {} {} todos.",
+                self.get_todos_with_status(*status).len(),
+                if *status { "completed" } else { "incomplete" }
+            ),
+            ActionPayload::ListByDue => format!("Would list all {} todos by due date.", self.len()),
+            ActionPayload::CompleteData(prefix) => format!(
+                "Would print the {} todos for completion.",
+                self.todos_starting_with(prefix.as_deref()).len()
+            ),
+            ActionPayload::Completions(shell) => format!("Would print the {} completion script.", shell),
+            ActionPayload::ListByPriority(priority) => format!(
+                "Would list {} {} priority todos.",
+                self.todos_with_priority(*priority).len(),
+                priority.label().to_lowercase()
+            ),
+            ActionPayload::Move(key, position) => {
+                format!("Would move {:?} to position {}.", key, position + 1)
+            }
+            ActionPayload::Note(key, Some(_)) => format!("Would save the note on {:?}.", key),
+            ActionPayload::Note(key, None) => format!("Would remove the note from {:?}.", key),
+            ActionPayload::Show(key) => format!("Would show {:?}.", key),
+            ActionPayload::Split(key, parts) => format!(
+                "Would split {:?} into {} todos.",
+                key,
+                self.clone().split_todo(key, parts)?.len()
+            ),
+            ActionPayload::Stale(days) => format!(
+                "Would list {} todos open for more than {} days.",
+                self.stale_todos(Utc::now(), *days).len(),
+                days
+            ),
+            ActionPayload::PurgeCompleted => format!(
+                "Would delete {} completed todos.",
+                self.get_todos_with_status(true).len()
+            ),
+            ActionPayload::Remove(key) => format!("Would remove {:?}.", key),
+            ActionPayload::Replay(log) => format!("Would replay {:?} and compare it with the list.", log),
+            ActionPayload::Replace(pattern, rep) => {
+                let report = self.replace(&compile_pattern(pattern)?, rep);
+                format!(
+                    "Would rename {} todos, skipping {} conflicts.",
+                    report.renamed.len(),
+                    report.conflicts.len()
+                )
+            }
+            ActionPayload::Set(key, status) => format!(
+                "Would {} {:?} as {}.",
+                if self.map.contains_key(key) { "mark" } else { "add" },
+                key,
+                if *status { "complete" } else { "incomplete" }
+            ),
+            ActionPayload::SetPriority(key, Some(priority)) => {
+                format!("Would set the priority of {:?} to {}.", key, priority)
+            }
+            ActionPayload::SetPriority(key, None) => format!("Would clear the priority of {:?}.", key),
+            ActionPayload::SetRecurrence(key, Some(recurrence)) => {
+                format!("Would make {:?} repeat {}.", key, recurrence)
+            }
+            ActionPayload::SetRecurrence(key, None) => format!("Would stop {:?} repeating.", key),
+            ActionPayload::Tag(key, tag) => format!("Would tag {:?} with {:?}.", key, tag),
+            ActionPayload::Toggle(key) => format!(
+                "Would mark {:?} as {}.",
+                key,
+                if self.get_status(key) == Some(true) { "incomplete" } else { "complete" }
+            ),
+            ActionPayload::Other(input) => format!("Would run debug command {:?}.", input),
+            ActionPayload::CopyProfile(_, _)
+            | ActionPayload::DeleteProfile(_)
+            | ActionPayload::DiffProfiles(_, _)
+            | ActionPayload::Recover
+            | ActionPayload::RenameProfile(_, _) => unreachable!("Profile commands are rejected by validate_action"),
+            ActionPayload::Serve => unreachable!("Serve is rejected by validate_action"),
+            ActionPayload::Triage => unreachable!("Triage is rejected by validate_action"),
+        };
+
+        Ok(description)
+    }
+}
+
+/// Rendering functions.
+impl TodoList {
+    /// Renders the output of a listing [ActionPayload] (`Age`, `CompleteData`,
+    /// `Completions`, `Digest`, `Dump`, `Grep`, `List`, `ListSince`, `ListWithStatus`,
+    /// `ListByDue`, `ListByPriority`, `Show` or `Stale`) to a [String], returning `None`
+    /// for any other action.
+    /// Todos with a color are rendered in it if `colorize` is `true`.
+    pub fn render_listing(&self, action: &ActionPayload, colorize: bool) -> Option<String> {
+        let opts = RenderOptions {
+            no_color: !colorize,
+            status: None,
+            table: self.table_view,
+        };
+        match action {
+            ActionPayload::Age => Some(self.render_ages(Utc::now(), colorize)),
+            ActionPayload::CompleteData(prefix) => Some(self.render_completions(prefix.as_deref())),
+            ActionPayload::Completions(shell) => Some(Completions::script(*shell)),
+            ActionPayload::Digest => Some(self.weekly_digest(Utc::today().naive_utc()).to_string()),
+            ActionPayload::Dump => self.to_json().ok().map(|json| json + "\n"),
+            ActionPayload::List => Some(self.render_list(opts)),
+            ActionPayload::ListSince(since) => Some(self.render_list_since(*since, colorize)),
+            ActionPayload::ListWithStatus(status) => Some(self.render_list(opts.with_status(*status))),
+            ActionPayload::Grep(pattern) => Regex::new(pattern).ok().map(|re| self.render_grep(&re, colorize)),
+            ActionPayload::ListByDue => Some(self.render_list_by_due(colorize)),
+            ActionPayload::ListByPriority(priority) => Some(self.render_list_by_priority(*priority, colorize)),
+            ActionPayload::Show(todo) => self.render_todo(todo, colorize),
+            ActionPayload::Stale(days) => Some(self.render_stale(Utc::now(), *days, colorize)),
+            _ => None,
+        }
+    }
+
+    /// Renders the listing for `action` and writes it to the file at `file` rather than
+    /// printing it. Fails if `action` is not a listing action.
+    pub fn save_listing<TFilePath: AsRef<Path>>(&self, action: &ActionPayload, file: TFilePath) -> Result<(), CommandError> {
+        match self.render_listing(action, false) {
+            Some(listing) => Ok(FileSystem::save_bytes(file, listing)?),
+            None => Err(CommandError::InputInvalid(format!(
+                "output of the {:?} command cannot be written to a file",
+                action.input_cmd_string()
+            ))),
+        }
+    }
+
+    /// Serializes the whole list to a JSON string with [`Cereal::serialize_json`].
+    pub fn to_json(&self) -> Result<String, CommandError> {
+        let bytes = Cereal::serialize_json(self).map_err(|err| CommandError::IoError(err.to_string()))?;
+        String::from_utf8(bytes).map_err(|err| CommandError::IoError(err.to_string()))
+    }
+
+    /// Renders the quoted text of `todo`, in the color of `item` if `colorize` is `true`.
+    fn render_quoted(todo: &str, item: &Todo, colorize: bool) -> String {
+        match item.style() {
+            Some(style) if colorize => format!("{:?}", todo).color(style).to_string(),
+            _ => format!("{:?}", todo),
+        }
+    }
+
+    /// Renders `todo` for a listing, cut down to the width set by
+    /// [`TodoList::set_max_width`]. Todos with a note are marked with a trailing `*`, the
+    /// note itself is only shown by [`TodoList::render_todo`] so that multi-line notes
+    /// don't break up listings.
+    fn render_text(&self, todo: &str, item: &Todo, colorize: bool) -> String {
+        let text = TodoList::render_quoted(&truncate_to_width(todo, self.max_width), item, colorize);
+        if item.description.is_some() {
+            text + "*"
+        } else {
+            text
+        }
+    }
+
+    /// Renders everything known about the todo `todo`, including its note, or `None` if it
+    /// does not exist.
+    pub fn render_todo(&self, todo: &str, colorize: bool) -> Option<String> {
+        let item = self.map.get(todo)?;
+        let date = |stamp: &DateTime<Utc>| self.format_date(&stamp.date().naive_utc());
+        let mut output = format!(
+            "{}\nStatus: {}\n",
+            TodoList::render_quoted(todo, item, colorize),
+            if item.completed { "complete" } else { "incomplete" }
+        );
+        if let Some(due) = item.due {
+            output.push_str(&format!("Due: {}\n", self.format_date(&due)));
+        }
+        if let Some(recurrence) = item.recurrence {
+            output.push_str(&format!("Repeats: {}\n", recurrence));
+        }
+        if let Some(parent) = &item.parent {
+            output.push_str(&format!("Subtask of: {:?}\n", parent));
+        }
+        let subtasks = self.subtasks_of(todo).len();
+        if subtasks > 0 {
+            output.push_str(&format!(
+                "Subtasks: {} of {} complete\n",
+                subtasks - self.incomplete_subtasks(todo),
+                subtasks
+            ));
+        }
+        if !item.tags.is_empty() {
+            output.push_str(&format!("Tags: {}\n", item.tags.join(", ")));
+        }
+        if let Some(color) = &item.color {
+            output.push_str(&format!("Color: {}\n", color));
+        }
+        if let Some(created_at) = &item.created_at {
+            output.push_str(&format!("Created: {}\n", date(created_at)));
+        }
+        if let Some(completed_at) = &item.completed_at {
+            output.push_str(&format!("Completed: {}\n", date(completed_at)));
+        }
+        if let Some(description) = &item.description {
+            output.push_str(&format!("\n{}\n", description));
+        }
+
+        Some(output)
+    }
+
+    /// Renders the todos as aligned rows of a status marker, the todo and its due date,
+    /// priority and tags if it has any, with subtasks indented under their parent. If
+    /// [`RenderOptions::status`] is set only the todos with that status are rendered, without
+    /// the nesting.
+    pub fn render_list(&self, opts: RenderOptions) -> String {
+        let colorize = opts.colorize();
+        let (heading, rows) = match opts.status {
+            None if self.is_empty() => {
+                return s("No todos in database, you're either very on top of things or slacking reallllllly bad.\n")
+            }
+            // TODO This might have performance implications for very large data-sets, keep an eye out.
+            Some(status) if !self.any_with_status(status) => {
+                return format!(
+                    "There are no {} todos in the database.\n",
+                    if status { "completed" } else { "incomplete" }
+                )
+            }
+            None => ("\nAll Todos\n--- -----\n", self.nested_rows()),
+            Some(status) => (
+                if status {
+                    "\nCompleted Todos\n--------- -----\n"
+                } else {
+                    "\nIncomplete Todos\n---------- -----\n"
+                },
+                self.map
+                    .iter()
+                    .filter(|(_, item)| item.completed == status)
+                    .map(|(todo, _)| (0, todo))
+                    .collect(),
+            ),
+        };
+
+        if opts.table {
+            return format!("{}{}\n", heading, self.render_table_rows(&rows));
+        }
+
+        // Each row is its rendering, how wide it is without color, and its metadata.
+        let rows: Vec<(String, usize, String)> = rows
+            .into_iter()
+            .map(|(depth, todo)| {
+                let item = &self.map[todo];
+                let indent = "    ".repeat(depth);
+                let symbol = self.status_symbol(item.completed);
+                let marker = match (item.completed, colorize) {
+                    (true, true) => symbol.green().to_string(),
+                    _ => s(symbol),
+                };
+                let text = self.render_text(todo, item, colorize);
+                let width = indent.len()
+                    + symbol.chars().count()
+                    + 1
+                    + self.render_text(todo, item, false).chars().count();
+                (format!("{}{} {}", indent, marker, text), width, self.render_metadata(item, colorize))
+            })
+            .collect();
+        let width = rows
+            .iter()
+            .filter(|(_, _, metadata)| !metadata.is_empty())
+            .map(|(_, width, _)| *width)
+            .max()
+            .unwrap_or_default();
+
+        let mut output = s(heading);
+        for (rendered, row_width, metadata) in rows {
+            if metadata.is_empty() {
+                output.push_str(&format!("{}\n", rendered));
+            } else {
+                let padding = " ".repeat(width - row_width);
+                output.push_str(&format!("{}{}  {}\n", rendered, padding, metadata));
+            }
+        }
+        output.push('\n');
+
+        output
+    }
+
+    /// Renders `rows` as a table with a column each for the status, the todo, its tags and
+    /// its due date. Tables are never colored so that their columns stay aligned.
+    fn render_table_rows(&self, rows: &[(usize, &String)]) -> String {
+        let rows: Vec<Vec<String>> = rows
+            .iter()
+            .map(|(depth, todo)| {
+                let item = &self.map[*todo];
+                vec![
+                    s(self.status_symbol(item.completed)),
+                    format!("{}{}", "  ".repeat(*depth), truncate_to_width(todo, self.max_width)),
+                    item.tags.join(", "),
+                    item.due.map(|due| self.format_date(&due)).unwrap_or_default(),
+                ]
+            })
+            .collect();
+
+        render_table(&["Status", "Todo", "Tags", "Due"], &rows)
+    }
+
+    /// Returns every todo with the depth it is nested at, each followed by its subtasks.
+    fn nested_rows(&self) -> Vec<(usize, &String)> {
+        let mut rows = Vec::new();
+        let mut rendered: HashSet<&String> = HashSet::new();
+        let roots = self
+            .map
+            .iter()
+            .filter(|(_, item)| item.parent.as_ref().map_or(true, |parent| !self.map.contains_key(parent)));
+        for (todo, _) in roots {
+            self.collect_subtree(todo, 0, &mut rendered, &mut rows);
+        }
+        // Todos whose parents lead round in a circle are never reached from a top level todo,
+        // show them at the top level rather than not at all.
+        for todo in self.map.keys() {
+            self.collect_subtree(todo, 0, &mut rendered, &mut rows);
+        }
+
+        rows
+    }
+
+    /// Adds `todo` at `depth` to `rows`, followed by its subtasks one level deeper. Todos
+    /// already in `rendered` are skipped.
+    fn collect_subtree<'a>(
+        &'a self,
+        todo: &'a String,
+        depth: usize,
+        rendered: &mut HashSet<&'a String>,
+        rows: &mut Vec<(usize, &'a String)>,
+    ) {
+        if !rendered.insert(todo) {
+            return;
+        }
+
+        rows.push((depth, todo));
+        for subtask in self.subtasks_of(todo) {
+            self.collect_subtree(subtask, depth + 1, rendered, rows);
+        }
+    }
+
+    /// Renders the due date, priority and tags of `item` for a listing, or an empty string
+    /// if it has none of them.
+    fn render_metadata(&self, item: &Todo, colorize: bool) -> String {
+        let mut parts = Vec::new();
+        if let Some(due) = &item.due {
+            let due = format!("due {}", self.format_date(due));
+            parts.push(if colorize { due.yellow().to_string() } else { due });
+        }
+        if let Some(priority) = item.priority {
+            parts.push(priority_label(priority, colorize));
+        }
+        if !item.tags.is_empty() {
+            let tags = item.tags.iter().map(|tag| format!("#{}", tag)).join(" ");
+            parts.push(if colorize { tags.cyan().to_string() } else { tags });
+        }
+
+        parts.join("  ")
+    }
+
+    fn render_list_since(&self, since: DateTime<Utc>, colorize: bool) -> String {
+        let todos = self.changed_since(since);
+        if todos.is_empty() {
+            return format!("Nothing has changed since {}.\n", self.format_date(&since.date().naive_utc()));
+        }
+
+        let mut output = format!(
+            "Changed Since {} ({})\n",
+            self.format_date(&since.date().naive_utc()),
+            todos.len()
+        );
+        for todo in todos {
+            let item = &self.map[todo];
+            output.push_str(&format!(
+                "\t{} {}\n",
+                self.status_symbol(item.completed),
+                self.render_text(todo, item, colorize)
+            ));
+        }
+
+        output
+    }
+
+    fn render_ages(&self, now: DateTime<Utc>, colorize: bool) -> String {
+        let ages = self.open_ages(now);
+        if ages.is_empty() {
+            return s("There are no incomplete todos in the database.\n");
+        }
+
+        let mut output = s("Open Todos\n---- -----\n");
+        for (todo, age) in ages {
+            let age = match age {
+                Some(age) if age.num_days() == 1 => s("1 day"),
+                Some(age) => format!("{} days", age.num_days()),
+                None => s("unknown"),
+            };
+            output.push_str(&format!(
+                "\t* {} open for {}\n",
+                self.render_text(todo, &self.map[todo], colorize),
+                age
+            ));
+        }
+
+        output
+    }
+
+    fn render_stale(&self, now: DateTime<Utc>, days: usize, colorize: bool) -> String {
+        let stale = self.stale_todos(now, days);
+        if stale.is_empty() {
+            return format!(
+                "Nothing has been open for more than {} days, nice work keeping on top of things!\n",
+                days
+            );
+        }
+
+        let mut output = format!("Stale Todos ({})\n----- -----\n", stale.len());
+        for (todo, age) in stale {
+            let age_text = format!("{:>4}d", age);
+            let age_text = if colorize {
+                age_text.color(stale_style(age, days)).to_string()
+            } else {
+                age_text
+            };
+            output.push_str(&format!(
+                "\t{} {}\n",
+                age_text,
+                self.render_text(todo, &self.map[todo], colorize)
+            ));
+        }
+
+        output
+    }
+
+    /// Renders one todo text per line with no decoration, so that shell completion
+    /// scripts can read it.
+    fn render_completions(&self, prefix: Option<&str>) -> String {
+        self.todos_starting_with(prefix)
+            .into_iter()
+            .map(|todo| format!("{}\n", todo))
+            .collect()
+    }
+
+    fn render_grep(&self, re: &Regex, colorize: bool) -> String {
+        let todos = self.grep(re);
+        if todos.is_empty() {
+            return format!("There are no todos matching {:?}.\n", re.as_str());
+        }
+
+        let mut output = format!("Todos matching {:?} ({})\n", re.as_str(), todos.len());
+        for todo in todos {
+            let item = &self.map[todo];
+            output.push_str(&format!(
+                "\t{} {}\n",
+                self.status_symbol(item.completed),
+                self.render_text(todo, item, colorize)
+            ));
+        }
+
+        output
+    }
+
+    fn render_list_by_due(&self, colorize: bool) -> String {
+        if self.is_empty() {
+            return "There are no todos in the database.\n".to_string();
+        }
+
+        let mut output = format!("Todos by Due Date ({})\n", self.len());
+        for (todo, due) in self.todos_by_due() {
+            let item = &self.map[todo];
+            output.push_str(&format!(
+                "\t{} {:<10} {}\n",
+                self.status_symbol(item.completed),
+                due.map_or_else(|| "-".to_string(), |due| self.format_date(&due)),
+                self.render_text(todo, item, colorize)
+            ));
+        }
+
+        output
+    }
+
+    fn render_list_by_priority(&self, priority: Priority, colorize: bool) -> String {
+        let todos = self.todos_with_priority(priority);
+        if todos.is_empty() {
+            return format!(
+                "There are no {} priority todos in the database.\n",
+                priority.label().to_lowercase()
+            );
+        }
+
+        let mut output = format!(
+            "{} Priority Todos ({})\n",
+            priority_label(priority, colorize),
+            todos.len()
+        );
+        for todo in todos {
+            let item = &self.map[todo];
+            output.push_str(&format!(
+                "\t{} {}\n",
+                self.status_symbol(item.completed),
+                self.render_text(todo, item, colorize)
+            ));
+        }
+
+        output
+    }
+}
+
+/// Import / export functions.
+impl TodoList {
+    /// Parses a plain-text or Markdown document into a [TodoList]. Lines of the form
+    /// `- [x] text` are imported as complete and `- [ ] text` as incomplete. Headings and
+    /// blank lines are ignored, and any other line is imported as an incomplete todo.
+    pub fn from_markdown(contents: &str) -> TodoList {
+        let mut list = TodoList::new();
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let item = line
+                .strip_prefix("- ")
+                .or_else(|| line.strip_prefix("* "))
+                .unwrap_or(line)
+                .trim_start();
+            let (text, status) = if let Some(rest) = item
+                .strip_prefix("[x]")
+                .or_else(|| item.strip_prefix("[X]"))
+            {
+                (rest.trim(), true)
+            } else if let Some(rest) = item.strip_prefix("[ ]") {
+                (rest.trim(), false)
+            } else {
+                (item, false)
+            };
+
+            // Duplicates within the document are skipped, the first occurrence wins.
+            let _ = list.add_todo(text, status);
+        }
+
+        list
+    }
+
+    /// Merges the todos from `other` into this list. Their text is trimmed, and todos that
+    /// are blank or already exist are skipped rather than overwritten, the same as
+    /// [`TodoList::add_todo`].
+    pub fn import(&mut self, other: TodoList) -> ImportSummary {
+        let mut summary = ImportSummary::default();
+        for (todo, item) in other.map {
+            match TodoList::clean_text(&todo) {
+                Ok(text) if self.duplicate_of(text).is_none() => {
+                    self.map.insert(text.to_string(), item);
+                    summary.imported += 1;
+                }
+                _ => summary.skipped += 1,
+            }
+        }
+
+        summary
+    }
+
+    /// Merges `other` into this list. Todos only in `other` are added as they are, unless
+    /// they duplicate a todo in this list ignoring case, see
+    /// [`TodoList::set_case_insensitive_duplicates`]. Todos only in this list are kept, and
+    /// todos in both with a different status are settled by `strategy`.
+    pub fn merge(&mut self, other: &TodoList, strategy: MergeStrategy) -> MergeReport {
+        let mut report = MergeReport::default();
+        let changes = match self.diff_with(other) {
+            DiffResult::Same => return report,
+            DiffResult::Changes(changes) => changes,
+        };
+
+        for change in changes {
+            match change {
+                DiffEntry::TodoNotFound { todo, that_has: true, .. } => {
+                    if self.duplicate_of(&todo).is_some() {
+                        continue;
+                    }
+                    if let Some(item) = other.map.get(&todo) {
+                        self.map.insert(todo, item.clone());
+                        report.added += 1;
+                    }
+                }
+                DiffEntry::TodoNotFound { .. } => {}
+                DiffEntry::TodoStatusMistake {
+                    todo,
+                    this_status,
+                    that_status,
+                } => {
+                    let status = strategy.resolve(this_status, that_status);
+                    if status != this_status {
+                        let _ = self.set_status(&todo, status);
+                    }
+                    report.conflicts += 1;
+                }
+            }
+        }
+
+        report
+    }
+
+    /// Merges `ours` and `theirs`, two lists that both started out as `base`. A change made
+    /// on only one side is applied, a change made the same way on both sides is applied
+    /// once, and a todo that each side changed differently is reported as a
+    /// [MergeConflict].
+    pub fn merge_3way(base: &TodoList, ours: &TodoList, theirs: &TodoList) -> ThreeWayMerge {
+        let mut merged = ours.clone();
+        let mut conflicts = Vec::new();
+        let changes = match ours.diff_with(theirs) {
+            DiffResult::Same => Vec::new(),
+            DiffResult::Changes(changes) => changes,
+        };
+
+        for change in changes {
+            let todo = match change {
+                DiffEntry::TodoNotFound { todo, .. } | DiffEntry::TodoStatusMistake { todo, .. } => todo,
+            };
+            let (base_status, our_status, their_status) =
+                (base.get_status(&todo), ours.get_status(&todo), theirs.get_status(&todo));
+
+            if our_status == base_status {
+                match theirs.map.get(&todo) {
+                    Some(item) if our_status.is_some() => {
+                        let _ = merged.set_status(&todo, item.completed);
+                    }
+                    Some(item) => {
+                        merged.map.insert(todo, item.clone());
+                    }
+                    None => {
+                        merged.map.shift_remove(&todo);
+                    }
+                }
+            } else if their_status != base_status {
+                conflicts.push(MergeConflict {
+                    todo,
+                    base: base_status,
+                    ours: our_status,
+                    theirs: their_status,
+                });
+            }
+        }
+
+        ThreeWayMerge { merged, conflicts }
+    }
+
+    /// Renders this list as a Markdown checklist which can be read back with
+    /// [`TodoList::from_markdown`].
+    pub fn to_markdown(&self) -> String {
+        self.map_todos(|(todo, status)| {
+            format!("- [{}] {}\n", if *status { "x" } else { " " }, todo)
+        })
+        .join("")
+    }
+
+    /// Renders this list as CSV with a `text,completed` header row.
+    pub fn to_csv(&self) -> Result<String, String> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer
+            .write_record(["text", "completed"])
+            .map_err(|err| err.to_string())?;
+        for (todo, item) in self.map.iter() {
+            writer
+                .write_record([todo.as_str(), if item.completed { "true" } else { "false" }])
+                .map_err(|err| err.to_string())?;
+        }
+
+        let bytes = writer.into_inner().map_err(|err| err.to_string())?;
+        String::from_utf8(bytes).map_err(|err| err.to_string())
+    }
+
+    /// Parses CSV with a header row into a [TodoList]. The `text` and `completed` columns
+    /// are required, any other columns are ignored. A malformed row produces an
+    /// [InputError] naming the line it was found on.
+    pub fn from_csv(contents: &str) -> Result<TodoList, InputError> {
+        let csv_error = |line: u64, msg: String| {
+            InputError::bad_arg_with(format!("Malformed CSV on line {}: {}", line, msg))
+        };
+
+        let mut reader = csv::Reader::from_reader(contents.as_bytes());
+        let headers = reader
+            .headers()
+            .map_err(|err| csv_error(1, err.to_string()))?
+            .clone();
+        let column = |name: &str| {
+            headers
+                .iter()
+                .position(|h| h.trim().eq_ignore_ascii_case(name))
+                .ok_or_else(|| csv_error(1, format!("missing {:?} column", name)))
+        };
+        let text_col = column("text")?;
+        let status_col = column("completed")?;
+
+        let mut list = TodoList::new();
+        for result in reader.records() {
+            let record = result.map_err(|err| {
+                csv_error(
+                    err.position().map_or(0, |pos| pos.line()),
+                    err.to_string(),
+                )
+            })?;
+            let line = record.position().map_or(0, |pos| pos.line());
+            let text = record.get(text_col).unwrap_or_default();
+            let status_raw = record.get(status_col).unwrap_or_default();
+            let status = string_to_bool(status_raw).ok_or_else(|| {
+                csv_error(
+                    line,
+                    format!("unable to parse {:?} to valid boolean value", status_raw),
+                )
+            })?;
+
+            if text.is_empty() {
+                return Err(csv_error(line, "todo text is empty".to_string()));
+            }
+
+            let _ = list.add_todo(text, status);
+        }
+
+        Ok(list)
+    }
+
+    /// Renders this list in the porcelain format, one `{status}\t{text}` line per todo where
+    /// the status is `true` or `false`. Tabs, newlines and backslashes in the text are
+    /// escaped with [escape_field] so that every todo stays on its own line.
+    pub fn to_porcelain(&self) -> String {
+        self.map
+            .iter()
+            .map(|(todo, item)| format!("{}\t{}\n", item.completed, escape_field(todo)))
+            .join("")
+    }
+
+    /// Parses the porcelain format written by [`TodoList::to_porcelain`], unescaping the
+    /// text of each todo. Blank lines are skipped and a malformed line produces an
+    /// [InputError] naming the line it was found on.
+    pub fn from_porcelain(contents: &str) -> Result<TodoList, InputError> {
+        let mut list = TodoList::new();
+        for (index, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let malformed = |msg: &str| {
+                InputError::bad_arg_with(format!("Malformed porcelain on line {}: {}", index + 1, msg))
+            };
+
+            let (status_raw, text) = line.split_once('\t').ok_or_else(|| malformed("missing tab"))?;
+            let status = string_to_bool(status_raw).ok_or_else(|| malformed("unable to parse the status"))?;
+            let text = unescape_field(text);
+            if text.is_empty() {
+                return Err(malformed("todo text is empty"));
+            }
+
+            let _ = list.add_todo(text, status);
+        }
+
+        Ok(list)
+    }
+
+    /// Writes this list as JSON Lines, one JSON object per todo in list order, so that the
+    /// whole document never has to be held in memory.
+    pub fn write_jsonl<W: Write>(&self, mut writer: W) -> Result<(), String> {
+        for (text, todo) in self.map.iter() {
+            serde_json::to_writer(&mut writer, &JsonLine { text: text.clone(), todo: todo.clone() })
+                .map_err(|err| err.to_string())?;
+            writer.write_all(b"\n").map_err(|err| err.to_string())?;
+        }
+
+        writer.flush().map_err(|err| err.to_string())
+    }
+
+    /// Reads JSON Lines written by [`TodoList::write_jsonl`] one line at a time. Blank lines
+    /// are skipped, and a malformed line produces an [InputError] naming the line.
+    pub fn read_jsonl<R: BufRead>(reader: R) -> Result<TodoList, InputError> {
+        let mut list = TodoList::new();
+        for (index, line) in reader.lines().enumerate() {
+            let jsonl_error = |msg: String| {
+                InputError::bad_arg_with(format!("Malformed JSON Lines on line {}: {}", index + 1, msg))
+            };
+
+            let line = line.map_err(|err| jsonl_error(err.to_string()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry: JsonLine = serde_json::from_str(&line).map_err(|err| jsonl_error(err.to_string()))?;
+            if entry.text.is_empty() {
+                return Err(jsonl_error(s("todo text is empty")));
+            }
+
+            list.map.entry(entry.text).or_insert(entry.todo);
+        }
+
+        Ok(list)
+    }
+
+    /// Imports the JSON Lines file at `file` into this list, adding each todo as its line is
+    /// read so that the whole file is never held in memory. Todos already in the list are
+    /// skipped. `progress` is called with the number of lines read every
+    /// [IMPORT_PROGRESS_INTERVAL] lines. A malformed line stops the import with an error
+    /// naming the line, keeping the todos imported before it.
+    pub fn import_jsonl_with<TFilePath: AsRef<Path>, F: FnMut(usize)>(
+        &mut self,
+        file: TFilePath,
+        mut progress: F,
+    ) -> Result<ImportSummary, CommandError> {
+        let mut summary = ImportSummary::default();
+        for (index, line) in FileSystem::read_lines(file)?.enumerate() {
+            let jsonl_error = |msg: String| {
+                CommandError::InputInvalid(format!("Malformed JSON Lines on line {}: {}", index + 1, msg))
+            };
+
+            if (index + 1) % IMPORT_PROGRESS_INTERVAL == 0 {
+                progress(index + 1);
+            }
+
+            let line = line.map_err(|err| jsonl_error(err.to_string()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry: JsonLine = serde_json::from_str(&line).map_err(|err| jsonl_error(err.to_string()))?;
+            if entry.text.is_empty() {
+                return Err(jsonl_error(s("todo text is empty")));
+            }
+
+            if self.map.contains_key(&entry.text) {
+                summary.skipped += 1;
+            } else {
+                self.map.insert(entry.text, entry.todo);
+                summary.imported += 1;
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Parses a JSON object mapping todo text to its status, such as
+    /// `{"buy milk": false, "buy eggs": true}`, as written by some other tools. Returns
+    /// `None` if `bytes` are not such an object, including for our own JSON encoding.
+    pub fn from_json_object(bytes: &[u8]) -> Option<TodoList> {
+        serde_json::from_slice::<IndexMap<String, bool>>(bytes)
+            .ok()
+            .map(|todos| todos.into_iter().collect())
+    }
+
+    /// Reads the file at `file` and imports its todos into this list. Files ending in
+    /// `.csv` are parsed with [`TodoList::from_csv`], files ending in `.tsv` are parsed with
+    /// [`TodoList::from_porcelain`], files ending in `.jsonl` are read
+    /// line by line with [`TodoList::import_jsonl_with`], JSON objects of statuses keyed by text
+    /// are read with [`TodoList::from_json_object`], files ending in the extension of an
+    /// [EncodingType] are deserialized with [Cereal], the same followed by `.gz` are
+    /// decompressed first, and anything else is treated as plain-text or Markdown.
+    pub fn import_file<S: AsRef<str>>(&mut self, file: S) -> Result<ImportSummary, CommandError> {
+        self.import_file_with(file, |_| {})
+    }
+
+    /// Imports the file at `file` like [`TodoList::import_file`], passing `progress` on to
+    /// [`TodoList::import_jsonl_with`] when it is a JSON Lines file.
+    pub fn import_file_with<S: AsRef<str>, F: FnMut(usize)>(
+        &mut self,
+        file: S,
+        progress: F,
+    ) -> Result<ImportSummary, CommandError> {
+        let path = std::path::Path::new(file.as_ref());
+        if !path.exists() {
+            return Err(CommandError::InputInvalid(format!(
+                "File {:?} not found",
+                file.as_ref()
+            )));
+        }
+
+        if file_extension(path).as_deref() == Some("jsonl") {
+            return self.import_jsonl_with(path, progress);
+        }
+
+        let bytes = FileSystem::load_bytes(path)
+            .map_err(|err| CommandError::InputInvalid(err.to_string()))?;
+        if let Some(encoding) = compressed_encoding(path) {
+            let imported = Cereal::deserialize_compressed(encoding, &bytes)
+                .map_err(CommandError::InputInvalid)?;
+            return Ok(self.import(imported));
+        }
+
+        let ext = file_extension(path);
+        if ext.as_deref() == Some(EncodingType::Json.get_file_ext()) {
+            if let Some(imported) = TodoList::from_json_object(&bytes) {
+                return Ok(self.import(imported));
+            }
+        }
+
+        if let Some(encoding) = ext.as_deref().and_then(EncodingType::from_file_ext) {
+            let imported = Cereal::deserialize_with(encoding, &bytes)
+                .map_err(CommandError::InputInvalid)?;
+            return Ok(self.import(imported));
+        }
+
+        let contents = String::from_utf8(bytes)
+            .map_err(|err| CommandError::InputInvalid(err.to_string()))?;
+        let imported = match ext.as_deref() {
+            Some("csv") => TodoList::from_csv(&contents)
+                .map_err(|err| CommandError::InputInvalid(err.to_string()))?,
+            Some("tsv") => TodoList::from_porcelain(&contents)
+                .map_err(|err| CommandError::InputInvalid(err.to_string()))?,
+            _ => TodoList::from_markdown(&contents),
+        };
+
+        Ok(self.import(imported))
+    }
+
+    /// Writes this list to the file at `file`, choosing the format from its extension.
+    /// Files ending in `.csv` are written with [`TodoList::to_csv`], files ending in `.tsv`
+    /// are written with [`TodoList::to_porcelain`], files ending in
+    /// `.jsonl` are streamed with [`TodoList::write_jsonl`], files ending in the extension
+    /// of an [EncodingType] are serialized with [Cereal], the same followed by `.gz` are
+    /// also compressed, and anything else is written as a Markdown checklist.
+    pub fn export_file<S: AsRef<str>>(&self, file: S) -> Result<(), CommandError> {
+        let path = std::path::Path::new(file.as_ref());
+        let ext = file_extension(path);
+        if ext.as_deref() == Some("jsonl") {
+            let writer = BufWriter::new(File::create(path)?);
+            return self.write_jsonl(writer).map_err(CommandError::IoError);
+        }
+
+        let contents = match ext.as_deref() {
+            Some(COMPRESSED_FILE_EXT) if compressed_encoding(path).is_some() => {
+                let encoding = compressed_encoding(path).unwrap();
+                Cereal::serialize_compressed(encoding, self).map_err(CommandError::InputInvalid)?
+            }
+            Some("csv") => self.to_csv().map_err(CommandError::InputInvalid)?.into_bytes(),
+            Some("tsv") => self.to_porcelain().into_bytes(),
+            Some(ext) if EncodingType::from_file_ext(ext).is_some() => {
+                let encoding = EncodingType::from_file_ext(ext).unwrap();
+                Cereal::serialize_with(encoding, self).map_err(CommandError::InputInvalid)?
+            }
+            _ => self.to_markdown().into_bytes(),
+        };
+
+        FileSystem::save_bytes(path, contents)
+            .map_err(|err| CommandError::InputInvalid(err.to_string()))
+    }
+}
+
+/// A single line of a JSON Lines export, a todo along with its text.
+#[derive(Serialize, Deserialize)]
+struct JsonLine {
+    text: String,
+    #[serde(flatten)]
+    todo: Todo,
+}
+
+/// Returns the color a todo that has been open for `age` days is shown in by the stale
+/// report, getting redder the further past the `days` threshold it is.
+fn stale_style(age: i64, days: usize) -> AnsiColors {
+    let threshold = days.max(1) as i64;
+    if age < threshold * 2 {
+        AnsiColors::Yellow
+    } else if age < threshold * 4 {
+        AnsiColors::BrightRed
+    } else {
+        AnsiColors::Red
+    }
+}
+
+/// Compiles the regular expression `pattern`, reporting a bad pattern as invalid input.
+fn compile_pattern(pattern: &str) -> Result<Regex, CommandError> {
+    Regex::new(pattern).map_err(|err| CommandError::InputInvalid(format!("invalid pattern {:?}, {}", pattern, err)))
+}
+
+/// Returns the lower-cased extension of `path`, if it has one.
+fn file_extension(path: &std::path::Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+}
+
+/// Returns the [EncodingType] of a compressed file such as `todos.msgpack.gz`, if `path`
+/// is one.
+fn compressed_encoding(path: &std::path::Path) -> Option<EncodingType> {
+    if file_extension(path).as_deref() != Some(COMPRESSED_FILE_EXT) {
+        return None;
+    }
+
+    path.file_stem()
+        .and_then(|stem| file_extension(Path::new(stem)))
+        .and_then(EncodingType::from_file_ext)
+}
+
+/// Debug command functions.
+impl TodoList {
+    fn run_debug_command<S: AsRef<str>>(&self, input: S) -> Result<(), CommandError> {
+        match input.as_ref().to_lowercase().as_str() {
+            "encoding" => {
+                return self.run_encoding_test();
+            }
+            "diff" => {
+                return self.run_diff_test();
+            }
+            _ => {
+                return Err(CommandError::InputInvalid(format!(
+                    "Unknown debug command {:?}",
+                    input.as_ref()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn run_encoding_test(&self) -> Result<(), CommandError> {
+        struct RunTime {
+            se_duration: Duration,
+            de_duration: Duration,
+        }
+
+        impl RunTime {
+            fn empty() -> Self {
+                Self {
+                    se_duration: Duration::default(),
+                    de_duration: Duration::default(),
+                }
+            }
+
+            fn from_se_time(d: Duration) -> Self {
+                Self {
+                    se_duration: d,
+                    de_duration: Duration::default(),
+                }
+            }
+
+            fn set_se_duration(&mut self, d: Duration) {
+                self.se_duration = d;
+            }
+
+            fn set_de_duration(&mut self, d: Duration) {
+                self.de_duration = d;
+            }
+        }
+
+        println!("Running serialization comparison...\n");
+        let mut byte_map: HashMap<EncodingType, Vec<u8>> = HashMap::new();
+        let mut time_map: HashMap<EncodingType, RunTime> = HashMap::new();
+
+        for ty in EncodingType::all() {
+            println!("Starting {}", ty);
+            let start = Instant::now();
+            match Cereal::serialize_with(ty, self) {
+                Ok(data) => {
+                    let duration = start.elapsed();
+                    time_map.insert(ty, RunTime::from_se_time(duration));
+                    byte_map.insert(ty, data);
+                }
+                Err(err) => {
+                    println!("There was an error with {:?}: {}", ty, err);
+                }
+            }
+
+            println!("{} Serialization Complete.", ty);
+        }
+
+        for (ty, bytes) in byte_map.iter() {
+            let file_name = format!("./data/{}.dat", ty);
+            match FileSystem::save_bytes(&file_name, bytes) {
+                Ok(_) => println!("Wrote {} successfully!", &file_name),
+                Err(e) => println!("Error writing {}: {}", &file_name, e),
+            }
+        }
+
+        for ty in EncodingType::all() {
+            let file_name = format!("./data/{}.dat", ty);
+            let path = std::path::Path::new(&file_name);
+            match path.try_exists() {
+                Ok(exists) => {
+                    if !exists {
+                        println!("Error, path {:?} does not exist!", path);
+                        continue;
+                    }
+
+                    match FileSystem::load_bytes(path) {
+                        Ok(bytes) => {
+                            let start = Instant::now();
+                            match Cereal::deserialize_with::<TodoList>(ty, &bytes) {
+                                Ok(recreated) => {
+                                    let duration = start.elapsed();
+                                    println!(
+                                        "Encoding {} took {}ms to deserialize.",
+                                        ty,
+                                        duration.as_millis()
+                                    );
+                                    time_map.entry(ty).and_modify(|rt| {
+                                        rt.de_duration = duration;
+                                    });
+                                    match self.diff_with(&recreated) {
+                                        DiffResult::Same => {
+                                            println!("Recreated todo-list from {} data and it matches the original!", ty);
+                                        }
+                                        DiffResult::Changes(diffs) => {
+                                            println!("Recreated todo-list {:?} from {} data and it does NOT match the original.", recreated, ty);
+                                            println!(
+                                                "Diff Results:\n\t{}\n",
+                                                diffs
+                                                    .iter()
+                                                    .map(|diff_entry| diff_entry.to_string())
+                                                    .join("\n\t")
+                                            )
+                                        }
+                                    }
+                                }
+                                Err(e) => println!("Error deserializing todolist: {}", e),
+                            }
+                        }
+                        Err(e) => println!("Error loading bytes from file: {}", e),
+                    }
+                }
+                Err(err) => println!("Error with path {:?}: {}", path, err),
+            }
+        }
+
+        let mut compressed_map: HashMap<EncodingType, usize> = HashMap::new();
+        for (ty, bytes) in byte_map.iter() {
+            match Cereal::compress(bytes) {
+                Ok(compressed) => {
+                    compressed_map.insert(*ty, compressed.len());
+                }
+                Err(err) => println!("There was an error compressing {}: {}", ty, err),
+            }
+        }
+
+        let colorize = should_color();
+        let header = |text: &str, width: usize, style: Style| {
+            paint(format!("{:^width$}", text, width = width), style.underline(), colorize)
+        };
+        let cell = |text: String, width: usize, style: Style| {
+            paint(format!("{:^width$}", text, width = width), style, colorize)
+        };
+
+        println!("Serialization Size Results");
+        println!(
+            "{}{}{}",
+            header("Encoding", 12, Style::new().white()),
+            header("Bytes", 7, Style::new().cyan()),
+            header("Gzip", 7, Style::new().yellow())
+        );
+        let mut first = true;
+        for (ty, bytes) in byte_map
+            .iter()
+            .sorted_by(|a, b| Ord::cmp(&a.1.len(), &b.1.len()))
+        {
+            let compressed = compressed_map.get(ty).map_or_else(|| s("-"), |len| len.to_string());
+            let bytes_style = if first { Style::new().bright_green() } else { Style::new().cyan() };
+            println!(
+                "{}{}{}",
+                cell(ty.to_string(), 12, Style::new().white()),
+                cell(bytes.len().to_string(), 7, bytes_style),
+                cell(compressed, 7, Style::new().yellow())
+            );
+            first = false;
+        }
+        if let Some((ty, len)) = compressed_map.iter().min_by_key(|(_, len)| **len) {
+            println!("Smallest compressed encoding is {} at {} bytes.", ty, len);
+        }
+        println!();
+
+        println!("Serialization Time Results (in MS)");
+        println!(
+            "{}{}{}",
+            header("Encoding", 12, Style::new().white()),
+            header("Se Time", 9, Style::new().cyan()),
+            header("De Time", 9, Style::new().yellow()),
+        );
+        first = true;
+        for (ty, rt) in time_map
+            .iter()
+            .sorted_by(|a, b| Ord::cmp(&a.1.se_duration, &b.1.se_duration))
+        {
+            let (se_style, de_style) = if first {
+                (Style::new().bright_green(), Style::new().bright_green())
+            } else {
+                (Style::new().cyan(), Style::new().yellow())
+            };
+            println!(
+                "{}{}{}",
+                cell(ty.to_string(), 12, Style::new().white()),
+                cell(format!("{:?}", rt.se_duration), 9, se_style),
+                cell(format!("{:?}", rt.de_duration), 9, de_style),
+            );
+            first = false;
+        }
+        println!();
+
+        Ok(())
+    }
+
+    fn run_diff_test(&self) -> Result<(), CommandError> {
+        if self.is_empty() {
+            return Err(CommandError::InputInvalid(
+                "TodoList must have at least 1 entry in order to run diff test!".to_string(),
+            ));
+        }
+
+        let mut other = self.clone();
+        println!("Diffing against cloned other...");
+        match self.diff_with(&other) {
+            DiffResult::Same => {
+                println!("Diff returned Same!");
+            }
+            DiffResult::Changes(diffs) => {
+                println!("Uh-oh, diff returned the following changes:");
+                for (i, d) in diffs.iter().enumerate() {
+                    println!("#{}: {}", i + 1, d);
+                }
+
+                println!();
+            }
+        }
+
+        let mut rng = rand::thread_rng();
+        let changes: usize = rng.gen_range(1..=self.len());
+        // The original state of every todo touched by a change, so that changes which cancel
+        // each other out (or touch the same todo twice) are only counted once.
+        let mut touched: HashMap<String, Option<bool>> = HashMap::new();
+        println!("Making {} changes.", changes);
+        for i in 0..changes {
+            let kind = if other.is_empty() { 1 } else { rng.gen_range(0..3) };
+            match kind {
+                0 => {
+                    // Change status
+                    let idx = rng.gen_range(0..other.len());
+                    let (todo, status) = match other.map.iter().nth(idx) {
+                        Some((s, b)) => (s.as_str().to_string(), b.completed),
+                        None => unreachable!(),
+                    };
+                    println!(
+                        "\t- Change #{}: Changing status of {:?} from {} to {}",
+                        i + 1,
+                        &todo,
+                        status,
+                        !status
+                    );
+                    touched
+                        .entry(todo.clone())
+                        .or_insert_with(|| self.get_status(&todo));
+                    let _ = other.set_status(todo, !status);
+                }
+                1 => {
+                    // Add Todo
+                    let to_add = format!("Here is a random todo I added. {}", rng.gen::<usize>());
+                    let status = rng.gen_bool(0.5);
+                    println!(
+                        "\t- Change #{}: Adding random todo {:?} with status {}",
+                        i + 1,
+                        &to_add,
+                        status
+                    );
+                    touched
+                        .entry(to_add.clone())
+                        .or_insert_with(|| self.get_status(&to_add));
+                    other.add_todo(&to_add, status);
+                }
+                2 => {
+                    // Remove Todo
+                    let idx = rng.gen_range(0..other.len());
+                    let existing = match other.map.iter().nth(idx) {
+                        Some((s, b)) => s.to_owned(),
+                        None => unreachable!(),
+                    };
+                    println!(
+                        "\t- Change #{}: Removing random todo {:?}",
+                        i + 1,
+                        &existing
+                    );
+                    touched
+                        .entry(existing.clone())
+                        .or_insert_with(|| self.get_status(&existing));
+                    other.remove_todo(&existing);
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        let expected = touched
+            .iter()
+            .filter(|(todo, before)| other.get_status(todo) != **before)
+            .count();
+
+        println!("Diffing against modified other...");
+        match self.diff_with(&other) {
+            DiffResult::Same => {
+                if expected == 0 {
+                    println!("Diff returned Same, the {} changes cancelled each other out.", changes);
+                } else {
+                    println!("Uh-oh, diff returned Same!");
+                }
+            }
+            DiffResult::Changes(diffs) => {
+                if diffs.len() == expected {
+                    println!(
+                        "Hurray, diff returned the correct number of differing todos ({}) for {} changes.",
+                        expected,
+                        changes
+                    );
+                } else {
+                    println!(
+                        "Uh oh, there are {} diff results but {} todos were changed.",
+                        diffs.len(),
+                        expected
+                    );
+                }
+
+                println!();
+                println!("Diff Entries:");
+                for (i, d) in diffs.iter().enumerate() {
+                    println!("#{}: {}", i + 1, d);
+                }
+
+                println!();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{state::actions::action_type::ActionType, todos::todolist_builder::TodoListBuilder};
+
+    #[test]
+    fn from_markdown_parses_checkboxes() {
+        let list = TodoList::from_markdown(
+            "# Groceries\n\n- [x] buy milk\n- [ ] buy eggs\n* [X] buy bread\nplain line\n",
+        );
+
+        assert_eq!(list.len(), 4);
+        assert_eq!(list.get_status("buy milk"), Some(true));
+        assert_eq!(list.get_status("buy eggs"), Some(false));
+        assert_eq!(list.get_status("buy bread"), Some(true));
+        assert_eq!(list.get_status("plain line"), Some(false));
+    }
+
+    #[test]
+    fn import_reads_json_object_of_statuses() {
+        let file = crate::utils::testing::temp_dir("json-object-import").join("todos.json");
+        std::fs::write(&file, r#"{"buy milk": false, "buy eggs": true}"#).unwrap();
+        let mut list = TodoList::new();
+
+        let summary = list.import_file(file.to_str().unwrap()).unwrap();
+
+        assert_eq!(summary, ImportSummary { imported: 2, skipped: 0 });
+        assert_eq!(list.get_todos_text(), vec!["buy milk", "buy eggs"]);
+        assert_eq!(list.get_status("buy milk"), Some(false));
+        assert_eq!(list.get_status("buy eggs"), Some(true));
+        assert!(TodoList::from_json_object(base_list().to_json().unwrap().as_bytes()).is_none());
+    }
+
+    #[test]
+    fn import_skips_duplicates() {
+        let mut list = TodoList::new();
+        list.add_todo("buy milk", false).unwrap();
+
+        let summary = list.import(TodoList::from_markdown("- [x] buy milk\n- [ ] buy eggs"));
+
+        assert_eq!(summary, ImportSummary { imported: 1, skipped: 1 });
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.get_status("buy milk"), Some(false));
+    }
+
+    #[test]
+    fn add_many_skips_duplicates() {
+        let mut list = TodoList::new();
+        assert_eq!(
+            list.add_todos(&["a", "b", "a"]),
+            AddSummary {
+                added: vec![s("a"), s("b")],
+                skipped: vec![s("a")],
+                blank: 0,
+            }
+        );
+
+        let mut list = TodoList::new();
+        let payload = ActionType::Add
+            .try_create_payload(&[s("a"), s("b"), s("a")])
+            .unwrap();
+        list.apply_action(payload).unwrap();
+        assert_eq!(list.get_todos_text(), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn add_many_counts_blank_items_separately() {
+        let mut list = TodoList::new();
+        let summary = list.add_todos(&["a", " ", "a", ""]);
+        assert_eq!(
+            summary,
+            AddSummary {
+                added: vec![s("a")],
+                skipped: vec![s("a")],
+                blank: 2,
+            }
+        );
+        assert_eq!(
+            summary.to_string(),
+            "Added 1 todo, skipped 1 duplicate. Left out 2 blank items."
+        );
+
+        let mut list = TodoList::new();
+        let payload = ActionType::Add.try_create_payload(&[s("a"), s(" "), s("b")]).unwrap();
+        list.apply_action(payload).unwrap();
+        assert_eq!(list.get_todos_text(), vec!["a", "b"]);
+        assert!(list.validate_action(&ActionPayload::AddMany(vec![s(""), s(" ")])).is_err());
+    }
+
+    #[test]
+    fn toggle_twice_restores_status() {
+        let mut list = base_list();
+
+        list.apply_action(ActionPayload::Toggle(s("one"))).unwrap();
+        assert_eq!(list.get_status("one"), Some(true));
+        list.apply_action(ActionPayload::Toggle(s("one"))).unwrap();
+        assert_eq!(list.get_status("one"), Some(false));
+
+        assert_eq!(
+            list.apply_action(ActionPayload::Toggle(s("missing"))),
+            Err(CommandError::TodoNotFound)
+        );
+    }
+
+    #[test]
+    fn completes_chosen_incomplete_todos() {
+        let mut list = base_list();
+        list.add_todo("three", false).unwrap();
+        list.add_todo("four", false).unwrap();
+
+        // The incomplete todos are "one", "three", and "four".
+        let chosen = list.chosen_incomplete(&[0, 2, 7]);
+        assert_eq!(chosen, vec![s("one"), s("four")]);
+
+        list.apply_action(ActionPayload::Complete(chosen)).unwrap();
+        assert_eq!(list.get_todos_with_status(false), vec!["three"]);
+        assert_eq!(list.get_todos_with_status(true), vec!["one", "two", "four"]);
+    }
+
+    #[test]
+    fn clear_needs_confirmation() {
+        let mut list = base_list();
+
+        let outcome = list.apply_action(ActionPayload::Clear).unwrap();
+        assert!(matches!(outcome, ActionOutcome::NeedsConfirmation(_)));
+        assert_eq!(list.len(), 2);
+
+        assert_eq!(list.apply_confirmed(ActionPayload::Clear), Ok(ActionOutcome::Cleared(2)));
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn outcomes_describe_changes() {
+        let mut list = base_list();
+
+        assert_eq!(
+            list.apply_action(ActionPayload::Add(s("three"))),
+            Ok(ActionOutcome::Added(s("three")))
+        );
+        assert_eq!(
+            list.apply_action(ActionPayload::Toggle(s("three"))),
+            Ok(ActionOutcome::Toggled(s("three"), true))
+        );
+        assert_eq!(
+            list.apply_action(ActionPayload::PurgeCompleted),
+            Ok(ActionOutcome::NeedsConfirmation(s(
+                "Are you sure you want to delete 2 completed todos?"
+            )))
+        );
+        assert!(matches!(
+            list.apply_action(ActionPayload::List),
+            Ok(ActionOutcome::Listing(listing)) if listing.contains("three")
+        ));
+    }
+
+    #[test]
+    fn clear_with_assume_yes_skips_prompt() {
+        let mut list = base_list();
+        list.set_assume_yes(true);
+
+        list.apply_action(ActionPayload::Clear).unwrap();
+
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn purge_removes_only_completed() {
+        let mut list = base_list();
+        list.add_todo("three", true).unwrap();
+
+        assert_eq!(list.purge_completed(), 2);
+        assert_eq!(list.get_todos_text(), vec!["one"]);
+        assert_eq!(list.purge_completed(), 0);
+    }
+
+    fn tagged_list() -> TodoList {
+        let mut list = base_list();
+        list.add_todo("three", false).unwrap();
+        list.tag_todo("one", "work").unwrap();
+        list.tag_todo("one", "urgent").unwrap();
+        list.tag_todo("two", "work").unwrap();
+        list.tag_todo("three", "home").unwrap();
+        list
+    }
+
+    #[test]
+    fn normalize_tags_merges_cased_duplicates() {
+        let json = r#"{"map":{"one":{"completed":false,"tags":["Work","work","URGENT","home","Home"]},"two":{"completed":true,"tags":["work"]}}}"#;
+        let mut list: TodoList = serde_json::from_str(json).unwrap();
+
+        assert_eq!(list.normalize_tags(), 2);
+        assert_eq!(list.get_todo("one").unwrap().tags, vec![s("work"), s("urgent"), s("home")]);
+        assert_eq!(list.get_todo("two").unwrap().tags, vec![s("work")]);
+        assert_eq!(list.normalize_tags(), 0);
+    }
+
+    #[test]
+    fn clear_tags_removes_everything() {
+        let mut list = tagged_list();
+
+        assert_eq!(list.clear_tags(None), 3);
+        assert!(list.map.values().all(|item| item.tags.is_empty()));
+    }
+
+    #[test]
+    fn clear_tags_removes_single_tag() {
+        let mut list = tagged_list();
+
+        list.apply_action(ActionPayload::ClearTags(Some(s("work")))).unwrap();
+
+        assert_eq!(list.get_todo("one").unwrap().tags, vec![s("urgent")]);
+        assert!(list.get_todo("two").unwrap().tags.is_empty());
+        assert_eq!(list.get_todo("three").unwrap().tags, vec![s("home")]);
+    }
+
+    #[test]
+    fn set_color_validates_palette() {
+        let mut list = base_list();
+
+        list.apply_action(ActionPayload::Color(s("one"), s("Blue"))).unwrap();
+        assert_eq!(list.get_todo("one").unwrap().color.as_deref(), Some("blue"));
+
+        assert!(matches!(
+            list.apply_action(ActionPayload::Color(s("one"), s("chartreuse"))),
+            Err(CommandError::InputInvalid(_))
+        ));
+        assert_eq!(
+            list.apply_action(ActionPayload::Color(s("missing"), s("red"))),
+            Err(CommandError::TodoNotFound)
+        );
+        assert_eq!(list.get_todo("one").unwrap().color.as_deref(), Some("blue"));
+    }
+
+    #[test]
+    fn list_aligns_metadata_and_respects_no_color() {
+        let mut list = base_list();
+        list.add_todo("buy milk", false).unwrap();
+        list.apply_action(ActionPayload::Due(s("buy milk"), Some(NaiveDate::from_ymd(2021, 11, 1)))).unwrap();
+        list.apply_action(ActionPayload::Tag(s("two"), s("home"))).unwrap();
+
+        assert_eq!(
+            list.render_list(RenderOptions::plain()),
+            "\nAll Todos\n--- -----\n[ ] \"one\"\n[X] \"two\"       #home\n[ ] \"buy milk\"  due 2021-11-01\n\n"
+        );
+        assert_eq!(
+            list.render_list(RenderOptions::plain().with_status(true)),
+            "\nCompleted Todos\n--------- -----\n[X] \"two\"  #home\n\n"
+        );
+
+        let colored = list.render_list(RenderOptions::default());
+        assert!(colored.contains(&"[X]".green().to_string()));
+        assert!(colored.contains(&"#home".cyan().to_string()));
+    }
+
+    #[test]
+    fn applied_listings_are_colored_only_when_asked() {
+        let mut list = base_list();
+        let plain = list.apply_action(ActionPayload::List).unwrap().to_string();
+        assert!(!plain.contains('\u{1b}'), "{:?}", plain);
+
+        list.set_colorize(true);
+        let colored = list.apply_action(ActionPayload::List).unwrap().to_string();
+        assert!(colored.contains(&"[X]".green().to_string()), "{:?}", colored);
+    }
+
+    #[test]
+    fn custom_status_symbols_replace_the_defaults() {
+        let mut list = base_list();
+        list.apply_action(ActionPayload::Tag(s("two"), s("home"))).unwrap();
+        list.set_status_symbols("✔", "✗");
+
+        let listing = list.render_list(RenderOptions::plain());
+
+        assert_eq!(listing, "\nAll Todos\n--- -----\n✗ \"one\"\n✔ \"two\"  #home\n\n");
+        assert!(!listing.contains("[X]") && !listing.contains("[ ]"));
+    }
+
+    #[test]
+    fn added_text_is_trimmed_and_blank_text_refused() {
+        let mut list = TodoList::new();
+        assert!(matches!(list.add_todo("  ", false), Err(CommandError::InputInvalid(_))));
+        assert!(matches!(
+            list.validate_action(&ActionPayload::Add(s(" \t "))),
+            Err(CommandError::InputInvalid(_))
+        ));
+
+        list.add_todo("  buy milk\nand eggs ", false).unwrap();
+        assert_eq!(list.get_todos_text(), vec!["buy milk\nand eggs"]);
+        assert!(matches!(list.add_todo("buy milk\nand eggs  ", false), Err(CommandError::TodoAlreadyExists)));
+    }
+
+    #[test]
+    fn edit_set_and_split_trim_text_and_refuse_blank_text() {
+        let mut list = base_list();
+        assert!(matches!(
+            list.apply_action(ActionPayload::Edit(s("one"), s("   "))),
+            Err(CommandError::InputInvalid(_))
+        ));
+        assert_eq!(
+            list.apply_action(ActionPayload::Edit(s("one"), s("  uno "))),
+            Ok(ActionOutcome::Edited(s("one"), s("uno")))
+        );
+
+        assert!(matches!(
+            list.apply_action(ActionPayload::Set(s(""), true)),
+            Err(CommandError::InputInvalid(_))
+        ));
+        assert!(matches!(list.set_status("  ", true), Err(CommandError::InputInvalid(_))));
+        list.apply_action(ActionPayload::Set(s("  three "), true)).unwrap();
+        assert_eq!(list.get_todos_text(), vec!["uno", "two", "three"]);
+        assert_eq!(list.get_status("three"), Some(true));
+
+        assert!(matches!(
+            list.apply_action(ActionPayload::Split(s("uno"), vec![s("a"), s(" ")])),
+            Err(CommandError::InputInvalid(_))
+        ));
+        list.apply_action(ActionPayload::Split(s("uno"), vec![s(" a"), s("b ")])).unwrap();
+        assert_eq!(list.get_todos_text(), vec!["a", "b", "two", "three"]);
+    }
+
+    #[test]
+    fn case_insensitive_duplicates_are_refused() {
+        let mut list = TodoList::new();
+        list.add_todo("Buy Milk", false).unwrap();
+        assert!(matches!(list.add_todo("Buy Milk", false), Err(CommandError::TodoAlreadyExists)));
+        list.add_todo("buy milk", false).unwrap();
+
+        let mut list = TodoList::new();
+        list.set_case_insensitive_duplicates(true);
+        list.add_todo("Buy Milk", false).unwrap();
+        assert!(matches!(list.add_todo("Buy Milk", false), Err(CommandError::TodoAlreadyExists)));
+        assert!(matches!(list.add_todo("buy MILK", false), Err(CommandError::TodoAlreadyExists)));
+        assert!(matches!(list.add_todo(" buy milk ", false), Err(CommandError::TodoAlreadyExists)));
+        assert!(matches!(
+            list.validate_action(&ActionPayload::Add(s("BUY MILK"))),
+            Err(CommandError::TodoAlreadyExists)
+        ));
+
+        list.add_todo("  Buy Eggs ", false).unwrap();
+        assert_eq!(list.get_todos_text(), vec!["Buy Milk", "Buy Eggs"]);
+
+        assert!(matches!(
+            list.apply_action(ActionPayload::Edit(s("Buy Eggs"), s("BUY MILK"))),
+            Err(CommandError::TodoAlreadyExists)
+        ));
+        assert!(matches!(
+            list.validate_action(&ActionPayload::Edit(s("Buy Eggs"), s("buy milk"))),
+            Err(CommandError::TodoAlreadyExists)
+        ));
+        list.apply_action(ActionPayload::Edit(s("Buy Eggs"), s("buy eggs"))).unwrap();
+        assert!(matches!(list.set_status("BUY MILK", true), Err(CommandError::TodoAlreadyExists)));
+        assert!(matches!(
+            list.split_todo("buy eggs", &["eggs", "Buy milk"]),
+            Err(CommandError::TodoAlreadyExists)
+        ));
+
+        let summary = list.import(TodoList::from(vec![(s("buy MILK"), true), (s("bread"), false)]));
+        assert_eq!((summary.imported, summary.skipped), (1, 1));
+        assert_eq!(list.get_todos_text(), vec!["Buy Milk", "buy eggs", "bread"]);
+    }
+
+    #[test]
+    fn edit_renames_in_place_and_refuses_collisions() {
+        let mut list = base_list();
+        list.apply_action(ActionPayload::Tag(s("one"), s("home"))).unwrap();
+
+        list.apply_action(ActionPayload::Edit(s("one"), s("uno"))).unwrap();
+        assert_eq!(list.get_todos_text(), vec!["uno", "two"]);
+        assert_eq!(list.get_todo("uno").unwrap().tags, vec![s("home")]);
+        assert_eq!(list.get_status("uno"), Some(false));
+
+        assert!(matches!(
+            list.apply_action(ActionPayload::Edit(s("uno"), s("two"))),
+            Err(CommandError::TodoAlreadyExists)
+        ));
+        assert!(matches!(
+            list.validate_action(&ActionPayload::Edit(s("uno"), s("two"))),
+            Err(CommandError::TodoAlreadyExists)
+        ));
+        assert_eq!(list.get_status("two"), Some(true));
+
+        list.apply_action(ActionPayload::Edit(s("two"), s("two"))).unwrap();
+        assert_eq!(list.get_todos_text(), vec!["uno", "two"]);
+        assert_eq!(list.get_status("two"), Some(true));
+    }
+
+    #[test]
+    fn replay_rebuilds_the_list_and_detects_divergence() {
+        let actions = vec![
+            ActionPayload::Add(s("one")),
+            ActionPayload::Add(s("two")),
+            ActionPayload::List,
+            ActionPayload::Toggle(s("two")),
+            ActionPayload::Add(s("three")),
+            ActionPayload::Clear,
+            ActionPayload::Add(s("one")),
+            ActionPayload::Add(s("two")),
+            ActionPayload::Toggle(s("two")),
+        ];
+        let replayed = TodoList::replay(actions.clone()).unwrap();
+        assert_eq!(replayed.diff_with(&base_list()), DiffResult::Same);
+        assert!(matches!(
+            TodoList::replay(vec![ActionPayload::Recover]),
+            Err(CommandError::InputInvalid(_))
+        ));
+
+        let dir = crate::utils::testing::temp_dir("todolist-replay");
+        let log = dir.join("actions.jsonl");
+        for action in &actions {
+            ActionLog::append(&log, action).unwrap();
+        }
+        let log = log.to_string_lossy().to_string();
+        let mut list = base_list();
+        assert!(matches!(
+            list.apply_action(ActionPayload::Replay(log.clone())),
+            Ok(ActionOutcome::Listing(listing)) if listing.starts_with("Replaying the 9 actions")
+        ));
+
+        list.add_todo("four", false).unwrap();
+        assert!(matches!(
+            list.apply_action(ActionPayload::Replay(log)),
+            Ok(ActionOutcome::Listing(listing)) if listing.contains("+ \"four\"")
+        ));
+    }
+
+    #[test]
+    fn replay_skips_actions_that_touch_files() {
+        let dir = crate::utils::testing::temp_dir("todolist-replay-files");
+        let import = dir.join("import.md");
+        std::fs::write(&import, "- [ ] from a file\n").unwrap();
+        let export = dir.join("export.md");
+        let actions = vec![
+            ActionPayload::Add(s("one")),
+            ActionPayload::Import(import.to_string_lossy().to_string()),
+            ActionPayload::ApplyPatch(dir.join("missing.patch").to_string_lossy().to_string()),
+            ActionPayload::Export(export.to_string_lossy().to_string()),
+            ActionPayload::Replay(dir.join("actions.jsonl").to_string_lossy().to_string()),
+            ActionPayload::Toggle(s("one")),
+        ];
+
+        let replayed = TodoList::replay(actions.clone()).unwrap();
+        assert_eq!(replayed.get_todos_text(), vec!["one"]);
+        assert_eq!(replayed.get_status("one"), Some(true));
+        assert!(!export.exists());
+        assert_eq!(TodoList::replay(actions).unwrap().get_todo("one"), replayed.get_todo("one"));
+        assert_eq!(replayed.get_todo("one").unwrap().completed_at, None);
+    }
+
+    #[test]
+    fn changes_render_what_a_save_persists() {
+        let saved = base_list();
+        let mut current = saved.clone();
+        current.apply_action(ActionPayload::Add(s("buy milk"))).unwrap();
+        current.apply_action(ActionPayload::Toggle(s("one"))).unwrap();
+
+        assert_eq!(
+            saved.diff_with(&current).render_changes(false),
+            "\t~ \"one\" is now complete\n\t+ \"buy milk\"\n1 added, 0 removed, 1 changed\n"
+        );
+        assert_eq!(saved.diff_with(&saved).render_changes(false), "No changes.\n");
+    }
+
+    #[test]
+    fn max_width_truncates_long_todos() {
+        let mut list = base_list();
+        list.add_todo("write the quarterly report", false).unwrap();
+
+        list.set_max_width(10);
+        let listing = list.render_list(RenderOptions::plain());
+        assert!(listing.contains("[ ] \"write the…\"\n"), "{}", listing);
+        assert!(listing.contains("[ ] \"one\"\n"), "{}", listing);
+
+        list.set_max_width(0);
+        let listing = list.render_list(RenderOptions::plain());
+        assert!(listing.contains("[ ] \"write the quarterly report\"\n"), "{}", listing);
+    }
+
+    #[test]
+    fn table_view_lists_todos_in_columns() {
+        let mut list = base_list();
+        list.add_todo("buy milk", false).unwrap();
+        list.apply_action(ActionPayload::Due(s("buy milk"), Some(NaiveDate::from_ymd(2021, 11, 1)))).unwrap();
+        list.apply_action(ActionPayload::Tag(s("two"), s("home"))).unwrap();
+
+        assert_eq!(
+            list.render_list(RenderOptions::default().with_table(true)),
+            concat!(
+                "\nAll Todos\n--- -----\n",
+                "Status  Todo      Tags  Due\n",
+                "------  --------  ----  ----------\n",
+                "[ ]     one\n",
+                "[X]     two       home\n",
+                "[ ]     buy milk        2021-11-01\n",
+                "\n"
+            )
+        );
+        list.set_table_view(true);
+        assert!(list.render_listing(&ActionPayload::ListWithStatus(false), false).unwrap().contains("Status  Todo"));
+    }
+
+    #[test]
+    fn listing_renders_todo_color() {
+        let mut list = base_list();
+        list.set_color("one", "red").unwrap();
+
+        let colored = list.render_listing(&ActionPayload::List, true).unwrap();
+        assert!(colored.contains(&format!("{:?}", "one").red().to_string()));
+        assert!(!colored.contains(&format!("{:?}", "two").red().to_string()));
+
+        let plain = list.render_listing(&ActionPayload::List, false).unwrap();
+        assert_eq!(plain, "\nAll Todos\n--- -----\n[ ] \"one\"\n[X] \"two\"\n\n");
+    }
+
+    #[test]
+    fn loads_lists_saved_with_bare_statuses() {
+        #[derive(Serialize)]
+        struct LegacyList {
+            map: IndexMap<String, bool>,
+        }
+
+        let mut map = IndexMap::new();
+        map.insert(s("one"), false);
+        map.insert(s("two"), true);
+        let legacy = LegacyList { map };
+
+        for ty in EncodingType::all() {
+            let bytes = Cereal::serialize_with(ty, &legacy).unwrap();
+            let list: TodoList = Cereal::deserialize_with(ty, &bytes).unwrap();
+            assert_eq!(list.diff_with(&base_list()), DiffResult::Same, "{}", ty);
+        }
+    }
+
+    fn base_list() -> TodoList {
+        TodoListBuilder::new().todo("one").done("two").build()
+    }
+
+    #[test]
+    fn diff_reports_added_todo() {
+        let base = base_list();
+        let mut other = base.clone();
+        other.add_todo("three", false).unwrap();
+
+        assert_eq!(
+            base.diff_with(&other),
+            DiffResult::Changes(vec![DiffEntry::TodoNotFound {
+                todo: "three".to_string(),
+                this_has: false,
+                that_has: true,
+                completed: false,
+            }])
+        );
+    }
+
+    #[test]
+    fn diff_reports_removed_todo() {
+        let base = base_list();
+        let mut other = base.clone();
+        other.remove_todo("one");
+
+        assert_eq!(
+            base.diff_with(&other),
+            DiffResult::Changes(vec![DiffEntry::TodoNotFound {
+                todo: "one".to_string(),
+                this_has: true,
+                that_has: false,
+                completed: false,
+            }])
+        );
+    }
+
+    #[test]
+    fn diff_reports_status_flip() {
+        let base = base_list();
+        let mut other = base.clone();
+        other.set_status("two", false).unwrap();
+
+        assert_eq!(
+            base.diff_with(&other),
+            DiffResult::Changes(vec![DiffEntry::TodoStatusMistake {
+                todo: "two".to_string(),
+                this_status: true,
+                that_status: false,
+            }])
+        );
+    }
+
+    #[test]
+    fn diff_reports_added_then_flipped_todo_once() {
+        let base = base_list();
+        let mut other = base.clone();
+        other.add_todo("three", false).unwrap();
+        other.set_status("three", true).unwrap();
+
+        assert_eq!(
+            base.diff_with(&other),
+            DiffResult::Changes(vec![DiffEntry::TodoNotFound {
+                todo: "three".to_string(),
+                this_has: false,
+                that_has: true,
+                completed: true,
+            }])
+        );
+    }
+
+    #[test]
+    fn patch_from_diff_turns_base_into_other() {
+        let mut base = base_list();
+        base.add_todo("three", false).unwrap();
+        let mut other = base.clone();
+        other.remove_todo("one");
+        other.set_status("two", false).unwrap();
+        other.add_todo("four", true).unwrap();
+
+        let patch = base.diff_with(&other).to_patch();
+        assert_eq!(patch.len(), 3);
+        base.apply_patch(&patch).unwrap();
+
+        assert_eq!(base.diff_with(&other), DiffResult::Same);
+        assert!(DiffResult::Same.to_patch().is_empty());
+    }
+
+    #[test]
+    fn diff_summary_counts_each_kind_of_change() {
+        let mut base = base_list();
+        base.add_todo("three", false).unwrap();
+        let mut other = base.clone();
+        other.remove_todo("one");
+        other.set_status("two", false).unwrap();
+        other.set_status("three", true).unwrap();
+        other.add_todo("four", false).unwrap();
+        other.add_todo("five", true).unwrap();
+        other.add_todo("six", false).unwrap();
+
+        let summary = base.diff_with(&other).summary();
+        assert_eq!(summary, DiffSummary { added: 3, removed: 1, changed: 2 });
+        assert_eq!(summary.render(false), "3 added, 1 removed, 2 changed");
+        assert_ne!(summary.render(true), summary.render(false));
+        assert_eq!(DiffResult::Same.summary().to_string(), "0 added, 0 removed, 0 changed");
+    }
+
+    #[test]
+    fn todos_keep_insertion_order() {
+        let mut list = TodoList::new();
+        for todo in ["c", "a", "d", "b"] {
+            list.add_todo(todo, false).unwrap();
+        }
+
+        let first: Vec<String> = list.get_todos_text().into_iter().cloned().collect();
+        let second: Vec<String> = list.get_todos_text().into_iter().cloned().collect();
+
+        assert_eq!(first, vec!["c", "a", "d", "b"]);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn move_todo_reorders() {
+        let mut list = TodoList::new();
+        for todo in ["a", "b", "c"] {
+            list.add_todo(todo, false).unwrap();
+        }
+
+        list.move_todo("c", 0).unwrap();
+        assert_eq!(list.get_todos_text(), vec!["c", "a", "b"]);
+
+        list.move_todo("c", 10).unwrap();
+        assert_eq!(list.get_todos_text(), vec!["a", "b", "c"]);
+
+        assert_eq!(list.move_todo("missing", 0), Err(CommandError::TodoNotFound));
+    }
+
+    #[test]
+    fn order_survives_serialization() {
+        let mut list = TodoList::new();
+        for todo in ["z", "y", "x"] {
+            list.add_todo(todo, false).unwrap();
+        }
+
+        for ty in EncodingType::all() {
+            let bytes = Cereal::serialize_with(ty, &list).unwrap();
+            let recreated: TodoList = Cereal::deserialize_with(ty, &bytes).unwrap();
+
+            // FlexBuffers stores map keys sorted so that they can be binary searched.
+            if ty != EncodingType::FlexBuffer {
+                assert_eq!(recreated.get_todos_text(), vec!["z", "y", "x"], "{}", ty);
+            }
+            assert_eq!(list.diff_with(&recreated), DiffResult::Same);
+        }
+    }
+
+    #[test]
+    fn save_listing_writes_rendered_list() {
+        let dir = crate::utils::testing::temp_dir("save-listing");
+        let file = dir.join("listing.txt");
+        let list = TodoListBuilder::new().done("buy milk").todo("buy eggs").build();
+
+        list.save_listing(&ActionPayload::List, &file).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            "\nAll Todos\n--- -----\n[X] \"buy milk\"\n[ ] \"buy eggs\"\n\n"
+        );
+        assert!(list.save_listing(&ActionPayload::Clear, &file).is_err());
+    }
+
+    #[test]
+    fn dump_prints_json_without_changing_anything() {
+        let mut list = base_list();
+
+        let outcome = list.apply_action(ActionPayload::Dump).unwrap();
+
+        let json = match &outcome {
+            ActionOutcome::Listing(json) => json,
+            other => panic!("Expected a listing, got {:?}", other),
+        };
+        let value: serde_json::Value = serde_json::from_str(json).unwrap();
+        assert_eq!(value["map"]["two"]["completed"], serde_json::Value::Bool(true));
+        assert!(!outcome.is_change());
+        assert_eq!(list.diff_with(&base_list()), DiffResult::Same);
+    }
+
+    #[test]
+    fn dry_run_describes_without_mutating() {
+        let list = base_list();
+
+        assert_eq!(
+            list.apply_action_dry(&ActionPayload::Clear),
+            Ok(s("Would delete all 2 todos."))
+        );
+        assert_eq!(
+            list.apply_action_dry(&ActionPayload::Remove(s("one"))),
+            Ok(s("Would remove \"one\"."))
+        );
+        assert_eq!(
+            list.apply_action_dry(&ActionPayload::Remove(s("missing"))),
+            Err(CommandError::TodoNotFound)
+        );
+        assert_eq!(list.len(), 2);
+    }
+
+    #[test]
+    fn preview_describes_what_will_happen() {
+        let list = base_list();
+
+        assert_eq!(payload_preview(&ActionPayload::Add(s("buy milk")), &list), "Will add \"buy milk\".");
+        assert_eq!(payload_preview(&ActionPayload::Remove(s("one")), &list), "Will remove \"one\".");
+        assert_eq!(payload_preview(&ActionPayload::Clear, &list), "Will delete all 2 todos.");
+        assert_eq!(
+            payload_preview(&ActionPayload::Set(s("one"), true), &list),
+            "Will mark \"one\" as complete."
+        );
+        assert_eq!(
+            payload_preview(&ActionPayload::Remove(s("missing")), &list),
+            "Will fail, Todo with that name not found"
+        );
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let mut list = TodoList::new();
+        list.add_todo("buy milk, eggs", true).unwrap();
+        list.add_todo("read \"the book\"", false).unwrap();
+        list.add_todo("plain", false).unwrap();
+
+        let csv = list.to_csv().unwrap();
+        let recreated = TodoList::from_csv(&csv).unwrap();
+
+        assert_eq!(list.diff_with(&recreated), DiffResult::Same);
+    }
+
+    #[test]
+    fn from_csv_reports_line_of_malformed_row() {
+        let err = TodoList::from_csv("text,completed\nbuy milk,true\nbuy eggs,maybe\n").unwrap_err();
+
+        assert!(err.to_string().contains("line 3"), "{}", err);
+    }
+
+    #[test]
+    fn porcelain_round_trips_tabs_and_newlines() {
+        let mut list = base_list();
+        list.add_todo("buy\tmilk\nand eggs", true).unwrap();
+        list.add_todo("C:\\temp\\notes", false).unwrap();
+
+        let porcelain = list.to_porcelain();
+        assert_eq!(porcelain.lines().count(), 4);
+        assert!(porcelain.contains("true\tbuy\\tmilk\\nand eggs\n"), "{:?}", porcelain);
+
+        let recreated = TodoList::from_porcelain(&porcelain).unwrap();
+        assert_eq!(recreated.get_todos(), list.get_todos());
+
+        let file = crate::utils::testing::temp_dir("porcelain-round-trip").join("todos.tsv");
+        list.export_file(file.to_str().unwrap()).unwrap();
+        let mut imported = TodoList::new();
+        imported.import_file(file.to_str().unwrap()).unwrap();
+        assert_eq!(imported.get_status("buy\tmilk\nand eggs"), Some(true));
+
+        let err = TodoList::from_porcelain("true\tone\nmaybe\ttwo\n").unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{}", err);
+    }
+
+    #[test]
+    fn jsonl_round_trip() {
+        let file = crate::utils::testing::temp_dir("jsonl-round-trip").join("todos.jsonl");
+        let mut list = tagged_list();
+        list.set_color("two", "green").unwrap();
+
+        list.export_file(file.to_str().unwrap()).unwrap();
+        let mut recreated = TodoList::new();
+        let summary = recreated.import_file(file.to_str().unwrap()).unwrap();
+
+        assert_eq!(summary.imported, list.len());
+        assert_eq!(recreated.get_todos_text(), list.get_todos_text());
+        for (text, todo) in list.map.iter() {
+            assert_eq!(recreated.get_todo(text), Some(todo));
+        }
+    }
+
+    #[test]
+    fn jsonl_import_streams_large_files() {
+        let file = crate::utils::testing::temp_dir("jsonl-streaming").join("todos.jsonl");
+        let mut source = TodoList::new();
+        for i in 0..3500 {
+            source.add_todo(format!("todo number {}", i), i % 2 == 0).unwrap();
+        }
+        source.export_file(file.to_str().unwrap()).unwrap();
+
+        let mut list = base_list();
+        list.add_todo("todo number 7", false).unwrap();
+        let mut reports = Vec::new();
+        let summary = list.import_jsonl_with(&file, |count| reports.push(count)).unwrap();
+
+        assert_eq!(summary, ImportSummary { imported: 3499, skipped: 1 });
+        assert_eq!(list.len(), 3502);
+        assert_eq!(list.get_status("todo number 3498"), Some(true));
+        assert_eq!(list.get_status("todo number 7"), Some(false));
+        assert_eq!(reports, vec![1000, 2000, 3000]);
+
+        let mut reports = Vec::new();
+        let summary = TodoList::new()
+            .import_file_with(file.to_str().unwrap(), |count| reports.push(count))
+            .unwrap();
+        assert_eq!(summary.imported, 3500);
+        assert_eq!(reports, vec![1000, 2000, 3000]);
+    }
+
+    #[test]
+    fn compressed_round_trip() {
+        let mut list = tagged_list();
+        for i in 0..200 {
+            list.add_todo(format!("todo number {}", i), i % 3 == 0).unwrap();
+        }
+        list.set_color("todo number 7", "cyan").unwrap();
+
+        for ty in EncodingType::all() {
+            let bytes = Cereal::serialize_compressed(ty, &list).unwrap();
+            let recreated: TodoList = Cereal::deserialize_compressed(ty, &bytes).unwrap();
+            assert_eq!(list.diff_with(&recreated), DiffResult::Same, "{}", ty);
+        }
+
+        let raw = Cereal::serialize_with(EncodingType::Json, &list).unwrap();
+        let compressed = Cereal::serialize_compressed(EncodingType::Json, &list).unwrap();
+        assert!(compressed.len() < raw.len());
+    }
+
+    #[test]
+    fn weekly_digest_sorts_todos_into_sections() {
+        // Wednesday, so the week runs from Monday the 11th to Sunday the 17th.
+        let today = NaiveDate::from_ymd(2021, 10, 13);
+        let at = |day: u32| Some(DateTime::<Utc>::from_utc(NaiveDate::from_ymd(2021, 10, day).and_hms(12, 0, 0), Utc));
+        let due = |day: u32| Some(NaiveDate::from_ymd(2021, 10, day));
+        let mut list = TodoList::new();
+        let mut insert = |text: &str, todo: Todo| {
+            list.map.insert(s(text), todo);
+        };
+        insert("finished", Todo { completed: true, created_at: at(1), completed_at: at(12), ..Todo::default() });
+        insert("finished last week", Todo { completed: true, created_at: at(1), completed_at: at(8), ..Todo::default() });
+        insert("new", Todo { created_at: at(11), ..Todo::default() });
+        insert("late", Todo { created_at: at(1), due: due(12), ..Todo::default() });
+        insert("friday", Todo { created_at: at(1), due: due(15), ..Todo::default() });
+        insert("next week", Todo { created_at: at(1), due: due(18), ..Todo::default() });
+        insert("done late", Todo { completed: true, created_at: at(1), completed_at: at(4), due: due(5), ..Todo::default() });
+        insert("legacy", Todo::new(false));
+
+        let digest = list.weekly_digest(today);
+
+        assert_eq!(digest.completed, vec![s("finished")]);
+        assert_eq!(digest.added, vec![s("new")]);
+        assert_eq!(digest.overdue, vec![s("late")]);
+        assert_eq!(digest.due, vec![s("friday")]);
+    }
+
+    #[test]
+    fn completing_records_when() {
+        let mut list = TodoList::new();
+        list.add_todo("one", false).unwrap();
+        assert!(list.get_todo("one").unwrap().created_at.is_some());
+        assert!(list.get_todo("one").unwrap().completed_at.is_none());
+
+        list.toggle_todo("one").unwrap();
+        assert!(list.get_todo("one").unwrap().completed_at.is_some());
+        list.toggle_todo("one").unwrap();
+        assert!(list.get_todo("one").unwrap().completed_at.is_none());
+    }
+
+    #[test]
+    fn patch_command_applies_patch_file() {
+        let file = crate::utils::testing::temp_dir("patch-command").join("changes.patch.json");
+        let base = base_list();
+        let mut other = base.clone();
+        other.remove_todo("one");
+        other.add_todo("three", true).unwrap();
+        base.diff_with(&other).to_patch().save(&file).unwrap();
+        let path = file.to_str().unwrap().to_string();
+
+        let mut list = base_list();
+        let outcome = list.apply_action(ActionPayload::ApplyPatch(path.clone())).unwrap();
+        assert_eq!(outcome, ActionOutcome::Patched { file: path.clone(), count: 2 });
+        assert_eq!(list.diff_with(&other), DiffResult::Same);
+
+        let mut mismatched = TodoList::new();
+        mismatched.add_todo("two", false).unwrap();
+        assert!(mismatched.apply_action(ActionPayload::ApplyPatch(path)).is_err());
+        assert_eq!(mismatched.get_todos_text(), vec!["two"]);
+    }
+
+    #[test]
+    fn notes_survive_encodings_and_mark_listings() {
+        let mut list = base_list();
+        let note = s("first line\nsecond line");
+        list.apply_action(ActionPayload::Note(s("one"), Some(note.clone()))).unwrap();
+
+        for ty in EncodingType::all() {
+            let bytes = Cereal::serialize_with(ty, &list).unwrap();
+            let recreated: TodoList = Cereal::deserialize_with(ty, &bytes).unwrap();
+            assert_eq!(recreated.get_todo("one").unwrap().description.as_ref(), Some(&note), "{}", ty);
+            assert_eq!(recreated.get_todo("two").unwrap().description, None, "{}", ty);
+        }
+
+        let listing = list.render_list(RenderOptions::plain());
+        assert!(listing.contains("[ ] \"one\"*\n"), "{}", listing);
+        assert!(!listing.contains("second line"), "{}", listing);
+        assert!(list.render_todo("one", false).unwrap().ends_with("\nfirst line\nsecond line\n"));
+
+        list.apply_action(ActionPayload::Note(s("one"), None)).unwrap();
+        assert_eq!(list.get_todo("one").unwrap().description, None);
+    }
+
+    #[test]
+    fn ages_count_from_creation() {
+        let now = DateTime::<Utc>::from_utc(NaiveDate::from_ymd(2021, 10, 13).and_hms(12, 0, 0), Utc);
+        let mut list = TodoList::new();
+        list.map.insert(s("old"), Todo { created_at: Some(now - chrono::Duration::days(10)), ..Todo::default() });
+        list.map.insert(s("done"), Todo { completed: true, created_at: Some(now - chrono::Duration::days(3)), ..Todo::default() });
+        list.map.insert(s("legacy"), Todo::new(false));
+
+        let ages = list.open_ages(now);
+        assert_eq!(ages.len(), 2);
+        assert_eq!(ages[0].1.map(|age| age.num_days()), Some(10));
+        assert_eq!(ages[1].1, None);
+        assert!(list.render_ages(now, false).contains("\"old\" open for 10 days"));
+    }
+
+    #[test]
+    fn list_by_priority_filters_and_sorts() {
+        let mut list = TodoList::new();
+        for (todo, priority) in [("b", Priority::High), ("c", Priority::Low), ("a", Priority::High)] {
+            list.add_todo(todo, false).unwrap();
+            list.apply_action(ActionPayload::SetPriority(s(todo), Some(priority))).unwrap();
+        }
+        list.add_todo("none", false).unwrap();
+
+        assert_eq!(list.todos_with_priority(Priority::High), vec!["a", "b"]);
+        assert_eq!(list.todos_with_priority(Priority::Low), vec!["c"]);
+        assert!(list.todos_with_priority(Priority::Medium).is_empty());
+
+        let listing = list.render_list_by_priority(Priority::High, false);
+        assert!(listing.starts_with("High Priority Todos (2)\n"), "{}", listing);
+        assert!(!listing.contains("\"c\""), "{}", listing);
+        assert_eq!(
+            list.render_list_by_priority(Priority::Medium, false),
+            "There are no medium priority todos in the database.\n"
+        );
+    }
+
+    #[test]
+    fn stale_todos_are_oldest_first() {
+        let now = DateTime::<Utc>::from_utc(NaiveDate::from_ymd(2021, 10, 13).and_hms(12, 0, 0), Utc);
+        let open_for = |days: i64| Todo { created_at: Some(now - chrono::Duration::days(days)), ..Todo::default() };
+        let mut list = TodoList::new();
+        list.map.insert(s("week"), open_for(7));
+        list.map.insert(s("fresh"), open_for(1));
+        list.map.insert(s("month"), open_for(30));
+        list.map.insert(s("done"), Todo { completed: true, ..open_for(60) });
+        list.map.insert(s("legacy"), Todo::new(false));
+
+        assert_eq!(list.stale_todos(now, 5), vec![(&s("month"), 30), (&s("week"), 7)]);
+        assert!(list.stale_todos(now, 30).is_empty());
+        assert!(list.render_stale(now, 30, false).starts_with("Nothing has been open"));
+        assert!(matches!(stale_style(30, 5), AnsiColors::Red));
+        assert!(matches!(stale_style(7, 5), AnsiColors::Yellow));
+
+        assert!(ActionType::Stale.try_create_payload(&[s("-1")]).is_err());
+        assert!(ActionType::Stale.try_create_payload(&[s("soon")]).is_err());
+    }
+
+    #[test]
+    fn subtasks_render_nested_and_block_completion() {
+        let mut list = TodoListBuilder::new().todo("Ship release").todo("Unrelated").build();
+        list.apply_action(ActionPayload::AddSub(s("Ship release"), s("Write changelog"))).unwrap();
+        list.apply_action(ActionPayload::AddSub(s("Ship release"), s("Tag version"))).unwrap();
+        list.set_status("Write changelog", true).unwrap();
+
+        assert_eq!(
+            list.render_listing(&ActionPayload::List, false).unwrap(),
+            "\nAll Todos\n--- -----\n[ ] \"Ship release\"\n    [X] \"Write changelog\"\n    [ ] \"Tag version\"\n[ ] \"Unrelated\"\n\n"
+        );
+        assert!(matches!(
+            list.apply_action(ActionPayload::Toggle(s("Ship release"))),
+            Err(CommandError::InputInvalid(msg)) if msg.contains("1 incomplete subtasks")
+        ));
+        assert_eq!(list.get_status("Ship release"), Some(false));
+
+        list.apply_action(ActionPayload::Edit(s("Ship release"), s("Ship v1"))).unwrap();
+        assert_eq!(list.subtasks_of("Ship v1"), vec!["Write changelog", "Tag version"]);
+        list.apply_action(ActionPayload::Complete(vec![s("Tag version"), s("Ship v1")])).unwrap();
+        assert_eq!(list.get_status("Ship v1"), Some(true));
+
+        list.apply_action(ActionPayload::Remove(s("Ship v1"))).unwrap();
+        assert_eq!(list.get_todo("Tag version").unwrap().parent, None);
+    }
+
+    #[test]
+    fn completing_recurring_todo_keeps_record_and_adds_next() {
+        let mut list = base_list();
+        let due = NaiveDate::from_ymd(2021, 11, 1);
+        list.add_todo("water plants", false).unwrap();
+        list.tag_todo("water plants", "home").unwrap();
+        list.set_due("water plants", Some(due)).unwrap();
+        list.apply_action(ActionPayload::SetRecurrence(s("water plants"), Some(Recurrence::Weekly)))
+            .unwrap();
+
+        list.apply_action(ActionPayload::Complete(vec![s("water plants")])).unwrap();
+
+        let record = list.get_todos_text()[3].clone();
+        let next = list.get_todo("water plants").unwrap();
+        assert_eq!(list.len(), 4);
+        assert!(!next.completed);
+        assert_eq!(next.due, Some(NaiveDate::from_ymd(2021, 11, 8)));
+        assert_eq!(next.tags, vec!["home"]);
+        assert_eq!(next.recurrence, Some(Recurrence::Weekly));
+        assert!(record.starts_with("water plants ("), "{}", record);
+        assert!(list.get_todo(&record).unwrap().completed);
+        assert_eq!(list.get_todo(&record).unwrap().recurrence, None);
+
+        list.apply_action(ActionPayload::Toggle(s("water plants"))).unwrap();
+        assert_eq!(list.len(), 5);
+        assert_ne!(list.get_todos_text()[3], list.get_todos_text()[4]);
+    }
+
+    #[test]
+    fn bump_priority_moves_one_level_and_clamps() {
+        let mut list = base_list();
+        list.set_priority("one", Some(Priority::Low)).unwrap();
+
+        assert_eq!(list.bump_priority("one", true), Some(Priority::Medium));
+        assert_eq!(list.bump_priority("one", true), Some(Priority::High));
+        assert_eq!(list.bump_priority("one", true), Some(Priority::High));
+        assert_eq!(list.bump_priority("one", false), Some(Priority::Medium));
+        assert_eq!(list.bump_priority("one", false), Some(Priority::Low));
+        assert_eq!(list.bump_priority("one", false), Some(Priority::Low));
+
+        assert_eq!(list.bump_priority("two", true), Some(Priority::Low));
+        assert_eq!(list.bump_priority("missing", true), None);
+    }
+
+    #[test]
+    fn conversions_round_trip() {
+        let todos = vec![(s("one"), false), (s("two"), true), (s("one"), true), (s(""), false)];
+        let list = TodoList::from(todos);
+
+        assert_eq!(list.len(), 2);
+        let borrowed: Vec<(&String, &bool)> = (&list).into_iter().collect();
+        assert_eq!(borrowed, vec![(&s("one"), &true), (&s("two"), &true)]);
+
+        let owned: Vec<(String, bool)> = list.into_iter().collect();
+        let rebuilt: TodoList = owned.clone().into_iter().collect();
+        for (text, done) in &rebuilt {
+            assert!(owned.contains(&(text.clone(), *done)));
+        }
+        assert_eq!(rebuilt.into_iter().collect::<Vec<_>>(), owned);
+    }
+
+    #[test]
+    fn is_empty_with_checks_status() {
+        let empty = TodoList::new();
+        assert!(empty.is_empty_with(None));
+        assert!(empty.is_empty_with(Some(true)));
+        assert!(empty.is_empty_with(Some(false)));
+
+        let all_done = TodoListBuilder::new().done("one").done("two").build();
+        assert!(!all_done.is_empty_with(None));
+        assert!(!all_done.is_empty_with(Some(true)));
+        assert!(all_done.is_empty_with(Some(false)));
+
+        let mixed = base_list();
+        assert!(!mixed.is_empty_with(None));
+        assert!(!mixed.is_empty_with(Some(true)));
+        assert!(!mixed.is_empty_with(Some(false)));
+    }
+
+    #[test]
+    fn clone_is_the_trait_clone() {
+        fn duplicate<T: Clone>(value: &T) -> T {
+            value.clone()
+        }
+
+        let mut list = base_list();
+        list.set_assume_yes(true);
+        let copy = duplicate(&list);
+
+        assert_eq!(list.diff_with(&copy), DiffResult::Same);
+        assert!(copy.assume_yes);
+    }
+
+    #[test]
+    fn split_replaces_todo_in_place() {
+        let mut list = tagged_list();
+        let outcome = list
+            .apply_action(ActionPayload::Split(s("one"), vec![s("one a"), s("one b"), s("one c")]))
+            .unwrap();
+
+        assert_eq!(
+            outcome,
+            ActionOutcome::Split(s("one"), vec![s("one a"), s("one b"), s("one c")])
+        );
+        assert!(list.get_todo("one").is_none());
+        assert_eq!(list.get_todos_text(), vec!["one a", "one b", "one c", "two", "three"]);
+        for part in ["one a", "one b", "one c"] {
+            let item = list.get_todo(part).unwrap();
+            assert!(!item.completed);
+            assert_eq!(item.tags, vec![s("work"), s("urgent")]);
+        }
+
+        assert_eq!(list.split_todo("two", &["three"]), Err(CommandError::TodoAlreadyExists));
+        assert_eq!(list.split_todo("two", &["x", "x"]), Err(CommandError::TodoAlreadyExists));
+        assert!(list.get_todo("two").is_some());
+    }
+
+    #[test]
+    fn merge_resolves_conflicts_by_strategy() {
+        let other = TodoListBuilder::new().done("one").todo("two").todo("three").build();
+        let cases = [
+            (MergeStrategy::PreferSelf, false, true),
+            (MergeStrategy::PreferOther, true, false),
+            (MergeStrategy::PreferCompleted, true, true),
+            (MergeStrategy::PreferIncomplete, false, false),
+        ];
+
+        for (strategy, one, two) in cases {
+            let mut list = base_list();
+            let report = list.merge(&other, strategy);
+
+            assert_eq!(report, MergeReport { added: 1, conflicts: 2 }, "{:?}", strategy);
+            assert_eq!(list.get_status("one"), Some(one), "{:?}", strategy);
+            assert_eq!(list.get_status("two"), Some(two), "{:?}", strategy);
+            assert_eq!(list.get_status("three"), Some(false), "{:?}", strategy);
+        }
+    }
+
+    #[test]
+    fn combine_merges_tags_and_status() {
+        let mut list = tagged_list();
+        list.tag_todo("two", "home").unwrap();
+        let outcome = list.apply_action(ActionPayload::Combine(vec![s("two"), s("one")])).unwrap();
+
+        assert_eq!(outcome, ActionOutcome::Combined(vec![s("two"), s("one")], s("two; one")));
+        assert!(list.get_todo("one").is_none());
+        assert!(list.get_todo("two").is_none());
+        assert_eq!(list.get_todos_text(), vec!["two; one", "three"]);
+        let combined = list.get_todo("two; one").unwrap();
+        assert!(!combined.completed);
+        assert_eq!(combined.tags, vec![s("work"), s("home"), s("urgent")]);
+
+        assert!(list.combine_todos(&["three"]).is_err());
+        assert_eq!(list.combine_todos(&["three", "missing"]), Err(CommandError::TodoNotFound));
+    }
+
+    #[test]
+    fn merge_3way_applies_changes_from_both_sides() {
+        let base = base_list();
+        let mut ours = base.clone();
+        ours.add_todo("three", false).unwrap();
+        ours.set_status("two", false).unwrap();
+        let mut theirs = base.clone();
+        theirs.set_status("one", true).unwrap();
+        theirs.set_status("two", false).unwrap();
+
+        let result = TodoList::merge_3way(&base, &ours, &theirs);
+
+        assert!(result.is_clean());
+        assert_eq!(result.merged.get_todos_text(), vec!["one", "two", "three"]);
+        assert_eq!(result.merged.get_status("one"), Some(true));
+        assert_eq!(result.merged.get_status("two"), Some(false));
+        assert_eq!(result.merged.get_status("three"), Some(false));
+    }
+
+    #[test]
+    fn merge_3way_reports_real_conflicts() {
+        let base = base_list();
+        let mut ours = base.clone();
+        ours.remove_todo("two");
+        let mut theirs = base.clone();
+        theirs.set_status("two", false).unwrap();
+
+        let result = TodoList::merge_3way(&base, &ours, &theirs);
+
+        assert_eq!(
+            result.conflicts,
+            vec![MergeConflict {
+                todo: s("two"),
+                base: Some(true),
+                ours: None,
+                theirs: Some(false),
+            }]
+        );
+        assert!(result.merged.get_todo("two").is_none());
+    }
+
+    #[test]
+    fn grep_matches_pattern() {
+        let list = TodoListBuilder::new().todo("buy milk").done("buy bread").todo("walk the dog").todo("rebuy").build();
+
+        assert_eq!(list.grep(&Regex::new("^buy ").unwrap()), vec!["buy milk", "buy bread"]);
+        assert_eq!(list.grep(&Regex::new(r"(?i)DOG$").unwrap()), vec!["walk the dog"]);
+        assert!(list.grep(&Regex::new("cat").unwrap()).is_empty());
+
+        let listing = list.render_listing(&ActionPayload::Grep(s("^buy ")), false).unwrap();
+        assert!(listing.starts_with("Todos matching \"^buy \" (2)\n"), "{}", listing);
+    }
+
+    #[test]
+    fn count_matches_counts_without_listing() {
+        let list = TodoListBuilder::new().todo("buy milk").done("buy bread").todo("walk the dog").todo("buy eggs").build();
+
+        assert_eq!(list.count_matches(&ActionPayload::Grep(s("^buy "))), Ok(3));
+        assert_eq!(list.count_matches(&ActionPayload::Grep(s("cat"))), Ok(0));
+        assert!(list.count_matches(&ActionPayload::Grep(s("(unclosed"))).is_err());
+        assert!(list.count_matches(&ActionPayload::List).is_err());
+    }
+
+    #[test]
+    fn complete_data_prints_matching_todos() {
+        let list = TodoListBuilder::new().todo("buy milk").done("buy bread").todo("walk the dog").build();
+
+        let outcome = list.clone().apply_action(ActionPayload::CompleteData(Some(s("buy")))).unwrap();
+        assert_eq!(outcome, ActionOutcome::Listing(s("buy milk\nbuy bread\n")));
+        assert_eq!(
+            list.render_listing(&ActionPayload::CompleteData(None), false).unwrap(),
+            "buy milk\nbuy bread\nwalk the dog\n"
+        );
+        assert_eq!(
+            ActionType::try_parse_cmd("__complete").unwrap().try_create_payload(&[s("walk")]),
+            Ok(ActionPayload::CompleteData(Some(s("walk"))))
+        );
+        assert!(ActionType::CompleteData.is_hidden());
+    }
+
+    #[test]
+    fn grep_rejects_invalid_pattern() {
+        assert!(matches!(
+            ActionType::Grep.try_create_payload(&[s("(unclosed")]),
+            Err(InputError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            base_list().apply_action(ActionPayload::Grep(s("[z-a]"))),
+            Err(CommandError::InputInvalid(_))
+        ));
+    }
+
+    #[test]
+    fn replace_renames_matching_todos() {
+        let mut list = TodoListBuilder::new().todo("buy milk").done("buy bread").todo("walk the dog").build();
+        let report = list.replace(&Regex::new("^buy (.+)$").unwrap(), "get $1");
+
+        assert_eq!(
+            report.renamed,
+            vec![(s("buy milk"), s("get milk")), (s("buy bread"), s("get bread"))]
+        );
+        assert!(report.conflicts.is_empty());
+        assert_eq!(list.get_todos_text(), vec!["buy milk", "buy bread", "walk the dog"]);
+
+        list.set_assume_yes(true);
+        let outcome = list.apply_action(ActionPayload::Replace(s("^buy (.+)$"), s("get $1"))).unwrap();
+        assert_eq!(outcome, ActionOutcome::Replaced(report));
+        assert_eq!(list.get_todos_text(), vec!["get milk", "get bread", "walk the dog"]);
+        assert_eq!(list.get_status("get bread"), Some(true));
+    }
+
+    #[test]
+    fn replace_reports_duplicates_as_conflicts() {
+        let mut list = TodoListBuilder::new().todo("buy milk").todo("get milk").todo("buy eggs").todo("sell eggs").build();
+        let report = list.replace(&Regex::new("^(buy|sell) ").unwrap(), "get ");
+
+        assert!(report.renamed.is_empty());
+        assert_eq!(
+            report.conflicts,
+            vec![
+                (s("buy milk"), s("get milk")),
+                (s("buy eggs"), s("get eggs")),
+                (s("sell eggs"), s("get eggs")),
+            ]
+        );
+        assert!(matches!(
+            list.apply_action(ActionPayload::Replace(s("^(buy|sell) "), s("get "))),
+            Ok(ActionOutcome::Replaced(_))
+        ));
+        assert_eq!(list.len(), 4);
+        assert!(list.get_todo("buy milk").is_some());
+
+        let mut list = TodoListBuilder::new().todo("Get milk").todo("buy milk").todo("buy bread").build();
+        list.set_case_insensitive_duplicates(true);
+        let report = list.replace(&Regex::new("^buy").unwrap(), "get");
+        assert_eq!(report.renamed, vec![(s("buy bread"), s("get bread"))]);
+        assert_eq!(report.conflicts, vec![(s("buy milk"), s("get milk"))]);
+
+        let report = list.replace(&Regex::new("bread").unwrap(), " ");
+        assert_eq!(report.renamed, vec![(s("buy bread"), s("buy"))]);
+    }
+
+    #[test]
+    fn list_by_due_puts_undated_last() {
+        let mut list = TodoListBuilder::new().todo("undated").todo("later").done("sooner").build();
+        list.set_due("later", Some(NaiveDate::from_ymd(2021, 11, 1))).unwrap();
+        list.set_due("sooner", Some(NaiveDate::from_ymd(2021, 10, 1))).unwrap();
+
+        let order: Vec<&String> = list.todos_by_due().into_iter().map(|(todo, _)| todo).collect();
+        assert_eq!(order, vec!["sooner", "later", "undated"]);
+        assert_eq!(
+            list.render_listing(&ActionPayload::ListByDue, false).unwrap(),
+            "Todos by Due Date (3)\n\t[X] 2021-10-01 \"sooner\"\n\t[ ] 2021-11-01 \"later\"\n\t[ ] -          \"undated\"\n"
+        );
+    }
+
+    #[test]
+    fn dates_render_in_the_date_format() {
+        let mut list = base_list();
+        list.set_due("one", Some(NaiveDate::from_ymd(2021, 10, 3))).unwrap();
+
+        assert!(list.render_todo("one", false).unwrap().contains("Due: 2021-10-03\n"));
+        list.set_date_format("%d/%m/%Y");
+        assert!(list.render_todo("one", false).unwrap().contains("Due: 03/10/2021\n"));
+        list.set_date_format("%b %-d, %Y");
+        assert!(list.render_todo("one", false).unwrap().contains("Due: Oct 3, 2021\n"));
+    }
+
+    #[test]
+    fn saving_without_a_format_keeps_the_encoding_on_disk() {
+        let dir = crate::utils::testing::temp_dir("keep-encoding");
+        let mut list = base_list();
+        list.save_to_profile_as(&dir, DEFAULT_PROFILE, EncodingType::Json).unwrap();
+
+        list.add_todo("three", false).unwrap();
+        list.save_to_disk(&dir, false, None).unwrap();
+        list.save_to_profile(&dir, DEFAULT_PROFILE).unwrap();
+
+        assert_eq!(Profile::encoding_of(&dir, DEFAULT_PROFILE), EncodingType::Json);
+        assert!(!Profile::path_for_encoding(&dir, DEFAULT_PROFILE, DEFAULT_ENCODING).is_file());
+        assert_eq!(TodoList::load_from_disk(&dir).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn encrypted_profile_round_trips() {
+        let dir = crate::utils::testing::temp_dir("encrypted-profile");
+        let mut list = base_list();
+        list.set_passphrase(Some(s("correct horse")));
+        list.save_to_profile(&dir, "secret").unwrap();
+
+        let bytes = std::fs::read(Profile::path_for(&dir, "secret")).unwrap();
+        assert!(Crypto::is_encrypted(&bytes));
+        assert!(TodoList::load_from_profile(&dir, "secret").unwrap_err().contains("passphrase is needed"));
+        assert_eq!(
+            TodoList::load_from_profile_with(&dir, "secret", Some("battery staple")).unwrap_err(),
+            crate::utils::crypto::DECRYPTION_FAILED
+        );
+
+        let loaded = TodoList::load_from_profile_with(&dir, "secret", Some("correct horse")).unwrap();
+        assert_eq!(loaded.diff_with(&list), DiffResult::Same);
+    }
+
+    #[test]
+    fn changed_since_only_returns_newer_todos() {
+        let at = |day: u32| Some(DateTime::<Utc>::from_utc(NaiveDate::from_ymd(2021, 10, day).and_hms(12, 0, 0), Utc));
+        let last_opened = at(10).unwrap();
+        let mut list = TodoList::new();
+        list.map.insert(s("old"), Todo { created_at: at(1), ..Todo::default() });
+        list.map.insert(s("new"), Todo { created_at: at(11), ..Todo::default() });
+        list.map.insert(s("finished"), Todo { completed: true, created_at: at(2), completed_at: at(12), ..Todo::default() });
+        list.map.insert(s("legacy"), Todo::new(false));
+
+        assert_eq!(list.changed_since(last_opened), vec!["new", "finished"]);
+        assert!(list.changed_since(at(12).unwrap()).is_empty());
     }
 }
@@ -0,0 +1,130 @@
+use super::{command_error::CommandError, todolist::TodoList};
+
+/// What a [TodoListBuilder] does when a todo cannot be added, usually because a todo with
+/// the same text was already added.
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub enum DuplicateMode {
+    /// Panic straight away, naming the offending todo. Handy in tests.
+    Panic,
+    /// Skip the todo and keep the error, see [`TodoListBuilder::build_with_errors`].
+    Collect,
+}
+
+/// A fluent way of putting together a [TodoList], mostly for tests and other programmatic
+/// use. Todos keep the order they are given in.
+///
+/// ```
+/// use todo::todos::todolist_builder::TodoListBuilder;
+///
+/// let list = TodoListBuilder::new().todo("buy milk").done("walk the dog").build();
+///
+/// assert_eq!(list.len(), 2);
+/// assert_eq!(list.get_status("buy milk"), Some(false));
+/// assert_eq!(list.get_status("walk the dog"), Some(true));
+/// ```
+///
+/// By default duplicates panic, [`TodoListBuilder::collecting`] keeps the errors instead:
+///
+/// ```
+/// use todo::todos::{command_error::CommandError, todolist_builder::TodoListBuilder};
+///
+/// let (list, errors) = TodoListBuilder::collecting()
+///     .todo("buy milk")
+///     .done("buy milk")
+///     .build_with_errors();
+///
+/// assert_eq!(list.get_status("buy milk"), Some(false));
+/// assert_eq!(errors, vec![("buy milk".to_string(), CommandError::TodoAlreadyExists)]);
+/// ```
+#[derive(Debug)]
+pub struct TodoListBuilder {
+    list: TodoList,
+    mode: DuplicateMode,
+    errors: Vec<(String, CommandError)>,
+}
+
+impl TodoListBuilder {
+    /// Creates a builder that panics on duplicate or empty todos.
+    pub fn new() -> Self {
+        Self::with_mode(DuplicateMode::Panic)
+    }
+
+    /// Creates a builder that skips duplicate or empty todos and keeps the errors.
+    pub fn collecting() -> Self {
+        Self::with_mode(DuplicateMode::Collect)
+    }
+
+    pub fn with_mode(mode: DuplicateMode) -> Self {
+        Self {
+            list: TodoList::new(),
+            mode,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Adds an incomplete todo.
+    pub fn todo<S: AsRef<str>>(self, todo: S) -> Self {
+        self.with_status(todo, false)
+    }
+
+    /// Adds a completed todo.
+    pub fn done<S: AsRef<str>>(self, todo: S) -> Self {
+        self.with_status(todo, true)
+    }
+
+    /// Adds a todo with the status `status`.
+    pub fn with_status<S: AsRef<str>>(mut self, todo: S, status: bool) -> Self {
+        if let Err(err) = self.list.add_todo(todo.as_ref(), status) {
+            match self.mode {
+                DuplicateMode::Panic => panic!("Unable to add todo {:?}: {}", todo.as_ref(), err),
+                DuplicateMode::Collect => self.errors.push((todo.as_ref().to_string(), err)),
+            }
+        }
+
+        self
+    }
+
+    /// Returns the finished list, dropping any collected errors.
+    pub fn build(self) -> TodoList {
+        self.list
+    }
+
+    /// Returns the finished list along with every todo that could not be added and why.
+    pub fn build_with_errors(self) -> (TodoList, Vec<(String, CommandError)>) {
+        (self.list, self.errors)
+    }
+}
+
+impl Default for TodoListBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_in_order() {
+        let list = TodoListBuilder::new().todo("one").done("two").todo("three").build();
+
+        assert_eq!(list.get_todos_text(), vec!["one", "two", "three"]);
+        assert_eq!(list.get_todos_with_status(true), vec!["two"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "\"one\"")]
+    fn panics_on_duplicate() {
+        TodoListBuilder::new().todo("one").done("one");
+    }
+
+    #[test]
+    fn collects_errors() {
+        let (list, errors) = TodoListBuilder::collecting().todo("one").done("one").todo("").build_with_errors();
+
+        assert_eq!(list.len(), 1);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0], ("one".to_string(), CommandError::TodoAlreadyExists));
+    }
+}
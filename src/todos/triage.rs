@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+use crate::state::actions::action_payload::ActionPayload;
+
+/// What to do with a todo while triaging, see [`TriageChoice::action_for`].
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Serialize, Deserialize, Clone)]
+pub enum TriageChoice {
+    Complete,
+    Delete,
+    Skip,
+    /// Tag the todo with the tag given.
+    Tag(String),
+}
+
+impl TriageChoice {
+    /// The choices offered for each todo, in the order they are shown. The tag of
+    /// [TriageChoice::Tag] is asked for once it is chosen, so it is empty here.
+    pub fn all() -> Vec<TriageChoice> {
+        vec![
+            TriageChoice::Complete,
+            TriageChoice::Delete,
+            TriageChoice::Skip,
+            TriageChoice::Tag(String::new()),
+        ]
+    }
+
+    /// Returns the action that carries out this choice for `todo`, or `None` if the todo
+    /// should be left as it is.
+    pub fn action_for<S: Into<String>>(&self, todo: S) -> Option<ActionPayload> {
+        match self {
+            TriageChoice::Complete => Some(ActionPayload::Set(todo.into(), true)),
+            TriageChoice::Delete => Some(ActionPayload::Remove(todo.into())),
+            TriageChoice::Skip => None,
+            TriageChoice::Tag(tag) if tag.is_empty() => None,
+            TriageChoice::Tag(tag) => Some(ActionPayload::Tag(todo.into(), tag.clone())),
+        }
+    }
+}
+
+impl std::fmt::Display for TriageChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TriageChoice::Complete => write!(f, "Complete"),
+            TriageChoice::Delete => write!(f, "Delete"),
+            TriageChoice::Skip => write!(f, "Skip"),
+            TriageChoice::Tag(_) => write!(f, "Tag"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todos::todolist_builder::TodoListBuilder;
+
+    #[test]
+    fn choices_map_to_state_changes() {
+        let list = TodoListBuilder::new().todo("buy milk").build();
+        let apply = |choice: TriageChoice| {
+            let mut list = list.clone();
+            if let Some(action) = choice.action_for("buy milk") {
+                list.apply_action(action).unwrap();
+            }
+            list.get_todo("buy milk").cloned()
+        };
+        let untouched = list.get_todo("buy milk").cloned();
+
+        assert!(apply(TriageChoice::Complete).unwrap().completed);
+        assert_eq!(apply(TriageChoice::Delete), None);
+        assert_eq!(apply(TriageChoice::Skip), untouched);
+        assert_eq!(apply(TriageChoice::Tag(String::new())), untouched);
+        assert_eq!(apply(TriageChoice::Tag("errand".to_string())).unwrap().tags, vec!["errand"]);
+    }
+}
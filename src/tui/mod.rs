@@ -0,0 +1,2 @@
+pub mod tui_state;
+pub mod tui_view;
@@ -0,0 +1,169 @@
+use crate::{state::actions::action_payload::ActionPayload, todos::todolist::TodoList};
+
+/// A key pressed in the TUI, independent of the terminal library so that [TuiState] can
+/// be tested without a terminal.
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub enum TuiKey {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Enter,
+    Delete,
+    Esc,
+    Char(char),
+    Other,
+}
+
+/// What the TUI should do after a key press, see [`TuiState::on_key`].
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Clone)]
+pub enum TuiAction {
+    /// The key does nothing.
+    Nothing,
+    /// The view changed, for example the selection moved, and should be drawn again.
+    Redraw,
+    /// Apply the action to the list, then [`TuiState::refresh`] and draw again.
+    Apply(ActionPayload),
+    /// Leave the TUI.
+    Quit,
+}
+
+/// The state of the TUI list view: the todos shown, which one is selected and which part of
+/// the list fits on screen. Key presses are turned into [TuiAction]s by
+/// [`TuiState::on_key`], which never touches the list itself.
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Clone, Default)]
+pub struct TuiState {
+    rows: Vec<(String, bool)>,
+    selected: usize,
+    offset: usize,
+    height: usize,
+}
+
+impl TuiState {
+    /// Creates the state for `list` in a pane `height` rows tall.
+    pub fn new(list: &TodoList, height: usize) -> Self {
+        let mut state = Self {
+            height: height.max(1),
+            ..Self::default()
+        };
+        state.refresh(list);
+        state
+    }
+
+    /// Reloads the rows from `list` after it changed, keeping the selection in range.
+    pub fn refresh(&mut self, list: &TodoList) {
+        self.rows = list
+            .get_todos_text()
+            .into_iter()
+            .map(|todo| (todo.clone(), list.get_status(todo).unwrap_or_default()))
+            .collect();
+        self.select(self.selected);
+    }
+
+    /// Sets the number of rows in the pane, for example after the terminal is resized.
+    pub fn set_height(&mut self, height: usize) {
+        self.height = height.max(1);
+        self.select(self.selected);
+    }
+
+    /// Returns the text of the selected todo, if there are any todos.
+    pub fn selected_todo(&self) -> Option<&str> {
+        self.rows.get(self.selected).map(|(todo, _)| todo.as_str())
+    }
+
+    /// Returns the rows that fit in the pane as `(index, text, completed)`, top first.
+    pub fn visible(&self) -> Vec<(usize, &str, bool)> {
+        self.rows
+            .iter()
+            .enumerate()
+            .skip(self.offset)
+            .take(self.height)
+            .map(|(idx, (todo, completed))| (idx, todo.as_str(), *completed))
+            .collect()
+    }
+
+    /// Returns the index of the selected row.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Works out what pressing `key` does. Moving the selection is handled here, anything
+    /// that changes the list is returned as [`TuiAction::Apply`] for the caller to apply.
+    ///
+    /// - Up / `k`, Down / `j`, Page Up, Page Down, Home / `g` and End / `G` move the selection.
+    /// - Enter, space and `x` toggle the selected todo.
+    /// - Delete and `d` remove the selected todo.
+    /// - Esc and `q` quit.
+    pub fn on_key(&mut self, key: TuiKey) -> TuiAction {
+        let last = self.rows.len().saturating_sub(1);
+        let target = match key {
+            TuiKey::Esc | TuiKey::Char('q') => return TuiAction::Quit,
+            TuiKey::Enter | TuiKey::Char(' ') | TuiKey::Char('x') => {
+                return self.act_on_selected(|todo| ActionPayload::Toggle(todo.to_string()))
+            }
+            TuiKey::Delete | TuiKey::Char('d') => {
+                return self.act_on_selected(|todo| ActionPayload::Remove(todo.to_string()))
+            }
+            TuiKey::Up | TuiKey::Char('k') => self.selected.saturating_sub(1),
+            TuiKey::Down | TuiKey::Char('j') => (self.selected + 1).min(last),
+            TuiKey::PageUp => self.selected.saturating_sub(self.height),
+            TuiKey::PageDown => (self.selected + self.height).min(last),
+            TuiKey::Home | TuiKey::Char('g') => 0,
+            TuiKey::End | TuiKey::Char('G') => last,
+            TuiKey::Char(_) | TuiKey::Other => return TuiAction::Nothing,
+        };
+
+        if target == self.selected {
+            return TuiAction::Nothing;
+        }
+        self.select(target);
+        TuiAction::Redraw
+    }
+
+    fn act_on_selected<F: FnOnce(&str) -> ActionPayload>(&self, action: F) -> TuiAction {
+        match self.selected_todo() {
+            Some(todo) => TuiAction::Apply(action(todo)),
+            None => TuiAction::Nothing,
+        }
+    }
+
+    /// Selects the row `idx`, clamped to the rows there are, and scrolls it into view.
+    fn select(&mut self, idx: usize) {
+        self.selected = idx.min(self.rows.len().saturating_sub(1));
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + self.height {
+            self.offset = self.selected + 1 - self.height;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todos::todolist_builder::TodoListBuilder;
+
+    #[test]
+    fn keys_map_to_actions() {
+        let list = TodoListBuilder::new().todo("one").done("two").todo("three").todo("four").build();
+        let mut state = TuiState::new(&list, 2);
+
+        assert_eq!(state.on_key(TuiKey::Up), TuiAction::Nothing);
+        assert_eq!(state.on_key(TuiKey::Char('j')), TuiAction::Redraw);
+        assert_eq!(state.on_key(TuiKey::Enter), TuiAction::Apply(ActionPayload::Toggle("two".to_string())));
+        assert_eq!(state.on_key(TuiKey::Char('d')), TuiAction::Apply(ActionPayload::Remove("two".to_string())));
+        assert_eq!(state.on_key(TuiKey::End), TuiAction::Redraw);
+        assert_eq!(state.selected_todo(), Some("four"));
+        assert_eq!(state.visible(), vec![(2, "three", false), (3, "four", false)]);
+        assert_eq!(state.on_key(TuiKey::Char('g')), TuiAction::Redraw);
+        assert_eq!(state.visible(), vec![(0, "one", false), (1, "two", true)]);
+        assert_eq!(state.on_key(TuiKey::Char('z')), TuiAction::Nothing);
+        assert_eq!(state.on_key(TuiKey::Char('q')), TuiAction::Quit);
+        assert_eq!(state.on_key(TuiKey::Esc), TuiAction::Quit);
+
+        let mut empty = TuiState::new(&TodoList::new(), 2);
+        assert_eq!(empty.on_key(TuiKey::Enter), TuiAction::Nothing);
+    }
+}
@@ -0,0 +1,124 @@
+use std::io::{self, Stdout, Write};
+
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode},
+    queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+use super::tui_state::{TuiAction, TuiKey, TuiState};
+use crate::todos::cached_todolist::CachedTodoList;
+
+/// The rows taken by the title and the status line, which the list pane does not get.
+const CHROME_ROWS: u16 = 2;
+
+/// Stateless struct used to group the functions drawing the full-screen TUI list view.
+pub struct TuiView;
+
+impl TuiView {
+    /// Shows `list` in a scrollable full-screen pane until the user quits, applying the
+    /// changes they make through [`CachedTodoList::apply_action`]. The terminal is restored
+    /// even if drawing fails. Saving is left to the caller, see [`CachedTodoList::flush`].
+    pub fn run(list: &mut CachedTodoList) -> io::Result<()> {
+        let mut stdout = io::stdout();
+        terminal::enable_raw_mode()?;
+        queue!(stdout, EnterAlternateScreen, Hide)?;
+
+        let result = TuiView::event_loop(&mut stdout, list);
+
+        queue!(stdout, Show, LeaveAlternateScreen)?;
+        stdout.flush()?;
+        terminal::disable_raw_mode()?;
+        result
+    }
+
+    fn event_loop(stdout: &mut Stdout, list: &mut CachedTodoList) -> io::Result<()> {
+        let (_, rows) = terminal::size()?;
+        let mut state = match list.list() {
+            Ok(todos) => TuiState::new(&todos, rows.saturating_sub(CHROME_ROWS) as usize),
+            Err(err) => return Err(io::Error::new(io::ErrorKind::Other, err)),
+        };
+        let mut status = String::from("j/k move, enter toggle, d delete, q quit");
+
+        loop {
+            TuiView::draw(stdout, &state, &status)?;
+            let key = match event::read()? {
+                Event::Key(key) => TuiView::key(key.code),
+                Event::Resize(_, rows) => {
+                    state.set_height(rows.saturating_sub(CHROME_ROWS) as usize);
+                    continue;
+                }
+                _ => continue,
+            };
+
+            match state.on_key(key) {
+                TuiAction::Nothing | TuiAction::Redraw => {}
+                TuiAction::Quit => return Ok(()),
+                TuiAction::Apply(action) => {
+                    status = match list.apply_action(action) {
+                        Ok(outcome) => outcome.to_string(),
+                        Err(err) => err.to_string(),
+                    };
+                    if let Ok(todos) = list.list() {
+                        state.refresh(&todos);
+                    }
+                }
+            }
+        }
+    }
+
+    fn draw(stdout: &mut Stdout, state: &TuiState, status: &str) -> io::Result<()> {
+        let (columns, rows) = terminal::size()?;
+        let width = columns as usize;
+        queue!(
+            stdout,
+            Clear(ClearType::All),
+            MoveTo(0, 0),
+            SetAttribute(Attribute::Bold),
+            Print(fit("Todos", width)),
+            SetAttribute(Attribute::Reset)
+        )?;
+
+        for (row, (idx, todo, completed)) in state.visible().into_iter().enumerate() {
+            let line = format!("{} {}", if completed { "[X]" } else { "[ ]" }, todo);
+            queue!(stdout, MoveTo(0, row as u16 + 1))?;
+            if idx == state.selected() {
+                queue!(
+                    stdout,
+                    SetAttribute(Attribute::Reverse),
+                    Print(fit(&line, width)),
+                    SetAttribute(Attribute::Reset)
+                )?;
+            } else {
+                queue!(stdout, Print(fit(&line, width)))?;
+            }
+        }
+
+        let status = status.lines().next().unwrap_or_default();
+        queue!(stdout, MoveTo(0, rows.saturating_sub(1)), Print(fit(status, width)))?;
+        stdout.flush()
+    }
+
+    fn key(code: KeyCode) -> TuiKey {
+        match code {
+            KeyCode::Up => TuiKey::Up,
+            KeyCode::Down => TuiKey::Down,
+            KeyCode::PageUp => TuiKey::PageUp,
+            KeyCode::PageDown => TuiKey::PageDown,
+            KeyCode::Home => TuiKey::Home,
+            KeyCode::End => TuiKey::End,
+            KeyCode::Enter => TuiKey::Enter,
+            KeyCode::Delete => TuiKey::Delete,
+            KeyCode::Esc => TuiKey::Esc,
+            KeyCode::Char(c) => TuiKey::Char(c),
+            _ => TuiKey::Other,
+        }
+    }
+}
+
+/// Cuts `text` down to `width` characters so that it never wraps onto the next row.
+fn fit(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
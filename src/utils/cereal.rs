@@ -1,3 +1,6 @@
+use std::io::{Read, Write};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 use rmp_serde as rmps;
 use rmps::{Deserializer, Serializer};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
@@ -10,11 +13,36 @@ pub enum EncodingType {
     Bson,
     MsgPack,
     FlexBuffer,
+    Ron,
 }
 
 impl EncodingType {
-    pub fn all() -> [EncodingType; 5] {
-        [EncodingType::Bson, EncodingType::Cbor, EncodingType::FlexBuffer, EncodingType::Json, EncodingType::MsgPack]
+    pub fn all() -> [EncodingType; 6] {
+        [EncodingType::Bson, EncodingType::Cbor, EncodingType::FlexBuffer, EncodingType::Json, EncodingType::MsgPack, EncodingType::Ron]
+    }
+
+    /// Returns the [EncodingType] with the given name or file extension, ignoring case, if
+    /// any. Both `MsgPack` and `msgpack` name [EncodingType::MsgPack].
+    pub fn from_name<S: AsRef<str>>(name: S) -> Option<EncodingType> {
+        EncodingType::all()
+            .into_iter()
+            .find(|ty| ty.to_string().eq_ignore_ascii_case(name.as_ref()))
+            .or_else(|| EncodingType::from_file_ext(name))
+    }
+
+    /// Returns the [EncodingType] that uses the given file extension, if any.
+    pub fn from_file_ext<S: AsRef<str>>(ext: S) -> Option<EncodingType> {
+        EncodingType::all()
+            .into_iter()
+            .find(|ty| ty.get_file_ext().eq_ignore_ascii_case(ext.as_ref()))
+    }
+
+    /// Returns `true` if this encoding produces text that a person can reasonably read.
+    pub fn is_human_readable(&self) -> bool {
+        match self {
+            EncodingType::Json | EncodingType::Ron => true,
+            EncodingType::Cbor | EncodingType::Bson | EncodingType::MsgPack | EncodingType::FlexBuffer => false,
+        }
     }
 
     pub fn get_file_ext(&self) -> &'static str {
@@ -24,12 +52,23 @@ impl EncodingType {
             EncodingType::Bson => "bson",
             EncodingType::MsgPack => "msgpack",
             EncodingType::FlexBuffer => "flex",
+            EncodingType::Ron => "ron",
         }
     }
+
+    /// Returns the file extension of this encoding once compressed with
+    /// [`Cereal::serialize_compressed`], for example `msgpack.gz`.
+    pub fn get_compressed_file_ext(&self) -> String {
+        format!("{}.{}", self.get_file_ext(), COMPRESSED_FILE_EXT)
+    }
 }
 
 pub const GLOBAL_ENCODING: EncodingType = EncodingType::Json;
 
+/// The extension appended to the file extension of an [EncodingType] when its output is
+/// gzip compressed.
+pub const COMPRESSED_FILE_EXT: &str = "gz";
+
 pub struct Cereal;
 
 impl Cereal {
@@ -40,16 +79,18 @@ impl Cereal {
             EncodingType::MsgPack => Cereal::serialize_msgpack(data).map_err(|e| e.to_string()),
             EncodingType::FlexBuffer => Cereal::serialize_flex(data).map_err(|e| e.to_string()),
             EncodingType::Bson => Cereal::serialize_bson(data).map_err(|e| e.to_string()),
+            EncodingType::Ron => Cereal::serialize_ron(data).map_err(|e| e.to_string()),
         }
     }
 
-    pub fn deserialize<TOutput: DeserializeOwned>(bytes: &Vec<u8>) -> Result<TOutput, String> {
+    pub fn deserialize<TOutput: DeserializeOwned>(bytes: &[u8]) -> Result<TOutput, String> {
         match GLOBAL_ENCODING {
             EncodingType::Json => Cereal::deserialize_json(bytes).map_err(|e| e.to_string()),
             EncodingType::Cbor => Cereal::deserialize_cbor(bytes).map_err(|e| e.to_string()),
             EncodingType::MsgPack => Cereal::deserialize_msgpack(bytes).map_err(|e| e.to_string()),
             EncodingType::FlexBuffer => Cereal::deserialize_flex(bytes).map_err(|e| e.to_string()),
             EncodingType::Bson => Cereal::deserialize_bson(bytes).map_err(|e| e.to_string()),
+            EncodingType::Ron => Cereal::deserialize_ron(bytes).map_err(|e| e.to_string()),
         }
     }
 
@@ -60,25 +101,54 @@ impl Cereal {
             EncodingType::MsgPack => Cereal::serialize_msgpack(data).map_err(|e| e.to_string()),
             EncodingType::FlexBuffer => Cereal::serialize_flex(data).map_err(|e| e.to_string()),
             EncodingType::Bson => Cereal::serialize_bson(data).map_err(|e| e.to_string()),
+            EncodingType::Ron => Cereal::serialize_ron(data).map_err(|e| e.to_string()),
         }
     }
 
-    pub fn deserialize_with<TOutput: DeserializeOwned>(encoding: EncodingType, bytes: &Vec<u8>) -> Result<TOutput, String> {
+    pub fn deserialize_with<TOutput: DeserializeOwned>(encoding: EncodingType, bytes: &[u8]) -> Result<TOutput, String> {
         match encoding {
             EncodingType::Json => Cereal::deserialize_json(bytes).map_err(|e| e.to_string()),
             EncodingType::Cbor => Cereal::deserialize_cbor(bytes).map_err(|e| e.to_string()),
             EncodingType::MsgPack => Cereal::deserialize_msgpack(bytes).map_err(|e| e.to_string()),
             EncodingType::FlexBuffer => Cereal::deserialize_flex(bytes).map_err(|e| e.to_string()),
             EncodingType::Bson => Cereal::deserialize_bson(bytes).map_err(|e| e.to_string()),
+            EncodingType::Ron => Cereal::deserialize_ron(bytes).map_err(|e| e.to_string()),
         }
     }
 
+    /// Serializes `data` with `encoding` and gzip compresses the output.
+    pub fn serialize_compressed<TData: Serialize>(encoding: EncodingType, data: &TData) -> Result<Vec<u8>, String> {
+        Cereal::serialize_with(encoding, data).and_then(|bytes| Cereal::compress(&bytes).map_err(|e| e.to_string()))
+    }
+
+    /// Decompresses `bytes` written by [`Cereal::serialize_compressed`] and deserializes
+    /// them with `encoding`.
+    pub fn deserialize_compressed<TOutput: DeserializeOwned>(encoding: EncodingType, bytes: &[u8]) -> Result<TOutput, String> {
+        Cereal::decompress(bytes)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| Cereal::deserialize_with(encoding, &bytes))
+    }
+
+    /// Gzip compresses `bytes`.
+    pub fn compress(bytes: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(bytes)?;
+        encoder.finish()
+    }
+
+    /// Decompresses gzip compressed `bytes`.
+    pub fn decompress(bytes: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        let mut decoded = Vec::new();
+        GzDecoder::new(bytes).read_to_end(&mut decoded)?;
+        Ok(decoded)
+    }
+
     pub fn serialize_json<TData: Serialize>(data: &TData) -> Result<Vec<u8>, serde_json::Error> {
         serde_json::to_vec(data)
     }
 
     pub fn deserialize_json<TOutput: DeserializeOwned>(
-        bytes: &Vec<u8>,
+        bytes: &[u8],
     ) -> Result<TOutput, serde_json::Error> {
         serde_json::from_slice(bytes)
     }
@@ -90,7 +160,7 @@ impl Cereal {
     }
 
     pub fn deserialize_msgpack<TOutput: DeserializeOwned>(
-        bytes: &Vec<u8>,
+        bytes: &[u8],
     ) -> Result<TOutput, rmps::decode::Error> {
         rmps::from_read_ref(bytes)
     }
@@ -102,7 +172,7 @@ impl Cereal {
     }
 
     pub fn deserialize_flex<TOutput: DeserializeOwned>(
-        bytes: &Vec<u8>,
+        bytes: &[u8],
     ) -> Result<TOutput, flexbuffers::DeserializationError> {
         flexbuffers::from_slice(bytes)
     }
@@ -114,7 +184,7 @@ impl Cereal {
     }
 
     pub fn deserialize_bson<TOutput: DeserializeOwned>(
-        bytes: &Vec<u8>,
+        bytes: &[u8],
     ) -> Result<TOutput, bson::de::Error> {
         bson::from_slice(bytes)
     }
@@ -126,11 +196,23 @@ impl Cereal {
     }
 
     pub fn deserialize_cbor<TOutput: DeserializeOwned>(
-        bytes: &Vec<u8>,
+        bytes: &[u8],
     ) -> Result<TOutput, serde_cbor::Error> {
         serde_cbor::from_slice(bytes)
     }
 
+    pub fn serialize_ron<TData: Serialize>(
+        data: &TData,
+    ) -> Result<Vec<u8>, ron::Error> {
+        ron::ser::to_string_pretty(data, ron::ser::PrettyConfig::default()).map(String::into_bytes)
+    }
+
+    pub fn deserialize_ron<TOutput: DeserializeOwned>(
+        bytes: &[u8],
+    ) -> Result<TOutput, ron::Error> {
+        ron::de::from_bytes(bytes)
+    }
+
     // pub fn deserialize_with<'Caller, TOutput: Deserialize<'Caller>>(encoding: EncodingType, bytes: &'Caller Vec<u8>) -> Result<TOutput, String> {
     //     match encoding {
     //         EncodingType::Json => Cereal::deserialize_json(bytes).map_err(|e| e.to_string()),
@@ -199,3 +281,35 @@ impl Cereal {
     //     serde_cbor::from_slice(bytes)
     // }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Sample {
+        name: String,
+        done: bool,
+    }
+
+    #[test]
+    fn ron_round_trip() {
+        let sample = Sample { name: "buy milk".to_string(), done: true };
+
+        let bytes = Cereal::serialize_with(EncodingType::Ron, &sample).unwrap();
+        let recreated: Sample = Cereal::deserialize_with(EncodingType::Ron, &bytes).unwrap();
+
+        assert_eq!(sample, recreated);
+    }
+
+    #[test]
+    fn ron_output_is_readable() {
+        let sample = Sample { name: "buy milk".to_string(), done: false };
+
+        let text = String::from_utf8(Cereal::serialize_ron(&sample).unwrap()).unwrap();
+
+        assert!(EncodingType::Ron.is_human_readable());
+        assert!(text.contains("name"), "{}", text);
+        assert!(text.contains("done"), "{}", text);
+    }
+}
@@ -0,0 +1,52 @@
+//! The color policy shared by everything that prints colored output. Output is only
+//! colored when stdout is a terminal and [NO_COLOR_ENV] is not set, so redirecting output
+//! to a file or another program always gives plain text.
+
+use std::fmt::Display;
+
+use owo_colors::{OwoColorize, Style};
+
+/// The environment variable that turns color off when it is set to anything but an empty
+/// string, see <https://no-color.org>.
+pub const NO_COLOR_ENV: &str = "NO_COLOR";
+
+/// Returns `true` if output should be colored, which is when stdout is a terminal and
+/// [NO_COLOR_ENV] is not set.
+pub fn should_color() -> bool {
+    let no_color_env = std::env::var(NO_COLOR_ENV).ok();
+    should_color_with(no_color_env.as_deref(), atty::is(atty::Stream::Stdout))
+}
+
+/// Returns `true` if output should be colored given the value of [NO_COLOR_ENV], if it is
+/// set, and whether stdout is a terminal.
+pub fn should_color_with(no_color_env: Option<&str>, is_tty: bool) -> bool {
+    is_tty && no_color_env.map_or(true, |value| value.is_empty())
+}
+
+/// Renders `value` in `style` if `colorize` is `true`, or as plain text otherwise. Pad
+/// `value` before painting it, padding a painted string counts its escape codes.
+pub fn paint<T: Display>(value: T, style: Style, colorize: bool) -> String {
+    if colorize {
+        value.style(style).to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_only_for_terminals_without_no_color() {
+        assert!(should_color_with(None, true));
+        assert!(should_color_with(Some(""), true));
+        assert!(!should_color_with(Some("1"), true));
+        // Piping or redirecting to a file is never colored.
+        assert!(!should_color_with(None, false));
+
+        let style = Style::new().red();
+        assert_eq!(paint("text", style, false), "text");
+        assert_ne!(paint("text", style, true), "text");
+    }
+}
@@ -0,0 +1,133 @@
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{Aead, NewAead},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use rand::RngCore;
+
+/// The bytes every encrypted file starts with, used to tell them apart from plain ones.
+pub const ENCRYPTED_MAGIC: &[u8; 8] = b"TODOENC1";
+/// The length in bytes of the keys used by [Crypto].
+pub const KEY_LEN: usize = 32;
+pub const SALT_LEN: usize = 16;
+pub const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = ENCRYPTED_MAGIC.len() + SALT_LEN + NONCE_LEN;
+
+/// The salt, nonce and ciphertext of encrypted data, see [`Crypto::split`].
+type Parts<'a> = (&'a [u8], &'a [u8], &'a [u8]);
+
+/// The error returned when encrypted bytes cannot be decrypted, which is almost always
+/// because the passphrase is wrong.
+pub const DECRYPTION_FAILED: &str = "Unable to decrypt, the passphrase is wrong or the file is damaged";
+
+/// Stateless struct used to group the encryption functions. Data is encrypted with
+/// ChaCha20-Poly1305 using a key derived from a passphrase with Argon2. Encrypted bytes
+/// are laid out as [ENCRYPTED_MAGIC], the salt, the nonce and then the ciphertext.
+pub struct Crypto;
+
+impl Crypto {
+    /// Returns `true` if `bytes` were written by [`Crypto::encrypt`].
+    pub fn is_encrypted(bytes: &[u8]) -> bool {
+        bytes.starts_with(ENCRYPTED_MAGIC)
+    }
+
+    /// Derives a key from `passphrase` and `salt` with Argon2.
+    pub fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; KEY_LEN], String> {
+        let mut key = [0u8; KEY_LEN];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|err| format!("Unable to derive key from passphrase: {}", err))?;
+        Ok(key)
+    }
+
+    /// Encrypts `plaintext` with a key derived from `passphrase` and a random salt.
+    pub fn encrypt(passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut salt);
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let key = Crypto::derive_key(passphrase, &salt)?;
+        Crypto::encrypt_with_key(&key, &salt, &nonce, plaintext)
+    }
+
+    /// Decrypts `bytes` written by [`Crypto::encrypt`] with the same `passphrase`.
+    pub fn decrypt(passphrase: &str, bytes: &[u8]) -> Result<Vec<u8>, String> {
+        let (salt, _, _) = Crypto::split(bytes)?;
+        let key = Crypto::derive_key(passphrase, salt)?;
+        Crypto::decrypt_with_key(&key, bytes)
+    }
+
+    /// Encrypts `plaintext` with `key`, recording `salt` so that the key can be derived
+    /// again from the passphrase it came from.
+    pub fn encrypt_with_key(
+        key: &[u8; KEY_LEN],
+        salt: &[u8; SALT_LEN],
+        nonce: &[u8; NONCE_LEN],
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, String> {
+        let ciphertext = ChaCha20Poly1305::new(Key::from_slice(key))
+            .encrypt(Nonce::from_slice(nonce), plaintext)
+            .map_err(|_| "Unable to encrypt".to_string())?;
+
+        let mut output = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+        output.extend_from_slice(ENCRYPTED_MAGIC);
+        output.extend_from_slice(salt);
+        output.extend_from_slice(nonce);
+        output.extend_from_slice(&ciphertext);
+        Ok(output)
+    }
+
+    /// Decrypts `bytes` written by [`Crypto::encrypt_with_key`] with the same `key`.
+    pub fn decrypt_with_key(key: &[u8; KEY_LEN], bytes: &[u8]) -> Result<Vec<u8>, String> {
+        let (_, nonce, ciphertext) = Crypto::split(bytes)?;
+        ChaCha20Poly1305::new(Key::from_slice(key))
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| DECRYPTION_FAILED.to_string())
+    }
+
+    /// Splits encrypted `bytes` into the salt, nonce and ciphertext.
+    fn split(bytes: &[u8]) -> Result<Parts<'_>, String> {
+        if !Crypto::is_encrypted(bytes) {
+            return Err("The data is not encrypted".to_string());
+        }
+        if bytes.len() < HEADER_LEN {
+            return Err(DECRYPTION_FAILED.to_string());
+        }
+
+        let (salt, rest) = bytes[ENCRYPTED_MAGIC.len()..].split_at(SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        Ok((salt, nonce, ciphertext))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_with_fixed_key() {
+        let key = [7u8; KEY_LEN];
+        let encrypted = Crypto::encrypt_with_key(&key, &[1u8; SALT_LEN], &[2u8; NONCE_LEN], b"buy milk").unwrap();
+
+        assert!(Crypto::is_encrypted(&encrypted));
+        assert!(!encrypted.windows(8).any(|window| window == b"buy milk"));
+        assert_eq!(Crypto::decrypt_with_key(&key, &encrypted).unwrap(), b"buy milk");
+        assert_eq!(
+            Crypto::decrypt_with_key(&[8u8; KEY_LEN], &encrypted),
+            Err(DECRYPTION_FAILED.to_string())
+        );
+    }
+
+    #[test]
+    fn round_trips_with_passphrase() {
+        let encrypted = Crypto::encrypt("correct horse", b"walk the dog").unwrap();
+
+        assert_eq!(Crypto::decrypt("correct horse", &encrypted).unwrap(), b"walk the dog");
+        assert_eq!(
+            Crypto::decrypt("battery staple", &encrypted),
+            Err(DECRYPTION_FAILED.to_string())
+        );
+        assert!(Crypto::decrypt("correct horse", b"plain").is_err());
+    }
+}
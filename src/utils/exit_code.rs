@@ -0,0 +1,114 @@
+use std::process::ExitCode;
+
+use crate::{input::input_error::InputError, todos::command_error::CommandError};
+
+/// The reasons the program can exit, each mapped to a distinct process exit code so that
+/// scripts can tell failures apart. Code `1` is shared by generic failures and by checks,
+/// such as `empty`, whose answer is no.
+#[derive(Eq, Hash, PartialEq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub enum ExitReason {
+    /// Everything went to plan.
+    Success,
+    /// A check command ran fine but its answer was no.
+    CheckFailed,
+    /// The command could not be parsed.
+    InvalidCommand,
+    /// The arguments given to the command were invalid.
+    InvalidArgument,
+    /// The todo or profile the command refers to does not exist.
+    NotFound,
+    /// The todo or profile the command would create already exists.
+    AlreadyExists,
+    /// The command could not be applied to the Todo-List.
+    ApplyFailed,
+    /// Reading or writing a file failed.
+    IoFailed,
+}
+
+impl ExitReason {
+    /// Returns the process exit code for this [ExitReason].
+    pub fn code(&self) -> u8 {
+        match self {
+            ExitReason::Success => 0,
+            ExitReason::CheckFailed => 1,
+            ExitReason::InvalidCommand => 2,
+            ExitReason::InvalidArgument => 3,
+            ExitReason::NotFound => 4,
+            ExitReason::AlreadyExists => 5,
+            ExitReason::ApplyFailed => 6,
+            ExitReason::IoFailed => 7,
+        }
+    }
+}
+
+impl From<&InputError> for ExitReason {
+    fn from(err: &InputError) -> Self {
+        match err {
+            InputError::InvalidCommand(_) => ExitReason::InvalidCommand,
+            InputError::InvalidArgument(_) => ExitReason::InvalidArgument,
+        }
+    }
+}
+
+impl From<&CommandError> for ExitReason {
+    fn from(err: &CommandError) -> Self {
+        match err {
+            CommandError::TodoNotFound | CommandError::ProfileNotFound(_) => ExitReason::NotFound,
+            CommandError::TodoAlreadyExists | CommandError::ProfileAlreadyExists(_) => {
+                ExitReason::AlreadyExists
+            }
+            CommandError::InputInvalid(_) => ExitReason::ApplyFailed,
+            CommandError::IoError(_) => ExitReason::IoFailed,
+        }
+    }
+}
+
+impl From<ExitReason> for ExitCode {
+    fn from(reason: ExitReason) -> Self {
+        ExitCode::from(reason.code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_errors() {
+        assert_eq!(ExitReason::from(&InputError::cmd_unknown("nope")), ExitReason::InvalidCommand);
+        assert_eq!(ExitReason::from(&InputError::bad_arg()), ExitReason::InvalidArgument);
+        assert_eq!(ExitReason::from(&CommandError::TodoNotFound), ExitReason::NotFound);
+        assert_eq!(
+            ExitReason::from(&CommandError::ProfileNotFound("work".to_string())),
+            ExitReason::NotFound
+        );
+        assert_eq!(ExitReason::from(&CommandError::TodoAlreadyExists), ExitReason::AlreadyExists);
+        assert_eq!(
+            ExitReason::from(&CommandError::InputInvalid("bad".to_string())),
+            ExitReason::ApplyFailed
+        );
+        assert_eq!(
+            ExitReason::from(&CommandError::IoError("disk".to_string())),
+            ExitReason::IoFailed
+        );
+    }
+
+    #[test]
+    fn codes_are_distinct() {
+        let reasons = [
+            ExitReason::Success,
+            ExitReason::CheckFailed,
+            ExitReason::InvalidCommand,
+            ExitReason::InvalidArgument,
+            ExitReason::NotFound,
+            ExitReason::AlreadyExists,
+            ExitReason::ApplyFailed,
+            ExitReason::IoFailed,
+        ];
+        let mut codes: Vec<u8> = reasons.iter().map(|r| r.code()).collect();
+        codes.dedup();
+
+        assert_eq!(codes.len(), reasons.len());
+        assert_eq!(ExitReason::Success.code(), 0);
+    }
+}
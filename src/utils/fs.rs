@@ -1,4 +1,8 @@
-use std::{io::Read, path::Path};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Lines, Read, Write},
+    path::{Path, PathBuf},
+};
 
 pub struct FileSystem;
 
@@ -16,26 +20,63 @@ impl FileSystem {
         std::fs::write(file, data)
     }
 
-    /// Loads all bytes from the file at the given path and returns them as a [`Vec<u8>`]
+    /// Saves the given bytes into a file at the given path without ever leaving it half
+    /// written. The bytes are written to a temp file next to the target, see
+    /// [`FileSystem::temp_path_for`], which is synced to disk and then renamed over the
+    /// target. If the write is interrupted the target keeps its previous contents.
+    ///
+    /// ### Arguments
+    /// * `file` - The path to the target file
+    /// * `data` - The data to save to the file
+    pub fn save_bytes_atomic<TFilePath: AsRef<Path>, TData: AsRef<[u8]>>(
+        file: TFilePath,
+        data: TData,
+    ) -> std::io::Result<()> {
+        let file = file.as_ref();
+        let temp = FileSystem::temp_path_for(file);
+
+        let written = File::create(&temp).and_then(|mut f| {
+            f.write_all(data.as_ref())?;
+            f.sync_all()
+        });
+        if let Err(err) = written.and_then(|_| std::fs::rename(&temp, file)) {
+            let _ = std::fs::remove_file(&temp);
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the path of the temp file [`FileSystem::save_bytes_atomic`] writes to before
+    /// it replaces `file`, a hidden `.tmp` file in the same directory so that the rename
+    /// never crosses filesystems.
+    ///
+    /// ### Arguments
+    /// * `file` - The path to the target file
+    pub fn temp_path_for<TFilePath: AsRef<Path>>(file: TFilePath) -> PathBuf {
+        let file = file.as_ref();
+        let name = file.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+        file.with_file_name(format!(".{}.tmp", name))
+    }
+
+    /// Loads all bytes from the file at the given path and returns them as a [`Vec<u8>`].
+    /// The file is opened read-only, so a missing file is a `NotFound` error.
     /// 
     /// ### Arguments
     /// * `file` - The path to the target file
     pub fn load_bytes<TFilePath: AsRef<Path>>(
         file: TFilePath,
     ) -> std::io::Result<Vec<u8>> {
-        let mut f = std::fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .read(true)
-            .open(file)?;
+        let mut f = File::open(file)?;
 
         let mut buf: Vec<u8> = Vec::new();
-        f.read_to_end(&mut buf);
-        
+        f.read_to_end(&mut buf)?;
+
         Ok(buf)
     }
 
     /// Loads all bytes from the file at the given path into the provided buffer and returns the number of bytes read.
+    /// The file is opened read-only, so a missing file is a `NotFound` error.
     /// 
     /// ### Arguments
     /// * `file` - The path to the target file
@@ -44,12 +85,84 @@ impl FileSystem {
         file: TFilePath,
         buffer: &mut Vec<u8>,
     ) -> std::io::Result<usize> {
-        let mut f = std::fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .read(true)
-            .open(file)?;
+        let mut f = File::open(file)?;
 
         f.read_to_end(buffer)
     }
+
+    /// Opens the file at the given path and returns an iterator over its lines, which are
+    /// read one at a time rather than loading the whole file.
+    ///
+    /// ### Arguments
+    /// * `file` - The path to the target file
+    pub fn read_lines<TFilePath: AsRef<Path>>(
+        file: TFilePath,
+    ) -> std::io::Result<Lines<BufReader<File>>> {
+        Ok(BufReader::new(File::open(file)?).lines())
+    }
+
+    /// Makes sure `dir` is a directory that files can be written to, creating it if it does
+    /// not exist yet. Writability is checked by creating and removing a hidden probe file,
+    /// so an unusable directory is reported up front instead of when saving.
+    ///
+    /// ### Arguments
+    /// * `dir` - The path to the directory
+    pub fn ensure_writable_dir<TDirPath: AsRef<Path>>(dir: TDirPath) -> std::io::Result<()> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        if !dir.is_dir() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("{:?} is not a directory", dir),
+            ));
+        }
+
+        let probe = dir.join(".write-probe.tmp");
+        File::create(&probe)?;
+        std::fs::remove_file(&probe)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::temp_dir;
+
+    #[test]
+    fn atomic_save_survives_interrupted_write() {
+        let dir = temp_dir("fs-atomic");
+        let file = dir.join("data.cbor");
+        FileSystem::save_bytes_atomic(&file, b"original").unwrap();
+
+        // A crash part way through a save leaves a truncated temp file behind.
+        std::fs::write(FileSystem::temp_path_for(&file), b"trunc").unwrap();
+        assert_eq!(std::fs::read(&file).unwrap(), b"original");
+
+        FileSystem::save_bytes_atomic(&file, b"updated").unwrap();
+        assert_eq!(std::fs::read(&file).unwrap(), b"updated");
+        assert!(!FileSystem::temp_path_for(&file).exists());
+    }
+
+    #[test]
+    fn writable_dir_is_created_and_files_are_refused() {
+        let dir = temp_dir("fs-writable").join("nested");
+        FileSystem::ensure_writable_dir(&dir).unwrap();
+        assert!(dir.is_dir());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+
+        let file = dir.join("data.cbor");
+        std::fs::write(&file, b"data").unwrap();
+        assert!(FileSystem::ensure_writable_dir(&file).is_err());
+    }
+
+    #[test]
+    fn loading_missing_file_errors_without_creating_it() {
+        let dir = temp_dir("fs-missing");
+        let file = dir.join("missing.cbor");
+
+        let err = FileSystem::load_bytes(&file).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        assert!(FileSystem::load_bytes_into(&file, &mut Vec::new()).is_err());
+        assert!(!file.exists());
+    }
 }
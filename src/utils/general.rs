@@ -14,3 +14,184 @@ pub fn string_to_bool<S: AsRef<str>>(s: S) -> Option<bool> {
         },
     }
 }
+
+/// Returns the Levenshtein distance between `a` and `b`, the number of single character
+/// insertions, deletions and substitutions needed to turn one into the other. Works on
+/// `char`s rather than bytes, so multi-byte characters count as one. Every fuzzy match in
+/// the crate should go through this.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Returns `bytes` as a human readable size such as `512 B` or `1.2 KB`, in powers of
+/// 1024 with one decimal place above a kilobyte.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KB", "MB", "GB", "TB", "PB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Renders `rows` as a table of aligned columns under `headers`, which are underlined with
+/// dashes. Each column is as wide as its widest cell, measured in `char`s, and rows with
+/// fewer cells than there are headers leave the remaining columns empty.
+pub fn render_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|header| header.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let render_row = |cells: Vec<&str>| {
+        let line = widths
+            .iter()
+            .enumerate()
+            .map(|(i, width)| {
+                let cell = cells.get(i).copied().unwrap_or_default();
+                format!("{}{}", cell, " ".repeat(width - cell.chars().count()))
+            })
+            .collect::<Vec<_>>()
+            .join("  ");
+        format!("{}\n", line.trim_end())
+    };
+
+    let underline: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+    let mut output = render_row(headers.to_vec());
+    output.push_str(&render_row(underline.iter().map(String::as_str).collect()));
+    for row in rows {
+        output.push_str(&render_row(row.iter().map(String::as_str).collect()));
+    }
+
+    output
+}
+
+/// Cuts `text` down to `width` characters, ending it with `…` when anything was cut off.
+/// A `width` of `0` leaves `text` as it is.
+pub fn truncate_to_width(text: &str, width: usize) -> String {
+    if width == 0 || text.chars().count() <= width {
+        return text.to_string();
+    }
+
+    let mut truncated: String = text.chars().take(width - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// Escapes backslashes, tabs, newlines and carriage returns in `field` so that it fits on
+/// one line of tab separated output. Reversed by [unescape_field].
+pub fn escape_field(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Reverses [escape_field]. A backslash that does not start a known escape is kept as is.
+pub fn unescape_field(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => unescaped.push('\\'),
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levenshtein_known_distances() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("flaw", "lawn"), 2);
+        assert_eq!(levenshtein("same", "same"), 0);
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("abc", ""), 3);
+        assert_eq!(levenshtein("café", "cafe"), 1);
+        assert_eq!(levenshtein("日本語", "日本"), 1);
+        assert_eq!(levenshtein("🦀🦀", "🦀"), 1);
+    }
+
+    #[test]
+    fn table_columns_line_up() {
+        let rows = vec![
+            vec![s("[ ]"), s("buy milk"), s("errand"), s("2021-11-01")],
+            vec![s("[X]"), s("write the quarterly report"), s(""), s("")],
+            vec![s("[ ]"), s("call"), s("phone, work")],
+        ];
+
+        assert_eq!(
+            render_table(&["Status", "Todo", "Tags", "Due"], &rows),
+            concat!(
+                "Status  Todo                        Tags         Due\n",
+                "------  --------------------------  -----------  ----------\n",
+                "[ ]     buy milk                    errand       2021-11-01\n",
+                "[X]     write the quarterly report\n",
+                "[ ]     call                        phone, work\n",
+            )
+        );
+    }
+
+    #[test]
+    fn truncation_keeps_to_the_width() {
+        assert_eq!(truncate_to_width("buy milk and eggs", 8), "buy mil…");
+        assert_eq!(truncate_to_width("buy milk", 8), "buy milk");
+        assert_eq!(truncate_to_width("日本語です", 3), "日本…");
+        assert_eq!(truncate_to_width("buy milk and eggs", 0), "buy milk and eggs");
+    }
+
+    #[test]
+    fn format_bytes_picks_unit() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1024), "1.0 KB");
+        assert_eq!(format_bytes(1229), "1.2 KB");
+        assert_eq!(format_bytes(5 * 1024 * 1024 + 512 * 1024), "5.5 MB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GB");
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    fs::{File, OpenOptions},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use fs2::FileExt;
+
+use crate::todos::command_error::CommandError;
+
+/// How long to wait between attempts to take a lock that is held by another instance.
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// An advisory, exclusive lock on a lock file, held until it is dropped. Every instance of
+/// the program takes the lock next to a database, see
+/// [`Profile::lock_path_for`](crate::todos::profile::Profile::lock_path_for), before it
+/// writes to it, so that two instances never clobber each other's changes.
+#[derive(Debug)]
+pub struct DatabaseLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl DatabaseLock {
+    /// Takes the lock on the lock file at `path`, creating it if needed. If another instance
+    /// holds the lock this waits up to `timeout` for it to be released, and then fails with
+    /// a "database is locked" error. A zero `timeout` fails straight away.
+    ///
+    /// ### Arguments
+    /// * `path` - The path to the lock file
+    /// * `timeout` - How long to wait for another instance to release the lock
+    pub fn acquire<TFilePath: AsRef<Path>>(path: TFilePath, timeout: Duration) -> Result<DatabaseLock, CommandError> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().write(true).create(true).truncate(false).open(&path)?;
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            match file.try_lock_exclusive() {
+                Ok(()) => return Ok(DatabaseLock { file, path }),
+                Err(err) if err.kind() != fs2::lock_contended_error().kind() => return Err(err.into()),
+                Err(_) if Instant::now() >= deadline => {
+                    return Err(CommandError::IoError(format!(
+                        "database is locked by another instance, remove {:?} if no other instance is running",
+                        path
+                    )))
+                }
+                Err(_) => std::thread::sleep(LOCK_RETRY_INTERVAL),
+            }
+        }
+    }
+
+    /// Returns the path of the lock file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for DatabaseLock {
+    fn drop(&mut self) {
+        // The lock is released when the file is closed anyway, and `drop` has no way to
+        // report the error, so a failed unlock is ignored.
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::temp_dir;
+
+    #[test]
+    fn second_instance_fails_while_locked() {
+        let path = temp_dir("lock").join("data.lock");
+
+        let lock = DatabaseLock::acquire(&path, Duration::ZERO).unwrap();
+        assert_eq!(lock.path(), path);
+        assert!(matches!(
+            DatabaseLock::acquire(&path, Duration::from_millis(100)),
+            Err(CommandError::IoError(msg)) if msg.starts_with("database is locked")
+        ));
+
+        drop(lock);
+        assert!(DatabaseLock::acquire(&path, Duration::ZERO).is_ok());
+    }
+}
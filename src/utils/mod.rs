@@ -1,4 +1,10 @@
 pub mod cereal;
+pub mod color;
+pub mod crypto;
+pub mod exit_code;
 pub mod fs;
 pub mod general;
+pub mod lock;
+#[cfg(test)]
+pub mod testing;
 pub mod timed;
\ No newline at end of file
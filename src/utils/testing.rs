@@ -0,0 +1,9 @@
+use std::path::PathBuf;
+
+/// Creates a new, empty directory under the system temp directory for a test to
+/// work in. `label` is included in the directory name to make debugging easier.
+pub fn temp_dir(label: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("todo-{}-{}", label, uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).expect("Unable to create temp dir for test");
+    dir
+}
@@ -4,11 +4,48 @@ use std::{
     time::{Duration, Instant},
 };
 
+/// Expiration bookkeeping shared by [TimedValue] and [TimedRef] so that both
+/// agree on what it means to be expired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Expiration {
+    at: Instant,
+}
+
+impl Expiration {
+    /// Creates a new [Expiration] that expires at the given time.
+    pub fn new(at: Instant) -> Self {
+        Self { at }
+    }
+
+    /// Returns the time at which this [Expiration] expires.
+    pub fn at(&self) -> Instant {
+        self.at
+    }
+
+    pub fn set(&mut self, at: Instant) {
+        self.at = at;
+    }
+
+    pub fn extend(&mut self, duration: Duration) {
+        self.at += duration;
+    }
+
+    /// Returns `true` if this [Expiration] has not yet expired.
+    pub fn valid(&self) -> bool {
+        !self.expired()
+    }
+
+    /// Returns `true` if this [Expiration] has expired.
+    pub fn expired(&self) -> bool {
+        self.at <= Instant::now()
+    }
+}
+
 /// An **owning** timed value.
 /// `TValue` - The type of value stored by this [TimedValue]
 pub struct TimedValue<TValue> {
     value: RefCell<TValue>,
-    expiration: Instant,
+    expiration: Expiration,
 }
 
 impl<TValue> TimedValue<TValue> {
@@ -18,12 +55,12 @@ impl<TValue> TimedValue<TValue> {
     pub fn new(value: TValue, expiriation: Instant) -> Self {
         Self {
             value: RefCell::new(value),
-            expiration: expiriation,
+            expiration: Expiration::new(expiriation),
         }
     }
 
     /// Returns the value stored in this [TimedValue].
-    pub fn value(&self) -> Option<Ref<TValue>> {
+    pub fn value(&self) -> Option<Ref<'_, TValue>> {
         if self.expired() {
             None
         } else {
@@ -32,7 +69,7 @@ impl<TValue> TimedValue<TValue> {
     }
 
     /// Returns the value stored in this [TimedValue] as mutable.
-    pub fn value_mut(&mut self) -> Option<RefMut<TValue>> {
+    pub fn value_mut(&mut self) -> Option<RefMut<'_, TValue>> {
         if self.expired() {
             None
         } else {
@@ -44,26 +81,83 @@ impl<TValue> TimedValue<TValue> {
         self.value = RefCell::new(value);
     }
 
+    /// Replaces the value stored in this [TimedValue], returning the old value. Unlike
+    /// [TimedValue::value] this works whether or not the value has expired.
+    pub fn replace(&mut self, value: TValue) -> TValue {
+        std::mem::replace(&mut self.value, RefCell::new(value)).into_inner()
+    }
+
     pub fn expiration(&self) -> Instant {
-        self.expiration
+        self.expiration.at()
     }
 
     pub fn set_expiration(&mut self, expiration: Instant) {
-        self.expiration = expiration;
+        self.expiration.set(expiration);
     }
 
     pub fn extend_expiration(&mut self, duration: Duration) {
-        self.expiration = self.expiration + duration;
+        self.expiration.extend(duration);
     }
 
     /// Returns `true` if this [TimedValue] has not yet expired.
     pub fn valid(&self) -> bool {
-        !self.expired()
+        self.expiration.valid()
     }
 
     /// Returns `true` if this [TimedValue] has expired.
     pub fn expired(&self) -> bool {
-        self.expiration <= Instant::now()
+        self.expiration.expired()
+    }
+}
+
+/// A **borrowing** timed value, for attaching an expiration to a value owned elsewhere.
+/// `'a` - The lifetime of the borrowed value
+/// `TValue` - The type of value referenced by this [TimedRef]
+pub struct TimedRef<'a, TValue> {
+    value: &'a TValue,
+    expiration: Expiration,
+}
+
+impl<'a, TValue> TimedRef<'a, TValue> {
+    /// Creates a new [TimedRef] with the given value and expiriation time.
+    /// `value` - The value to reference
+    /// `expiriation` - The time at which this reference will expire
+    pub fn new(value: &'a TValue, expiriation: Instant) -> Self {
+        Self {
+            value,
+            expiration: Expiration::new(expiriation),
+        }
+    }
+
+    /// Returns the value referenced by this [TimedRef].
+    pub fn value(&self) -> Option<&'a TValue> {
+        if self.expired() {
+            None
+        } else {
+            Some(self.value)
+        }
+    }
+
+    pub fn expiration(&self) -> Instant {
+        self.expiration.at()
+    }
+
+    pub fn set_expiration(&mut self, expiration: Instant) {
+        self.expiration.set(expiration);
+    }
+
+    pub fn extend_expiration(&mut self, duration: Duration) {
+        self.expiration.extend(duration);
+    }
+
+    /// Returns `true` if this [TimedRef] has not yet expired.
+    pub fn valid(&self) -> bool {
+        self.expiration.valid()
+    }
+
+    /// Returns `true` if this [TimedRef] has expired.
+    pub fn expired(&self) -> bool {
+        self.expiration.expired()
     }
 }
 
@@ -88,4 +182,16 @@ mod tests {
         timed_ref.value_mut().unwrap().number = 2;
         assert_eq!(tester.number, 2);
     }
+
+    #[test]
+    fn timed_ref_expires() {
+        let tester = Tester { number: 3 };
+
+        let mut timed_ref = TimedRef::new(&tester, Instant::now() + Duration::from_secs(10000));
+        assert_eq!(timed_ref.value().map(|t| t.number), Some(3));
+
+        timed_ref.set_expiration(Instant::now());
+        assert!(timed_ref.expired());
+        assert!(timed_ref.value().is_none());
+    }
 }
@@ -0,0 +1,51 @@
+#![cfg(unix)]
+
+use std::{path::PathBuf, thread};
+
+use todo::{
+    service::{
+        protocol::{ServiceRequest, ServiceResponse},
+        unix_service::{ServiceClient, UnixService},
+    },
+    state::actions::action_payload::ActionPayload,
+    todos::{
+        cached_todolist::{CachedTodoList, DEFAULT_CACHE_TTL},
+        profile::Profile,
+        todolist::TodoList,
+    },
+};
+
+fn temp_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("todo-daemon-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn daemon_round_trips_an_add() {
+    let dir = temp_dir();
+    TodoList::new().save_to_profile(&dir, "work").unwrap();
+    let socket = Profile::socket_path_for(&dir, "work");
+    let service = UnixService::bind(&socket).unwrap();
+
+    let server_dir = dir.clone();
+    let server = thread::spawn(move || {
+        let mut list = CachedTodoList::load(&server_dir, "work", DEFAULT_CACHE_TTL).unwrap();
+        service.serve(&mut list).unwrap();
+    });
+
+    let add = ServiceRequest::Apply {
+        action: ActionPayload::Add("buy milk".to_string()),
+        assume_yes: false,
+        colorize: false,
+    };
+    assert!(matches!(ServiceClient::send(&socket, &add).unwrap(), ServiceResponse::Applied(_)));
+    assert_eq!(
+        ServiceClient::send(&socket, &ServiceRequest::Shutdown).unwrap(),
+        ServiceResponse::ShuttingDown
+    );
+    server.join().unwrap();
+
+    assert_eq!(TodoList::load_from_profile(&dir, "work").unwrap().get_todos_text(), vec!["buy milk"]);
+    assert!(!socket.exists());
+}
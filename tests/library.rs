@@ -0,0 +1,29 @@
+use todo::{
+    state::actions::action_payload::ActionPayload,
+    todos::todolist::{DiffResult, TodoList},
+    utils::cereal::{Cereal, EncodingType},
+};
+
+#[test]
+fn apply_action_through_library() {
+    let mut list = TodoList::new();
+
+    list.apply_action(ActionPayload::Add("buy milk".to_string())).unwrap();
+    list.apply_action(ActionPayload::Set("buy milk".to_string(), true)).unwrap();
+
+    assert_eq!(list.get_todos_with_status(true), vec!["buy milk"]);
+}
+
+#[test]
+fn cereal_round_trip_through_library() {
+    let mut list = TodoList::new();
+    list.add_todo("buy milk", false).unwrap();
+    list.add_todo("buy eggs", true).unwrap();
+
+    for ty in EncodingType::all() {
+        let bytes = Cereal::serialize_with(ty, &list).unwrap();
+        let recreated: TodoList = Cereal::deserialize_with(ty, &bytes).unwrap();
+
+        assert_eq!(list.diff_with(&recreated), DiffResult::Same, "{}", ty);
+    }
+}